@@ -90,3 +90,20 @@ mod test_protoc_extra_args {
         Ok(())
     }
 }
+
+mod test_list_languages {
+    use crate::util;
+    use anyhow::Result;
+    use generator::Lang;
+
+    #[test]
+    fn prints_supported_languages() -> Result<()> {
+        let output = util::protox().arg("--list-languages").output()?;
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout)?;
+        let languages = stdout.lines().collect::<Vec<&str>>();
+        assert!(languages.contains(&Lang::Cpp.as_config().as_str()));
+        assert!(languages.contains(&Lang::Rust.as_config().as_str()));
+        Ok(())
+    }
+}
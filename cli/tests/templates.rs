@@ -41,3 +41,60 @@ fn smoketest_templates() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn stdout_single_file() -> Result<()> {
+    let test_dir = tempdir_in(env!("CARGO_TARGET_TMPDIR"))?;
+    let output = test_dir.path().join("output");
+    let mut cmd = util::protox();
+    cmd.arg("--input")
+        .arg(util::path_to_str(resources_dir())?)
+        .arg("--output-root")
+        .arg(util::path_to_str(test_dir.path())?)
+        .arg("--stdout")
+        .arg("--template")
+        .arg(util::path_to_str(resources_dir().join("template-a"))?)
+        .arg(util::path_to_str(&output)?);
+
+    let result = cmd.output()?;
+    assert!(result.status.success());
+    let stdout = String::from_utf8(result.stdout)?;
+    assert!(stdout.contains("DO NOT EDIT MANUALLY"));
+    assert!(!output.join("example.test").exists());
+    Ok(())
+}
+
+#[test]
+fn diff_against_existing_output() -> Result<()> {
+    let test_dir = tempdir_in(env!("CARGO_TARGET_TMPDIR"))?;
+    let output = test_dir.path().join("output");
+    let template_args = vec![
+        "--template".to_owned(),
+        util::path_to_str(resources_dir().join("template-a"))?,
+        util::path_to_str(&output)?,
+    ];
+    util::test_with_args_in(test_dir.path(), &template_args)?;
+
+    let diff_cmd = || -> Result<std::process::Command> {
+        let mut cmd = util::protox();
+        cmd.arg("--input")
+            .arg(util::path_to_str(resources_dir())?)
+            .arg("--diff")
+            .args(&template_args);
+        Ok(cmd)
+    };
+
+    let result = diff_cmd()?.output()?;
+    assert!(result.status.success());
+    assert!(result.stdout.is_empty());
+
+    let generated = output.join("example.test");
+    fs::write(&generated, "changed\n")?;
+    let result = diff_cmd()?.output()?;
+    assert!(!result.status.success());
+    let stdout = String::from_utf8(result.stdout)?;
+    assert!(stdout.contains("-changed"));
+    assert!(stdout.contains("+++ b/example.test"));
+    assert_eq!(fs::read_to_string(&generated)?, "changed\n");
+    Ok(())
+}
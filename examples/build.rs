@@ -25,7 +25,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // Configure protox directly.
     let mut config = generator::Config::default();
-    config.input = input_dir.join("proto");
+    config.input = vec![input_dir.join("proto")];
     config.includes = vec![protox_includes_dir.to_str().unwrap().to_owned()];
     config.descriptor_set_path = output_dir.join("descriptor_set");
 
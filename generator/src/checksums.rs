@@ -0,0 +1,251 @@
+use crate::util::DisplayNormalized;
+use crate::{util, Config};
+use anyhow::{Context, Result};
+use log::info;
+use prost_types::FileDescriptorSet;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Writes the SHA-256 of every generated file to `config.checksums`, if set.
+pub fn generate(config: &Config) -> Result<()> {
+    let path = match &config.checksums {
+        None => return Ok(()),
+        Some(path) => path,
+    };
+    write_checksums(&output_dirs(config), path)?;
+    info!("Wrote checksums to '{}'", path.display_normalized());
+    Ok(())
+}
+
+fn output_dirs(config: &Config) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    dirs.extend(config.protos.iter().map(|c| c.output.clone()));
+    dirs.extend(config.templates.iter().map(|c| c.output.clone()));
+    dirs.extend(config.inline_templates.iter().map(|c| c.output.clone()));
+    dirs.extend(config.scripts.iter().map(|c| c.output.clone()));
+    dirs.extend(config.encode.iter().map(|c| c.output.clone()));
+    dirs.extend(config.descriptor_sets.iter().map(|c| c.output.clone()));
+    dirs.extend(config.plugin_outs.iter().map(|(_, out)| out.clone()));
+    dirs
+}
+
+/// Writes one line per file found under `dirs` in the format used by `sha256sum`, so the result
+/// can be checked with `sha256sum -c`. Paths are relative to the directory containing `path` when
+/// possible, and absolute otherwise.
+///
+/// Digests cover each file's exact bytes, headers included. protox itself never writes a timestamp
+/// into generated files, so nothing is excluded, and a timestamp in a user-configured header is
+/// hashed as is.
+fn write_checksums(dirs: &[PathBuf], path: &Path) -> Result<()> {
+    let base = util::path_parent_or_error(path)?;
+    let mut files = collect_files(dirs)?;
+    files.retain(|file| file != path);
+    let mut contents = String::new();
+    for file in &files {
+        let bytes = fs::read(file).with_context(|| {
+            format!(
+                "Failed to read generated file at path '{}'",
+                file.display_normalized()
+            )
+        })?;
+        let relative = file.strip_prefix(base).unwrap_or(file);
+        contents.push_str(&format!(
+            "{}  {}\n",
+            sha256_hex(&bytes),
+            relative.display_normalized()
+        ));
+    }
+    fs::write(path, contents).with_context(|| {
+        format!(
+            "Failed to write checksums to path '{}'",
+            path.display_normalized()
+        )
+    })
+}
+
+fn collect_files(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for dir in dirs {
+        // e.g. with --stdout nothing is written to the output dir.
+        if !dir.exists() {
+            continue;
+        }
+        for entry in WalkDir::new(dir) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                files.push(entry.into_path());
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// SHA-256 of the source of every file in the descriptor set which can be found in one of the
+/// `--input` roots, keyed by its name in the descriptor set. Files without a source, e.g. with a
+/// --buf-image input, are left out.
+pub(crate) fn source_file_hashes(
+    config: &Config,
+    descriptor_set: &FileDescriptorSet,
+) -> Result<HashMap<String, String>> {
+    let mut hashes = HashMap::new();
+    for name in descriptor_set
+        .file
+        .iter()
+        .filter_map(|file| file.name.as_ref())
+    {
+        let path = match config
+            .input
+            .iter()
+            .map(|input| input.join(name))
+            .find(|path| path.is_file())
+        {
+            None => continue,
+            Some(path) => path,
+        };
+        let bytes = fs::read(&path).with_context(|| {
+            format!(
+                "Failed to read proto file at path '{}'",
+                path.display_normalized()
+            )
+        })?;
+        hashes.insert(name.clone(), sha256_hex(&bytes));
+    }
+    Ok(hashes)
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use prost_types::{FileDescriptorProto, FileDescriptorSet};
+    use tempfile::tempdir;
+
+    use crate::checksums::{output_dirs, sha256_hex, source_file_hashes, write_checksums};
+    use crate::in_out_config::InOutConfig;
+    use crate::Config;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn digest_of_known_input() {
+        assert_eq!(sha256_hex(b"abc"), ABC_SHA256);
+    }
+
+    #[test]
+    fn lists_digest_of_each_file_relative_to_checksum_file() -> Result<()> {
+        let root = tempdir()?;
+        let output = root.path().join("output");
+        fs::create_dir_all(output.join("sub"))?;
+        fs::write(output.join("sub").join("file.txt"), "abc")?;
+        fs::write(output.join("empty.txt"), "")?;
+        let path = root.path().join("checksums.txt");
+
+        write_checksums(&[output], &path)?;
+
+        let checksums = fs::read_to_string(&path)?;
+        assert_eq!(
+            checksums,
+            format!(
+                "{}  output/empty.txt\n{}  output/sub/file.txt\n",
+                sha256_hex(b""),
+                ABC_SHA256
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn skips_missing_dirs_and_checksum_file() -> Result<()> {
+        let root = tempdir()?;
+        fs::write(root.path().join("file.txt"), "abc")?;
+        let path = root.path().join("checksums.txt");
+
+        write_checksums(
+            &[root.path().to_path_buf(), root.path().join("missing")],
+            &path,
+        )?;
+
+        let checksums = fs::read_to_string(&path)?;
+        assert_eq!(checksums, format!("{}  file.txt\n", ABC_SHA256));
+        Ok(())
+    }
+
+    #[test]
+    fn includes_descriptor_set_outputs() -> Result<()> {
+        let root = tempdir()?;
+        let output = root.path().join("descriptors");
+        fs::create_dir_all(&output)?;
+        fs::write(output.join("set.pb"), "abc")?;
+        let mut config = Config::default();
+        config.descriptor_sets = vec![InOutConfig {
+            input: root.path().join("templates"),
+            output: output.clone(),
+            overlays: vec![],
+        }];
+        let path = root.path().join("checksums.txt");
+
+        write_checksums(&output_dirs(&config), &path)?;
+
+        let checksums = fs::read_to_string(&path)?;
+        assert_eq!(checksums, format!("{}  descriptors/set.pb\n", ABC_SHA256));
+        Ok(())
+    }
+
+    #[test]
+    fn includes_plugin_outputs() -> Result<()> {
+        let root = tempdir()?;
+        let output = root.path().join("plugin");
+        fs::create_dir_all(&output)?;
+        fs::write(output.join("generated.txt"), "abc")?;
+        let mut config = Config::default();
+        config.plugin_outs = vec![("custom".to_owned(), output.clone())];
+        let path = root.path().join("checksums.txt");
+
+        write_checksums(&output_dirs(&config), &path)?;
+
+        let checksums = fs::read_to_string(&path)?;
+        assert_eq!(checksums, format!("{}  plugin/generated.txt\n", ABC_SHA256));
+        Ok(())
+    }
+
+    #[test]
+    fn hashes_source_files_found_in_inputs() -> Result<()> {
+        let root = tempdir()?;
+        let input0 = root.path().join("input0");
+        let input1 = root.path().join("input1");
+        fs::create_dir_all(input1.join("sub"))?;
+        fs::write(input1.join("sub/file.proto"), "abc")?;
+        let mut config = Config::default();
+        config.input = vec![input0, input1];
+        let descriptor_set = FileDescriptorSet {
+            file: vec![
+                FileDescriptorProto {
+                    name: Some("sub/file.proto".to_owned()),
+                    ..Default::default()
+                },
+                FileDescriptorProto {
+                    name: Some("missing.proto".to_owned()),
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let hashes = source_file_hashes(&config, &descriptor_set)?;
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes["sub/file.proto"], ABC_SHA256);
+        Ok(())
+    }
+}
@@ -1,15 +1,22 @@
+use crate::config_format::ConfigFormat;
 use crate::encode_config::EncodeConfig;
 use crate::idl::Idl;
 use crate::in_out_config::InOutConfig;
+use crate::inline_template_config::InlineTemplateConfig;
 use crate::lang::Lang;
 use crate::lang_config::LangConfig;
 use crate::protoc;
 use crate::script_config::ScriptConfig;
+use crate::util;
 use anyhow::{anyhow, Context, Result};
 use clap::{crate_version, App, Arg, ArgMatches, Values};
+use std::collections::HashMap;
 use std::env;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 use tempfile::{tempdir, TempDir};
 
 pub const APP_NAME: &str = "protox";
@@ -21,6 +28,8 @@ pub const SCRIPT_IN: &str = "script-in";
 pub const SCRIPT_OUT: &str = "script-out";
 pub const SCRIPT_OVERLAY: &str = "script-overlay";
 pub const TEMPLATE: &str = "template";
+pub const TEMPLATE_INLINE: &str = "template-inline";
+pub const DESCRIPTOR_SETS: &str = "descriptor-sets";
 pub const ENCODE: &str = "encode";
 pub const BYPASS: &str = "bypass";
 pub const TEMPLATE_ROOT: &str = "template-root";
@@ -31,16 +40,43 @@ pub const INIT_SCRIPT: &str = "init-script";
 pub const INIT_TEMPLATE: &str = "init-template";
 pub const DESCRIPTOR_SET_OUT: &str = "descriptor-set-out";
 pub const PROTOC_ARGS: &str = "protoc-args";
+pub const LIST_LANGUAGES: &str = "list-languages";
+pub const PROGRESS: &str = "progress";
+pub const STDOUT: &str = "stdout";
+pub const DIFF: &str = "diff";
+pub const ALLOW_OVERWRITE: &str = "allow-overwrite";
+pub const APPEND: &str = "append";
+pub const SINCE: &str = "since";
+pub const LINK_PROTO_OUTPUT: &str = "link-proto-output";
+pub const FAIL_ON_DEPRECATED: &str = "fail-on-deprecated";
+pub const ERROR_ON_EMPTY: &str = "error-on-empty";
+pub const INCLUDE_IMPORTS: &str = "include-imports";
+pub const LINT: &str = "lint";
+pub const OVERLAY_TARGET: &str = "overlay-target";
+pub const LANG_INCLUDE: &str = "lang-include";
+pub const PLUGIN: &str = "plugin";
+pub const PLUGIN_OUT: &str = "plugin-out";
+pub const JOBS: &str = "jobs";
+pub const CONFIG_FORMAT: &str = "config-format";
+pub const CHECKSUMS: &str = "checksums";
+pub const BUF_IMAGE: &str = "buf-image";
+pub const PROTOC_TIMEOUT: &str = "protoc-timeout";
+pub const EDITIONS: &str = "editions";
 pub const LONG_HELP_NEWLINE: &str = "\n\n";
 
-const MAIN_OPTS: &[&str; 7] = &[
+const MAIN_OPTS: &[&str; 12] = &[
     PROTO,
+    PLUGIN_OUT,
     TEMPLATE,
+    TEMPLATE_INLINE,
+    DESCRIPTOR_SETS,
     SCRIPT,
     BYPASS,
     ENCODE,
+    LINT,
     INIT_SCRIPT,
     INIT_TEMPLATE,
+    LIST_LANGUAGES,
 ];
 
 const DISPLAY_ORDER_DEFAULT: usize = 990;
@@ -70,12 +106,13 @@ where
 
             Arg::new(INPUT)
                 .display_order(1)
-                .help("File path to search for protobuf IDL files.")
+                .help("File path to search for protobuf IDL files. Can be specified multiple times to search several roots.")
                 .default_short()
                 .long(INPUT)
                 .takes_value(true)
-                .required(true)
-                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
+                .multiple_occurrences(true)
+                .required_unless_present(BUF_IMAGE)
+                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE, LIST_LANGUAGES]),
 
             Arg::new(SCRIPT)
                 .display_order(display_order())
@@ -135,6 +172,7 @@ where
                 .long_help(join_help(&[
                     "Protobuf code will be generated for language LANG to directory located at OUTPUT.",
                     &format!("If OUTPUT is a relative path, it is evaluated relative to --{}.", OUTPUT_ROOT),
+                    "Any `{lang}` in OUTPUT is replaced with LANG, e.g. `generated/{lang}` puts each language in its own directory.",
                     &format!("Supported languages for LANG: {}.", lang_list(&protoc::supported_languages())),
                 ]).as_str())
                 .default_short()
@@ -148,6 +186,7 @@ where
                 .display_order(display_order())
                 .long_help(join_help(&[
                     "Code will be generated for the templates and configuration found inside the INPUT folder, and written to directory located at OUTPUT.",
+                    &format!("OUTPUT may be omitted, in which case output is written directly to --{}.", OUTPUT_ROOT),
                     "Templates use the mustache template language (https://mustache.github.io/).",
                     &format!("If INPUT is a relative path, it is evaluated relative to --{}.", TEMPLATE_ROOT),
                     &format!("If OUTPUT is a relative path, it is evaluated relative to --{}.", OUTPUT_ROOT),
@@ -155,11 +194,44 @@ where
                 ]).as_str())
                 .default_short()
                 .long(TEMPLATE)
-                .value_names(&["INPUT", "OUTPUT"])
+                .value_name("INPUT [OUTPUT]")
+                .min_values(1)
+                .max_values(2)
                 .multiple_occurrences(true)
                 .required_unless_present_any(all_except(MAIN_OPTS, TEMPLATE))
                 .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
 
+            Arg::new(TEMPLATE_INLINE)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    "Code will be generated for each proto file from the TEMPLATE string, used as the file template with a default configuration, and written to the directory located at OUTPUT.",
+                    "Generated files have the `txt` extension. For anything more, use a template folder.",
+                    &format!("If OUTPUT is a relative path, it is evaluated relative to --{}.", OUTPUT_ROOT),
+                    &format!("e.g. --{} '{{{{source_file}}}}' path/to/output/folder", TEMPLATE_INLINE),
+                ]).as_str())
+                .long(TEMPLATE_INLINE)
+                .value_names(&["TEMPLATE", "OUTPUT"])
+                .multiple_occurrences(true)
+                .required_unless_present_any(all_except(MAIN_OPTS, TEMPLATE_INLINE))
+                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
+
+            Arg::new(DESCRIPTOR_SETS)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    "Binary FileDescriptorSets will be written for the configuration found inside the INPUT folder to the directory located at OUTPUT, one for each proto file, one for each package with `one_file_per_package`, or a single set with `single_file`.",
+                    "The INPUT folder only needs the config file. Its `file_extension` is the extension of the written sets, e.g. `pb`.",
+                    &format!("OUTPUT may be omitted, in which case output is written directly to --{}.", OUTPUT_ROOT),
+                    &format!("If INPUT is a relative path, it is evaluated relative to --{}.", TEMPLATE_ROOT),
+                    &format!("If OUTPUT is a relative path, it is evaluated relative to --{}.", OUTPUT_ROOT),
+                ]).as_str())
+                .long(DESCRIPTOR_SETS)
+                .value_name("INPUT [OUTPUT]")
+                .min_values(1)
+                .max_values(2)
+                .multiple_occurrences(true)
+                .required_unless_present_any(all_except(MAIN_OPTS, DESCRIPTOR_SETS))
+                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
+
             Arg::new(ENCODE)
                 .display_order(display_order())
                 .long_help(join_help(&[
@@ -174,6 +246,16 @@ where
                 .required_unless_present_any(all_except(MAIN_OPTS, ENCODE))
                 .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
 
+            Arg::new(LINT)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    "Check that the names of messages, fields, enums and enum values follow naming conventions, and fail listing every violation if not.",
+                    "The rules are read from the `lint` section of each --template and --script config, and default to UpperCamelCase messages and enums, lower_snake_case fields, and UPPER_SNAKE_CASE enum values.",
+                ]).as_str())
+                .long(LINT)
+                .required_unless_present_any(all_except(MAIN_OPTS, LINT))
+                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
+
             Arg::new(BYPASS)
                 .display_order(display_order())
                 .long_help("Bypass protox additional functionality and run protoc directly.")
@@ -186,7 +268,7 @@ where
 
             Arg::new(TEMPLATE_ROOT)
                 .display_order(display_order())
-                .help(format!("All non-absolute --{} INPUT paths will be prefixed with this path. Required if any --{} INPUT paths are relative.", TEMPLATE, TEMPLATE).as_str())
+                .help(format!("All non-absolute --{} and --{} INPUT paths will be prefixed with this path. Required if any of their INPUT paths are relative.", TEMPLATE, DESCRIPTOR_SETS).as_str())
                 .long(TEMPLATE_ROOT)
                 .takes_value(true),
 
@@ -197,6 +279,17 @@ where
                 .long(OUTPUT_ROOT)
                 .takes_value(true),
 
+            Arg::new(OVERLAY_TARGET)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    "Use the overlay values configured for target NAME for every proto element, instead of the target derived from each element's fully-qualified name.",
+                    "May be specified multiple times, in which case the targets are merged in order, with later targets taking precedence.",
+                ]).as_str())
+                .long(OVERLAY_TARGET)
+                .value_name("NAME")
+                .takes_value(true)
+                .multiple_occurrences(true),
+
             Arg::new(INCLUDES)
                 .display_order(display_order())
                 .help("Additional include folders passed directly to protoc as --proto_path options.")
@@ -204,6 +297,40 @@ where
                 .takes_value(true)
                 .multiple_values(true),
 
+            Arg::new(LANG_INCLUDE)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    &format!("Additional include folder passed to protoc as a --proto_path option only when generating code for --{} LANG.", PROTO),
+                    "Languages with their own includes are generated in a separate protoc pass. Only applies to languages generated by protoc itself.",
+                ]).as_str())
+                .long(LANG_INCLUDE)
+                .value_names(&["LANG", "PATH"])
+                .multiple_occurrences(true),
+
+            Arg::new(PLUGIN)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    "Register the protoc plugin executable at PATH under NAME, forwarded to protoc as --plugin=protoc-gen-NAME=PATH.",
+                    &format!("Only needed for plugins which are not on the PATH as protoc-gen-NAME. Use with --{} NAME OUTPUT.", PLUGIN_OUT),
+                ]).as_str())
+                .long(PLUGIN)
+                .value_name("NAME=PATH")
+                .takes_value(true)
+                .multiple_occurrences(true),
+
+            Arg::new(PLUGIN_OUT)
+                .display_order(display_order())
+                .long_help(join_help(&[
+                    "Code will be generated by the protoc plugin NAME to the directory located at OUTPUT, forwarded to protoc as --NAME_out=OUTPUT.",
+                    &format!("The plugin runs in the same protoc invocation as --{}, so third-party plugins can be used alongside protox's own rendering.", PROTO),
+                    &format!("If OUTPUT is a relative path, it is evaluated relative to --{}.", OUTPUT_ROOT),
+                ]).as_str())
+                .long(PLUGIN_OUT)
+                .value_names(&["NAME", "OUTPUT"])
+                .multiple_occurrences(true)
+                .required_unless_present_any(all_except(MAIN_OPTS, PLUGIN_OUT))
+                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
+
             Arg::new(INIT_SCRIPT)
                 .display_order(display_order())
                 .help(format!("Initialize the TARGET directory as a new scripted rendering target with the basic input files required for running protox with --{}.", SCRIPT).as_str())
@@ -220,6 +347,135 @@ where
                 .value_name("TARGET")
                 .conflicts_with(INIT_SCRIPT),
 
+            Arg::new(LIST_LANGUAGES)
+                .display_order(display_order())
+                .help(format!("Print the languages supported by --{} LANG and exit.", PROTO).as_str())
+                .long(LIST_LANGUAGES)
+                .conflicts_with_all(&[INIT_SCRIPT, INIT_TEMPLATE]),
+
+            Arg::new(PROGRESS)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .help("Report 'rendering file N of M' to stderr while rendering templates and scripts.")
+                .long(PROGRESS),
+
+            Arg::new(STDOUT)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    &format!("Write the rendered file to stdout instead of the output directory. Requires exactly one --{}, --{}, --{} or --{} and no other outputs, so no --{}, --{} or --{}. Errors if more than one file would be rendered.", TEMPLATE, TEMPLATE_INLINE, SCRIPT, DESCRIPTOR_SETS, PROTO, PLUGIN_OUT, ENCODE),
+                    "To leave out the generated header, set `generated_header: []` in the renderer config.",
+                ]).as_str())
+                .long(STDOUT),
+
+            Arg::new(DIFF)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    &format!("Instead of writing the output of each --{}, --{}, --{} and --{}, compare it to the files already in the output directory and print a unified diff of any differences.", TEMPLATE, TEMPLATE_INLINE, SCRIPT, DESCRIPTOR_SETS),
+                    &format!("Nothing else is written: --{}, --{}, --{} and --{} are skipped.", PROTO, PLUGIN_OUT, ENCODE, CHECKSUMS),
+                    "Exits with an error if anything differs, which makes it useful to check generated code is up to date in CI. Files in the output directory that would not be generated count as differences.",
+                ]).as_str())
+                .long(DIFF)
+                .conflicts_with(STDOUT),
+
+            Arg::new(JOBS)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Maximum number of files each --template, --template-inline, --script and --descriptor-sets renders in parallel.",
+                    "Defaults to the number of CPUs. A value of 1 renders one file at a time. Errors are reported in file order for any value.",
+                ]).as_str())
+                .long(JOBS)
+                .value_name("N")
+                .takes_value(true),
+
+            Arg::new(CONFIG_FORMAT)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    &format!("Parse the config file of every --{}, --{} and --{} as FORMAT, whatever its extension.", TEMPLATE, SCRIPT, DESCRIPTOR_SETS),
+                    "By default config files are parsed as YAML, which also reads JSON, so an extensionless `config` file works either way. With `json`, configs must be strict JSON.",
+                ]).as_str())
+                .long(CONFIG_FORMAT)
+                .value_name("FORMAT")
+                .takes_value(true)
+                .possible_values(["yaml", "json"]),
+
+            Arg::new(CHECKSUMS)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "After generation, write the SHA-256 digest of every generated file to PATH, one file per line in the format used by `sha256sum`.",
+                    "Paths are relative to the folder containing PATH when possible, so the output can be verified with `sha256sum -c PATH` from that folder.",
+                    "Digests cover the exact bytes written. protox never writes a timestamp into generated files, so output is reproducible, but a timestamp in a configured `generated_header` or `generated_header_file` is hashed like any other content.",
+                ]).as_str())
+                .long(CHECKSUMS)
+                .value_name("PATH")
+                .takes_value(true),
+
+            Arg::new(BUF_IMAGE)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Render --template and --script sets from the binary buf image at PATH, e.g. one written by `buf build -o image.binpb`, instead of running protoc.",
+                    &format!("Buf images are compatible with descriptor sets, and buf's own extensions to them are ignored. --{} is not needed, and --{} and --{} can't be used since they require protoc.", INPUT, PROTO, ENCODE),
+                ]).as_str())
+                .long(BUF_IMAGE)
+                .value_name("PATH")
+                .takes_value(true)
+                .conflicts_with_all(&[PROTO, PLUGIN_OUT, ENCODE]),
+
+            Arg::new(ALLOW_OVERWRITE)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help("By default, rendering fails if two distinct inputs would be written to the same output file, e.g. due to file name case conversion. With this flag, later files silently overwrite earlier ones.")
+                .long(ALLOW_OVERWRITE),
+
+            Arg::new(APPEND)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    &format!("Append to files which already exist in the output directories of --{} and --{} instead of overwriting them, without writing the generated header again, so the output of successive runs accumulates.", TEMPLATE, SCRIPT),
+                    "The output directories don't need to be empty. Nothing is ever removed from appended files, so content from earlier runs is kept even after the protos it was generated from change or are deleted. Clear the output directory to start over.",
+                ]).as_str())
+                .long(APPEND)
+                .conflicts_with_all(&[STDOUT, DIFF]),
+
+            Arg::new(SINCE)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    &format!("Compare the descriptor set against the baseline descriptor set at BASELINE, e.g. one kept from the previous run with --{}, and only render the files which were added or changed since, along with every file that imports one of them directly or transitively.", DESCRIPTOR_SET_OUT),
+                    "The output directories don't need to be empty, and files rendered from unchanged protos are left as they are.",
+                    "Only the files rendered from a single proto file are skipped. Outputs of several proto files, i.e. with `single_file` or `one_file_per_package`, metadata, directory files and descriptor sets are still rendered from every file, as is --lint.",
+                ]).as_str())
+                .long(SINCE)
+                .value_name("BASELINE")
+                .takes_value(true),
+
+            Arg::new(LINK_PROTO_OUTPUT)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Allows a --proto LANG OUTPUT directory to also be the output of a --template or --script set, which otherwise must be empty.",
+                    "The files generated by protoc are then listed in that set's metadata as `native_files`, next to the files rendered by protox.",
+                ]).as_str())
+                .long(LINK_PROTO_OUTPUT),
+
+            Arg::new(FAIL_ON_DEPRECATED)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Fail before rendering any --template or --script set if a field of a message which is not deprecated has a deprecated message or enum as its type.",
+                    "Fields which are deprecated themselves are allowed, so existing uses can be marked while blocking new ones.",
+                ]).as_str())
+                .long(FAIL_ON_DEPRECATED),
+
+            Arg::new(ERROR_ON_EMPTY)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "By default, a warning is logged when a --template or --script set has no proto files to render, either because the descriptor set is empty or all of its files are in `ignored_files`.",
+                    "With this flag, it is an error instead, so that inputs which don't match anything are noticed.",
+                ]).as_str())
+                .long(ERROR_ON_EMPTY),
+
+            Arg::new(INCLUDE_IMPORTS)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Render imported files found in the descriptor set, such as the well-known types under google/protobuf/.",
+                    "By default, files matching a template config's `import_prefixes` are only used for type resolution and are not rendered. This sets `render_imports` for every --template and --script.",
+                ]).as_str())
+                .long(INCLUDE_IMPORTS),
+
             Arg::new(DESCRIPTOR_SET_OUT)
                 .display_order(DISPLAY_ORDER_DEFAULT)
                 .default_value(DEFAULT_DESCRIPTOR_SET_FILENAME)
@@ -230,6 +486,24 @@ where
                 .long(DESCRIPTOR_SET_OUT)
                 .takes_value(true),
 
+            Arg::new(PROTOC_TIMEOUT)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Kill protoc and fail if a single protoc invocation takes longer than SECS seconds, e.g. when it hangs on a misconfigured include path.",
+                    "By default there is no timeout.",
+                ]).as_str())
+                .long(PROTOC_TIMEOUT)
+                .value_name("SECS")
+                .takes_value(true),
+
+            Arg::new(EDITIONS)
+                .display_order(DISPLAY_ORDER_DEFAULT)
+                .long_help(join_help(&[
+                    "Allow proto files which use protobuf editions (`edition = \"2023\";`) by passing --experimental_editions to protoc.",
+                    "Editions support depends on the version of protoc, which may require this flag or may not support editions at all.",
+                ]).as_str())
+                .long(EDITIONS),
+
             Arg::new(PROTOC_ARGS)
                 .display_order(DISPLAY_ORDER_DEFAULT)
                 .long_help(format!("Add any arguments directly to protoc invocation. Note they must be wrapped with \"\" as to not be picked up as arguments to protox.\nFor example: --{} \"--error_format=FORMAT\"", PROTOC_ARGS).as_str())
@@ -252,17 +526,46 @@ fn all_except<'a>(options: &[&'a str], opt: &'a str) -> Vec<&'a str> {
 
 pub struct Config {
     pub idl: Idl,
-    pub input: PathBuf,
+    pub input: Vec<PathBuf>,
     pub protos: Vec<LangConfig>,
+    /// Protoc plugins registered with --plugin, as (name, path) pairs.
+    pub plugins: Vec<(String, String)>,
+    /// Protoc plugin outputs from --plugin-out, as (name, output) pairs.
+    pub plugin_outs: Vec<(String, PathBuf)>,
     pub templates: Vec<InOutConfig>,
+    pub inline_templates: Vec<InlineTemplateConfig>,
+    pub descriptor_sets: Vec<InOutConfig>,
     pub scripts: Vec<ScriptConfig>,
     pub encode: Vec<EncodeConfig>,
     pub bypass: bool,
     pub includes: Vec<String>,
+    /// Include folders added after `includes` only for the protoc pass of each language. Languages
+    /// with their own includes are generated in a separate protoc pass.
+    pub lang_includes: HashMap<Lang, Vec<String>>,
     pub init_script_target: Option<PathBuf>,
     pub init_template_target: Option<PathBuf>,
+    pub list_languages: bool,
+    pub progress: bool,
+    pub stdout: bool,
+    pub diff: bool,
+    pub jobs: usize,
+    /// Format every renderer config file is parsed as, from --config-format.
+    pub config_format: Option<ConfigFormat>,
+    pub checksums: Option<PathBuf>,
+    pub buf_image: Option<PathBuf>,
+    pub allow_overwrite: bool,
+    pub append: bool,
+    pub since: Option<PathBuf>,
+    pub link_proto_output: bool,
+    pub fail_on_deprecated: bool,
+    pub error_on_empty: bool,
+    pub include_imports: bool,
+    pub lint: bool,
+    pub overlay_targets: Vec<String>,
     pub descriptor_set_path: PathBuf,
     pub extra_protoc_args: Vec<String>,
+    pub protoc_timeout: Option<Duration>,
+    pub editions: bool,
 
     // Owned here to keep alive for full program execution.
     #[allow(dead_code)]
@@ -273,17 +576,41 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             idl: Default::default(),
-            input: Default::default(),
+            input: vec![],
             protos: vec![],
+            plugins: vec![],
+            plugin_outs: vec![],
             templates: vec![],
+            inline_templates: vec![],
+            descriptor_sets: vec![],
             scripts: vec![],
             encode: vec![],
             bypass: false,
             includes: vec![],
+            lang_includes: HashMap::new(),
             init_script_target: None,
             init_template_target: None,
+            list_languages: false,
+            progress: false,
+            stdout: false,
+            diff: false,
+            jobs: default_jobs(),
+            config_format: None,
+            checksums: None,
+            buf_image: None,
+            allow_overwrite: false,
+            append: false,
+            since: None,
+            link_proto_output: false,
+            fail_on_deprecated: false,
+            error_on_empty: false,
+            include_imports: false,
+            lint: false,
+            overlay_targets: vec![],
             descriptor_set_path: Default::default(),
             extra_protoc_args: vec![],
+            protoc_timeout: None,
+            editions: false,
             intermediate_dir: tempdir().unwrap(),
         }
     }
@@ -299,39 +626,75 @@ impl Config {
 
     pub fn from_args(args: &ArgMatches) -> Result<Self> {
         let intermediate_dir = tempdir()?;
-        let input = parse_optional_path_from_arg(INPUT, &args)?.unwrap_or(PathBuf::new());
         let output_root = parse_optional_path_from_arg(OUTPUT_ROOT, &args)?;
         let template_root = parse_optional_path_from_arg(TEMPLATE_ROOT, &args)?;
         let script_root = parse_optional_path_from_arg(SCRIPT_ROOT, &args)?;
         let descriptor_set_path = parse_descriptor_path(intermediate_dir.path(), &args);
         let config = Self {
             idl: Idl::from_args(&args)?,
-            input,
+            input: parse_inputs(&args)?,
             protos: parse_protos(&args, output_root.as_ref())?,
+            plugins: parse_plugins(&args)?,
+            plugin_outs: parse_plugin_outs(&args, output_root.as_ref())?,
             templates: parse_in_out_configs(
                 TEMPLATE,
                 &args,
                 template_root.as_ref(),
                 output_root.as_ref(),
             )?,
+            inline_templates: parse_inline_template_configs(&args, output_root.as_ref())?,
+            descriptor_sets: parse_in_out_configs(
+                DESCRIPTOR_SETS,
+                &args,
+                template_root.as_ref(),
+                output_root.as_ref(),
+            )?,
             scripts: parse_script_configs(&args, script_root.as_ref(), output_root.as_ref())?,
             encode: parse_encode_configs(args, output_root.as_ref())?,
             bypass: args.is_present(BYPASS),
             includes: parse_includes(&args),
+            lang_includes: parse_lang_includes(&args)?,
             init_script_target: parse_optional_path_from_arg(INIT_SCRIPT, &args)?,
             init_template_target: parse_optional_path_from_arg(INIT_TEMPLATE, &args)?,
+            list_languages: args.is_present(LIST_LANGUAGES),
+            progress: args.is_present(PROGRESS),
+            stdout: args.is_present(STDOUT),
+            diff: args.is_present(DIFF),
+            jobs: parse_jobs(&args)?,
+            config_format: ConfigFormat::from_args(&args)?,
+            checksums: parse_optional_path_from_arg(CHECKSUMS, &args)?,
+            buf_image: parse_optional_path_from_arg(BUF_IMAGE, &args)?,
+            allow_overwrite: args.is_present(ALLOW_OVERWRITE),
+            append: args.is_present(APPEND),
+            since: parse_optional_path_from_arg(SINCE, &args)?,
+            link_proto_output: args.is_present(LINK_PROTO_OUTPUT),
+            fail_on_deprecated: args.is_present(FAIL_ON_DEPRECATED),
+            error_on_empty: args.is_present(ERROR_ON_EMPTY),
+            include_imports: args.is_present(INCLUDE_IMPORTS),
+            lint: args.is_present(LINT),
+            overlay_targets: parse_arg_to_vec(OVERLAY_TARGET, &args),
             descriptor_set_path,
             extra_protoc_args: parse_extra_protoc_args(&args),
+            protoc_timeout: parse_protoc_timeout(&args)?,
+            editions: args.is_present(EDITIONS),
             intermediate_dir,
         };
         check_proto_supported_languages(&config)?;
+        check_stdout(&config)?;
         Ok(config)
     }
 
     pub fn requires_descriptor_set(&self) -> bool {
-        self.protos.iter().find(|x| x.lang == Lang::Rust).is_some()
-            || !self.templates.is_empty()
+        self.protos.iter().find(|x| x.lang == Lang::Rust).is_some() || self.renders_descriptor_set()
+    }
+
+    /// True if the descriptor set is loaded after protoc to be linted or rendered.
+    pub fn renders_descriptor_set(&self) -> bool {
+        !self.templates.is_empty()
+            || !self.inline_templates.is_empty()
+            || !self.descriptor_sets.is_empty()
             || !self.scripts.is_empty()
+            || self.lint
     }
 }
 
@@ -339,6 +702,33 @@ fn check_proto_supported_languages(config: &Config) -> Result<()> {
     check_supported_languages(PROTO, &config.protos, &protoc::supported_languages())
 }
 
+/// With --stdout, there must be exactly one output, and it must be rendered rather than written by
+/// protoc or --encode, which can't write to stdout.
+fn check_stdout(config: &Config) -> Result<()> {
+    if !config.stdout {
+        return Ok(());
+    }
+    let rendered_outputs = config.templates.len()
+        + config.inline_templates.len()
+        + config.scripts.len()
+        + config.descriptor_sets.len();
+    let other_outputs = config.protos.len() + config.plugin_outs.len() + config.encode.len();
+    if rendered_outputs != 1 || other_outputs != 0 {
+        return Err(anyhow!(
+            "--{} requires exactly one --{}, --{}, --{} or --{}, and no --{}, --{} or --{}.",
+            STDOUT,
+            TEMPLATE,
+            TEMPLATE_INLINE,
+            SCRIPT,
+            DESCRIPTOR_SETS,
+            PROTO,
+            PLUGIN_OUT,
+            ENCODE
+        ));
+    }
+    Ok(())
+}
+
 fn check_supported_languages(
     name: &str,
     config: &Vec<LangConfig>,
@@ -396,6 +786,49 @@ fn parse_protos(args: &ArgMatches, output_root: Option<&PathBuf>) -> Result<Vec<
     Ok(configs)
 }
 
+fn parse_plugins(args: &ArgMatches) -> Result<Vec<(String, String)>> {
+    let mut plugins = Vec::new();
+    for value in parse_arg_to_vec(PLUGIN, args) {
+        match value.split_once('=') {
+            Some((name, path)) if !name.is_empty() && !path.is_empty() => {
+                plugins.push((name.to_owned(), path.to_owned()))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "--{} must be in the form NAME=PATH, got '{}'.",
+                    PLUGIN,
+                    value
+                ))
+            }
+        }
+    }
+    Ok(plugins)
+}
+
+fn parse_plugin_outs(
+    args: &ArgMatches,
+    output_root: Option<&PathBuf>,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut plugin_outs = Vec::new();
+    let values = match args.grouped_values_of(PLUGIN_OUT) {
+        None => return Ok(plugin_outs),
+        Some(values) => values,
+    };
+    for value in values {
+        let name = value
+            .get(0)
+            .ok_or(anyhow!("--{} is missing NAME", PLUGIN_OUT))?;
+        let output = value
+            .get(1)
+            .ok_or(anyhow!("--{} is missing OUTPUT", PLUGIN_OUT))?;
+        plugin_outs.push((
+            name.to_string(),
+            util::path_as_absolute(output, output_root)?,
+        ));
+    }
+    Ok(plugin_outs)
+}
+
 fn parse_in_out_configs(
     arg_name: &str,
     args: &ArgMatches,
@@ -411,13 +844,41 @@ fn parse_in_out_configs(
         let input = value
             .get(0)
             .ok_or(anyhow!("--{} is missing INPUT", arg_name))?;
+        let config = match value.get(1) {
+            Some(output) => InOutConfig::from_config(input, output, input_root, output_root)?,
+            None => {
+                let output_root = output_root.ok_or(anyhow!(
+                    "--{} is missing OUTPUT, which is required without --{}",
+                    arg_name,
+                    OUTPUT_ROOT
+                ))?;
+                InOutConfig::from_config(input, "", input_root, Some(output_root))?
+            }
+        };
+        configs.push(config);
+    }
+    Ok(configs)
+}
+
+fn parse_inline_template_configs(
+    args: &ArgMatches,
+    output_root: Option<&PathBuf>,
+) -> Result<Vec<InlineTemplateConfig>> {
+    let mut configs = Vec::new();
+    let values = match args.grouped_values_of(TEMPLATE_INLINE) {
+        None => return Ok(configs),
+        Some(values) => values,
+    };
+    for value in values {
+        let template = value
+            .get(0)
+            .ok_or(anyhow!("--{} is missing TEMPLATE", TEMPLATE_INLINE))?;
         let output = value
             .get(1)
-            .ok_or(anyhow!("--{} is missing OUTPUT", arg_name))?;
-        configs.push(InOutConfig::from_config(
-            input,
+            .ok_or(anyhow!("--{} is missing OUTPUT", TEMPLATE_INLINE))?;
+        configs.push(InlineTemplateConfig::from_config(
+            template,
             output,
-            input_root,
             output_root,
         )?);
     }
@@ -504,14 +965,79 @@ fn parse_encode_configs(
     Ok(configs)
 }
 
+fn parse_inputs(args: &ArgMatches) -> Result<Vec<PathBuf>> {
+    let mut inputs = Vec::new();
+    for input in parse_arg_to_vec(INPUT, args) {
+        inputs.push(current_dir(INPUT)?.join(input));
+    }
+    Ok(inputs)
+}
+
 fn parse_includes(args: &ArgMatches) -> Vec<String> {
     parse_arg_to_vec(INCLUDES, args)
 }
 
+fn parse_lang_includes(args: &ArgMatches) -> Result<HashMap<Lang, Vec<String>>> {
+    let mut includes = HashMap::<Lang, Vec<String>>::new();
+    let values = match args.grouped_values_of(LANG_INCLUDE) {
+        None => return Ok(includes),
+        Some(values) => values,
+    };
+    for value in values {
+        let lang = value
+            .get(0)
+            .ok_or(anyhow!("--{} is missing LANG", LANG_INCLUDE))?;
+        let include = value
+            .get(1)
+            .ok_or(anyhow!("--{} is missing PATH", LANG_INCLUDE))?;
+        includes
+            .entry(Lang::from_str(lang)?)
+            .or_default()
+            .push(include.to_string());
+    }
+    Ok(includes)
+}
+
 fn parse_extra_protoc_args(args: &ArgMatches) -> Vec<String> {
     parse_arg_to_vec(PROTOC_ARGS, args)
 }
 
+fn parse_jobs(args: &ArgMatches) -> Result<usize> {
+    let jobs = match args.value_of(JOBS) {
+        None => return Ok(default_jobs()),
+        Some(jobs) => jobs,
+    };
+    match jobs.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(anyhow!(
+            "--{} must be a number greater than 0, got '{}'.",
+            JOBS,
+            jobs
+        )),
+    }
+}
+
+fn default_jobs() -> usize {
+    thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(1)
+}
+
+fn parse_protoc_timeout(args: &ArgMatches) -> Result<Option<Duration>> {
+    let secs = match args.value_of(PROTOC_TIMEOUT) {
+        None => return Ok(None),
+        Some(secs) => secs,
+    };
+    match secs.parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(Some(Duration::from_secs(secs))),
+        _ => Err(anyhow!(
+            "--{} must be a number of seconds greater than 0, got '{}'.",
+            PROTOC_TIMEOUT,
+            secs
+        )),
+    }
+}
+
 fn parse_arg_to_vec(arg_name: &str, args: &ArgMatches) -> Vec<String> {
     args.values_of(arg_name)
         .unwrap_or(Values::default())
@@ -540,11 +1066,14 @@ impl ArgExt for Arg<'_> {
 #[cfg(test)]
 mod tests {
     use crate::config::{
-        parse_cli_args, APP_NAME, INCLUDES, INPUT, OUTPUT_ROOT, PROTO, PROTOC_ARGS,
+        parse_cli_args, APP_NAME, CHECKSUMS, DESCRIPTOR_SETS, INCLUDES, INPUT, LANG_INCLUDE, LINT,
+        LIST_LANGUAGES, OUTPUT_ROOT, OVERLAY_TARGET, PLUGIN, PLUGIN_OUT, PROTO, PROTOC_ARGS,
+        STDOUT, TEMPLATE, TEMPLATE_INLINE,
     };
-    use crate::{Config, DisplayNormalized};
+    use crate::{Config, DisplayNormalized, Lang};
     use anyhow::Result;
     use std::env::current_dir;
+    use tempfile::tempdir;
 
     #[test]
     fn parse_input() -> Result<()> {
@@ -560,7 +1089,205 @@ mod tests {
             &arg(OUTPUT_ROOT),
             &output.display_normalized(),
         ])?)?;
-        assert_eq!(config.input, input);
+        assert_eq!(config.input, vec![input]);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_multiple_inputs() -> Result<()> {
+        let input_arg = arg(INPUT);
+        let config = config_with_required_args([input_arg.as_str(), "path/to/other"])?;
+        assert_eq!(
+            config.input,
+            vec![
+                current_dir()?.join("path/to/input"),
+                current_dir()?.join("path/to/other")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_template_without_output() -> Result<()> {
+        let input = current_dir()?;
+        let output = current_dir()?.join("output");
+        let config = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(TEMPLATE),
+            &input.join("template").display_normalized(),
+            &arg(OUTPUT_ROOT),
+            &output.display_normalized(),
+        ])?)?;
+        assert_eq!(config.templates.len(), 1);
+        assert_eq!(config.templates[0].output, output);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_template_with_output() -> Result<()> {
+        let input = current_dir()?;
+        let output = current_dir()?.join("output");
+        let config = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(TEMPLATE),
+            &input.join("template").display_normalized(),
+            "sub",
+            &arg(OUTPUT_ROOT),
+            &output.display_normalized(),
+        ])?)?;
+        assert_eq!(config.templates[0].output, output.join("sub"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_template_without_output_or_root_errors() -> Result<()> {
+        let input = current_dir()?;
+        let result = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(TEMPLATE),
+            &input.join("template").display_normalized(),
+        ])?);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn stdout_requires_single_template_or_script() -> Result<()> {
+        let input = current_dir()?;
+        let result = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(PROTO),
+            "cpp",
+            "proto_cpp",
+            &arg(OUTPUT_ROOT),
+            &input.display_normalized(),
+            &arg(STDOUT),
+        ])?);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn stdout_rejects_proto_with_template() -> Result<()> {
+        let input = current_dir()?;
+        let result = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(PROTO),
+            "cpp",
+            "proto_cpp",
+            &arg(TEMPLATE),
+            &input.display_normalized(),
+            "out",
+            &arg(OUTPUT_ROOT),
+            &input.display_normalized(),
+            &arg(STDOUT),
+        ])?);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn stdout_counts_inline_templates() -> Result<()> {
+        let input = current_dir()?;
+        let config = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(TEMPLATE_INLINE),
+            "{{source_file}}",
+            "out",
+            &arg(OUTPUT_ROOT),
+            &input.display_normalized(),
+            &arg(STDOUT),
+        ])?)?;
+        assert_eq!(config.inline_templates.len(), 1);
+
+        let result = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(TEMPLATE_INLINE),
+            "{{source_file}}",
+            "out",
+            &arg(TEMPLATE),
+            &input.display_normalized(),
+            "other",
+            &arg(OUTPUT_ROOT),
+            &input.display_normalized(),
+            &arg(STDOUT),
+        ])?);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_list_languages() -> Result<()> {
+        let config = Config::from_args(&parse_cli_args([APP_NAME, &arg(LIST_LANGUAGES)])?)?;
+        assert!(config.list_languages);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_lint_without_other_main_options() -> Result<()> {
+        let input = current_dir()?;
+        let config = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(LINT),
+        ])?)?;
+        assert!(config.lint);
+        assert!(config.requires_descriptor_set());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_descriptor_sets() -> Result<()> {
+        let input = current_dir()?;
+        let output = current_dir()?.join("output");
+        let config = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(DESCRIPTOR_SETS),
+            &input.join("sets").display_normalized(),
+            "sub",
+            &arg(OUTPUT_ROOT),
+            &output.display_normalized(),
+        ])?)?;
+        assert!(config.templates.is_empty());
+        assert_eq!(config.descriptor_sets.len(), 1);
+        assert_eq!(config.descriptor_sets[0].output, output.join("sub"));
+        assert!(config.requires_descriptor_set());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_template_inline() -> Result<()> {
+        let input = current_dir()?;
+        let output = tempdir()?;
+        let config = Config::from_args(&parse_cli_args([
+            APP_NAME,
+            &arg(INPUT),
+            &input.display_normalized(),
+            &arg(TEMPLATE_INLINE),
+            "{{source_file}}",
+            &output.path().display_normalized(),
+        ])?)?;
+        assert_eq!(config.inline_templates.len(), 1);
+        assert_eq!(config.inline_templates[0].template, "{{source_file}}");
+        assert_eq!(config.inline_templates[0].output, output.path());
+        assert!(config.requires_descriptor_set());
         Ok(())
     }
 
@@ -616,6 +1343,146 @@ mod tests {
         Ok(())
     }
 
+    mod parse_jobs {
+        use crate::config::tests::{arg, config_with_required_args};
+        use crate::config::{default_jobs, JOBS};
+        use anyhow::Result;
+
+        #[test]
+        fn default() -> Result<()> {
+            let config = config_with_required_args(Vec::<String>::new())?;
+            assert_eq!(config.jobs, default_jobs());
+            Ok(())
+        }
+
+        #[test]
+        fn explicit() -> Result<()> {
+            let arg = arg(JOBS);
+            let config = config_with_required_args([arg.as_str(), "1"])?;
+            assert_eq!(config.jobs, 1);
+            Ok(())
+        }
+
+        #[test]
+        fn zero_errors() {
+            let arg = arg(JOBS);
+            assert!(config_with_required_args([arg.as_str(), "0"]).is_err());
+        }
+
+        #[test]
+        fn invalid_errors() {
+            let arg = arg(JOBS);
+            assert!(config_with_required_args([arg.as_str(), "many"]).is_err());
+        }
+    }
+
+    mod parse_config_format {
+        use crate::config::tests::{arg, config_with_required_args};
+        use crate::config::CONFIG_FORMAT;
+        use crate::config_format::ConfigFormat;
+        use anyhow::Result;
+
+        #[test]
+        fn default() -> Result<()> {
+            let config = config_with_required_args(Vec::<String>::new())?;
+            assert_eq!(config.config_format, None);
+            Ok(())
+        }
+
+        #[test]
+        fn json() -> Result<()> {
+            let arg = arg(CONFIG_FORMAT);
+            let config = config_with_required_args([arg.as_str(), "json"])?;
+            assert_eq!(config.config_format, Some(ConfigFormat::Json));
+            Ok(())
+        }
+
+        #[test]
+        fn unsupported_errors() {
+            let arg = arg(CONFIG_FORMAT);
+            assert!(config_with_required_args([arg.as_str(), "toml"]).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_checksums() -> Result<()> {
+        let arg = arg(CHECKSUMS);
+        let config = config_with_required_args([arg.as_str(), "checksums.txt"])?;
+        assert_eq!(config.checksums, Some(current_dir()?.join("checksums.txt")));
+        Ok(())
+    }
+
+    mod parse_protoc_timeout {
+        use crate::config::tests::{arg, config_with_required_args};
+        use crate::config::PROTOC_TIMEOUT;
+        use anyhow::Result;
+        use std::time::Duration;
+
+        #[test]
+        fn default_is_none() -> Result<()> {
+            let config = config_with_required_args(Vec::<String>::new())?;
+            assert_eq!(config.protoc_timeout, None);
+            Ok(())
+        }
+
+        #[test]
+        fn explicit() -> Result<()> {
+            let arg = arg(PROTOC_TIMEOUT);
+            let config = config_with_required_args([arg.as_str(), "30"])?;
+            assert_eq!(config.protoc_timeout, Some(Duration::from_secs(30)));
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_errors() {
+            let arg = arg(PROTOC_TIMEOUT);
+            assert!(config_with_required_args([arg.as_str(), "0"]).is_err());
+            assert!(config_with_required_args([arg.as_str(), "soon"]).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_lang_includes() -> Result<()> {
+        let arg = arg(LANG_INCLUDE);
+        let config = config_with_required_args([arg.as_str(), "cpp", "cpp/include"])?;
+        assert_eq!(
+            config.lang_includes.get(&Lang::Cpp),
+            Some(&vec!["cpp/include".to_owned()])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_plugins() -> Result<()> {
+        let output = current_dir()?.join("foo_out");
+        let config = config_with_required_args([
+            arg(PLUGIN).as_str(),
+            "foo=bin/protoc-gen-foo",
+            arg(PLUGIN_OUT).as_str(),
+            "foo",
+            &output.display_normalized(),
+        ])?;
+        assert_eq!(
+            config.plugins,
+            vec![("foo".to_owned(), "bin/protoc-gen-foo".to_owned())]
+        );
+        assert_eq!(config.plugin_outs, vec![("foo".to_owned(), output)]);
+        Ok(())
+    }
+
+    #[test]
+    fn plugin_without_path_errors() {
+        assert!(config_with_required_args([arg(PLUGIN).as_str(), "foo"]).is_err());
+    }
+
+    #[test]
+    fn parse_overlay_targets() -> Result<()> {
+        let arg = arg(OVERLAY_TARGET);
+        let config = config_with_required_args([arg.as_str(), "debug", arg.as_str(), "local"])?;
+        assert_eq!(config.overlay_targets, vec!["debug", "local"]);
+        Ok(())
+    }
+
     fn quote(value: &str) -> String {
         ["\"", value, "\""].concat()
     }
@@ -0,0 +1,67 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use clap::ArgMatches;
+
+use crate::config;
+
+/// Format renderer config files are parsed as, regardless of their extension.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// The `--config-format` override, or None to parse every config file as YAML, which is a
+    /// superset of JSON.
+    pub fn from_args(args: &ArgMatches) -> Result<Option<Self>> {
+        args.value_of(config::CONFIG_FORMAT)
+            .map(ConfigFormat::from_str)
+            .transpose()
+    }
+
+    pub fn as_config(&self) -> &'static str {
+        match self {
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yaml" => Ok(ConfigFormat::Yaml),
+            "json" => Ok(ConfigFormat::Json),
+            _ => Err(anyhow!(
+                "Unsupported config format: {}. Supported formats: yaml, json",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+
+    use crate::config_format::ConfigFormat;
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        for format in [ConfigFormat::Yaml, ConfigFormat::Json] {
+            assert_eq!(ConfigFormat::from_str(format.as_config())?, format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn toml_is_unsupported() {
+        assert!(ConfigFormat::from_str("toml").is_err());
+    }
+}
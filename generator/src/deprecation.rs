@@ -0,0 +1,316 @@
+use crate::renderer::MapDetection;
+use anyhow::{bail, Result};
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorSet};
+use std::collections::{HashMap, HashSet};
+
+/// Fully-qualified names of every deprecated message and enum in a descriptor set, with a
+/// leading '.' as used by field type names, e.g. `.pkg.Message`.
+pub struct DeprecationIndex {
+    deprecated_types: HashSet<String>,
+}
+
+impl DeprecationIndex {
+    pub fn from_descriptor_set(descriptor_set: &FileDescriptorSet) -> Self {
+        let mut index = Self {
+            deprecated_types: HashSet::new(),
+        };
+        for file in &descriptor_set.file {
+            let scope = package_scope(&file.package);
+            for message in &file.message_type {
+                index.insert_message(&scope, message);
+            }
+            for proto in &file.enum_type {
+                index.insert_enum(&scope, proto);
+            }
+        }
+        index
+    }
+
+    pub fn is_deprecated(&self, type_name: &str) -> bool {
+        self.deprecated_types.contains(type_name)
+    }
+
+    fn insert_message(&mut self, scope: &str, message: &DescriptorProto) {
+        let name = qualified_name(scope, &message.name);
+        for nested in &message.nested_type {
+            self.insert_message(&name, nested);
+        }
+        for proto in &message.enum_type {
+            self.insert_enum(&name, proto);
+        }
+        if message.options.as_ref().and_then(|o| o.deprecated) == Some(true) {
+            self.deprecated_types.insert(name);
+        }
+    }
+
+    fn insert_enum(&mut self, scope: &str, proto: &EnumDescriptorProto) {
+        if proto.options.as_ref().and_then(|o| o.deprecated) == Some(true) {
+            self.deprecated_types
+                .insert(qualified_name(scope, &proto.name));
+        }
+    }
+}
+
+/// Errors on the first field of a non-deprecated message whose type is a deprecated message or
+/// enum. Fields which are deprecated themselves are allowed to keep referencing deprecated types.
+/// Map fields are checked by the value type of their map entry, found with `map_detection`.
+pub fn check_deprecated_usage(
+    descriptor_set: &FileDescriptorSet,
+    map_detection: MapDetection,
+) -> Result<()> {
+    let index = DeprecationIndex::from_descriptor_set(descriptor_set);
+    for file in &descriptor_set.file {
+        let scope = package_scope(&file.package);
+        for message in &file.message_type {
+            check_message(&index, map_detection, &scope, message)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_message(
+    index: &DeprecationIndex,
+    map_detection: MapDetection,
+    scope: &str,
+    message: &DescriptorProto,
+) -> Result<()> {
+    let name = qualified_name(scope, &message.name);
+    if index.is_deprecated(&name) {
+        return Ok(());
+    }
+    let is_map_entry = |nested: &DescriptorProto| map_detection.is_map_entry(Some(message), nested);
+    let map_entries = message
+        .nested_type
+        .iter()
+        .filter(|nested| is_map_entry(nested))
+        .map(|nested| (qualified_name(&name, &nested.name), nested))
+        .collect::<HashMap<_, _>>();
+    for field in &message.field {
+        if field.options.as_ref().and_then(|o| o.deprecated) == Some(true) {
+            continue;
+        }
+        let type_name = match &field.type_name {
+            None => continue,
+            Some(type_name) => match map_entries.get(type_name) {
+                None => type_name,
+                Some(entry) => match map_value_type(entry) {
+                    None => continue,
+                    Some(type_name) => type_name,
+                },
+            },
+        };
+        if index.is_deprecated(type_name) {
+            bail!(
+                "Field '{}.{}' references deprecated type '{}'",
+                name.trim_start_matches('.'),
+                field.name.as_deref().unwrap_or_default(),
+                type_name.trim_start_matches('.'),
+            );
+        }
+    }
+    // Map entries are checked through the fields that use them.
+    for nested in message.nested_type.iter().filter(|n| !is_map_entry(n)) {
+        check_message(index, map_detection, &name, nested)?;
+    }
+    Ok(())
+}
+
+/// Type name of the `value` field of a map entry, if it is a message or enum.
+fn map_value_type(entry: &DescriptorProto) -> Option<&String> {
+    entry
+        .field
+        .iter()
+        .find(|field| field.number == Some(2))
+        .and_then(|field| field.type_name.as_ref())
+}
+
+fn package_scope(package: &Option<String>) -> String {
+    match package {
+        None => String::new(),
+        Some(package) => [".", package].concat(),
+    }
+}
+
+fn qualified_name(scope: &str, name: &Option<String>) -> String {
+    [scope, ".", name.as_deref().unwrap_or_default()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use prost_types::field_descriptor_proto::Label;
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumOptions, FieldDescriptorProto, FieldOptions,
+        FileDescriptorProto, FileDescriptorSet, MessageOptions,
+    };
+
+    use crate::deprecation::{check_deprecated_usage, DeprecationIndex};
+    use crate::renderer::MapDetection;
+
+    #[test]
+    fn indexes_nested_types() {
+        let mut outer = deprecated_message("Outer");
+        outer.nested_type.push(deprecated_message("Inner"));
+        outer.enum_type.push(deprecated_enum("Kind"));
+        let index = DeprecationIndex::from_descriptor_set(&descriptor_set(vec![outer], vec![]));
+        assert!(index.is_deprecated(".pkg.Outer"));
+        assert!(index.is_deprecated(".pkg.Outer.Inner"));
+        assert!(index.is_deprecated(".pkg.Outer.Kind"));
+        assert!(!index.is_deprecated(".pkg.Other"));
+    }
+
+    #[test]
+    fn field_referencing_deprecated_type_errors() {
+        let user = message("User", vec![field("old", ".pkg.Old")]);
+        let set = descriptor_set(vec![user, deprecated_message("Old")], vec![]);
+        let error = check_deprecated_usage(&set, MapDetection::Option)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("pkg.User.old"), "{}", error);
+        assert!(error.contains("pkg.Old"), "{}", error);
+    }
+
+    #[test]
+    fn field_referencing_deprecated_enum_errors() {
+        let user = message("User", vec![field("kind", ".pkg.Kind")]);
+        let set = descriptor_set(vec![user], vec![deprecated_enum("Kind")]);
+        assert!(check_deprecated_usage(&set, MapDetection::Option).is_err());
+    }
+
+    #[test]
+    fn deprecated_users_are_allowed() -> Result<()> {
+        let mut user = deprecated_message("User");
+        user.field.push(field("old", ".pkg.Old"));
+        let mut deprecated_field = field("old", ".pkg.Old");
+        deprecated_field.options = Some(FieldOptions {
+            deprecated: Some(true),
+            ..Default::default()
+        });
+        let other = message("Other", vec![deprecated_field]);
+        let set = descriptor_set(vec![user, other, deprecated_message("Old")], vec![]);
+        check_deprecated_usage(&set, MapDetection::Option)
+    }
+
+    #[test]
+    fn map_field_checks_value_type() {
+        let user = map_message("User", field("values", ".pkg.User.ValuesEntry"));
+        let set = descriptor_set(vec![user, deprecated_message("Old")], vec![]);
+        let error = check_deprecated_usage(&set, MapDetection::Option)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("pkg.User.values"), "{}", error);
+        assert!(!error.contains("ValuesEntry"), "{}", error);
+    }
+
+    #[test]
+    fn deprecated_map_field_is_allowed() -> Result<()> {
+        let mut values = field("values", ".pkg.User.ValuesEntry");
+        values.options = Some(FieldOptions {
+            deprecated: Some(true),
+            ..Default::default()
+        });
+        let user = map_message("User", values);
+        check_deprecated_usage(
+            &descriptor_set(vec![user, deprecated_message("Old")], vec![]),
+            MapDetection::Option,
+        )
+    }
+
+    #[test]
+    fn heuristic_checks_conventional_map_value_type() {
+        let mut values = field("values", ".pkg.User.ValuesEntry");
+        values.label = Some(Label::Repeated as i32);
+        let mut user = map_message("User", values);
+        user.nested_type[0].options = None;
+        let set = descriptor_set(vec![user, deprecated_message("Old")], vec![]);
+
+        let error = check_deprecated_usage(&set, MapDetection::Heuristic)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("pkg.User.values"), "{}", error);
+        assert!(!error.contains("ValuesEntry"), "{}", error);
+        // Without the option, the entry is checked like any other nested message.
+        let error = check_deprecated_usage(&set, MapDetection::Option)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("ValuesEntry.value"), "{}", error);
+    }
+
+    /// `name` with the field `values` of type `map<string, Old>`.
+    fn map_message(name: &str, values: FieldDescriptorProto) -> DescriptorProto {
+        let mut value = field("value", ".pkg.Old");
+        value.number = Some(2);
+        let entry = DescriptorProto {
+            name: Some("ValuesEntry".to_owned()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("key".to_owned()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+                value,
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        DescriptorProto {
+            nested_type: vec![entry],
+            ..message(name, vec![values])
+        }
+    }
+
+    fn descriptor_set(
+        messages: Vec<DescriptorProto>,
+        enums: Vec<EnumDescriptorProto>,
+    ) -> FileDescriptorSet {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("file.proto".to_owned()),
+                package: Some("pkg".to_owned()),
+                message_type: messages,
+                enum_type: enums,
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn message(name: &str, fields: Vec<FieldDescriptorProto>) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_owned()),
+            field: fields,
+            ..Default::default()
+        }
+    }
+
+    fn deprecated_message(name: &str) -> DescriptorProto {
+        DescriptorProto {
+            options: Some(MessageOptions {
+                deprecated: Some(true),
+                ..Default::default()
+            }),
+            ..message(name, vec![])
+        }
+    }
+
+    fn deprecated_enum(name: &str) -> EnumDescriptorProto {
+        EnumDescriptorProto {
+            name: Some(name.to_owned()),
+            options: Some(EnumOptions {
+                deprecated: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn field(name: &str, type_name: &str) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            type_name: Some(type_name.to_owned()),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::util::DisplayNormalized;
+use anyhow::{Context, Result};
+use similar::TextDiff;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+const MISSING_FILE: &str = "/dev/null";
+
+/// Compares every file under `generated` to the file at the same relative path under `existing`
+/// and writes a unified diff to `writer` for each that differs. Files only found in `existing`
+/// are reported as removed. Returns true if anything differs.
+pub fn diff_dirs<W: io::Write>(generated: &Path, existing: &Path, writer: &mut W) -> Result<bool> {
+    let mut relative_paths = relative_file_paths(generated)?;
+    relative_paths.extend(relative_file_paths(existing)?);
+    let mut differs = false;
+    for relative in &relative_paths {
+        let old = read_if_exists(&existing.join(relative))?;
+        let new = read_if_exists(&generated.join(relative))?;
+        if old == new {
+            continue;
+        }
+        differs = true;
+        let old_name = diff_file_name("a", relative, &old);
+        let new_name = diff_file_name("b", relative, &new);
+        let old = String::from_utf8_lossy(old.as_deref().unwrap_or_default());
+        let new = String::from_utf8_lossy(new.as_deref().unwrap_or_default());
+        write!(
+            writer,
+            "{}",
+            TextDiff::from_lines(&*old, &*new)
+                .unified_diff()
+                .header(&old_name, &new_name)
+        )?;
+    }
+    writer.flush()?;
+    Ok(differs)
+}
+
+fn relative_file_paths(dir: &Path) -> Result<BTreeSet<PathBuf>> {
+    let mut paths = BTreeSet::new();
+    if !dir.exists() {
+        return Ok(paths);
+    }
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            paths.insert(entry.path().strip_prefix(dir)?.to_path_buf());
+        }
+    }
+    Ok(paths)
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    fs::read(path).map(Some).with_context(|| {
+        format!(
+            "Failed to read file at path '{}'",
+            path.display_normalized()
+        )
+    })
+}
+
+fn diff_file_name(prefix: &str, relative: &Path, contents: &Option<Vec<u8>>) -> String {
+    match contents {
+        None => MISSING_FILE.to_owned(),
+        Some(_) => format!("{}/{}", prefix, relative.display_normalized()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use crate::diff::diff_dirs;
+
+    #[test]
+    fn matching_dirs() -> Result<()> {
+        let generated = tempdir()?;
+        let existing = tempdir()?;
+        fs::write(generated.path().join("file.txt"), "line0\nline1\n")?;
+        fs::write(existing.path().join("file.txt"), "line0\nline1\n")?;
+        let mut output = Vec::new();
+        assert!(!diff_dirs(generated.path(), existing.path(), &mut output)?);
+        assert!(output.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn changed_file() -> Result<()> {
+        let generated = tempdir()?;
+        let existing = tempdir()?;
+        fs::write(generated.path().join("file.txt"), "line0\nnew\n")?;
+        fs::write(existing.path().join("file.txt"), "line0\nold\n")?;
+        let mut output = Vec::new();
+        assert!(diff_dirs(generated.path(), existing.path(), &mut output)?);
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("--- a/file.txt"));
+        assert!(output.contains("+++ b/file.txt"));
+        assert!(output.contains("-old"));
+        assert!(output.contains("+new"));
+        Ok(())
+    }
+
+    #[test]
+    fn added_and_removed_files() -> Result<()> {
+        let generated = tempdir()?;
+        let existing = tempdir()?;
+        fs::write(generated.path().join("added.txt"), "added\n")?;
+        fs::write(existing.path().join("removed.txt"), "removed\n")?;
+        let mut output = Vec::new();
+        assert!(diff_dirs(generated.path(), existing.path(), &mut output)?);
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("--- /dev/null\n+++ b/added.txt"));
+        assert!(output.contains("--- a/removed.txt\n+++ /dev/null"));
+        Ok(())
+    }
+
+    #[test]
+    fn missing_existing_dir_is_all_added() -> Result<()> {
+        let generated = tempdir()?;
+        let existing = tempdir()?;
+        fs::write(generated.path().join("file.txt"), "line\n")?;
+        let mut output = Vec::new();
+        assert!(diff_dirs(
+            generated.path(),
+            &existing.path().join("missing"),
+            &mut output
+        )?);
+        Ok(())
+    }
+}
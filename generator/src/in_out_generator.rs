@@ -1,9 +1,12 @@
-use crate::render::Render;
-use crate::{util, Config, DisplayNormalized, InOutConfig};
-use anyhow::Context;
-use anyhow::Result;
-use log::info;
+use crate::render::{Render, RenderOptions};
+use crate::{checksums, diff, incremental, util, Config, DisplayNormalized, InOutConfig};
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
 use prost_types::FileDescriptorSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+use walkdir::WalkDir;
 
 pub trait InOutGenerator<R: Render> {
     fn name(&self) -> &str;
@@ -12,31 +15,132 @@ pub trait InOutGenerator<R: Render> {
     fn app_config(&self) -> &Config;
     fn in_out_configs(&self) -> Vec<InOutConfig>;
 
-    fn generate(&mut self) -> Result<()> {
-        if self.in_out_configs().is_empty() {
-            return Ok(());
-        }
-        let descriptor_set = util::load_descriptor_set(self.app_config())?;
-        self.generate_from_descriptor_set(&descriptor_set)?;
-        Ok(())
+    /// Loads the renderer for `config`, the `index`th of `in_out_configs`.
+    fn load_renderer(&mut self, _index: usize, config: &InOutConfig) -> Result<()> {
+        let config_format = self.app_config().config_format;
+        self.renderer()
+            .load(&config.input, &config.overlays, config_format)
     }
 
-    fn generate_from_descriptor_set(&mut self, descriptor_set: &FileDescriptorSet) -> Result<()> {
+    /// Renders every set of `in_out_configs` from `descriptor_set`. With `--diff`, returns true if
+    /// any output differs from the existing files, which the caller reports once every generator
+    /// has printed its diff.
+    fn generate_from_descriptor_set(&mut self, descriptor_set: &FileDescriptorSet) -> Result<bool> {
         if self.in_out_configs().is_empty() {
-            return Ok(());
+            return Ok(false);
         }
-        for config in &self.in_out_configs() {
+        let mut options = render_options(self.app_config());
+        options.source_file_hashes =
+            checksums::source_file_hashes(self.app_config(), descriptor_set)
+                .with_context(|| format!("{} source file hashes", self.name()))?;
+        options.changed_files = incremental::load_changed_files(self.app_config(), descriptor_set)?;
+        let mut differs = false;
+        // Sets are always rendered one at a time in the order returned by `in_out_configs`, which
+        // is the order they were specified on the command line. Every file of a set is written
+        // and closed before the next set is loaded.
+        for (index, config) in self.in_out_configs().iter().enumerate() {
             log_render_start(self.name(), &config);
-            self.renderer().load(&config.input, &config.overlays)?;
-            util::create_dir_or_error(&config.output)
-                .with_context(|| error_context(self.name()))?;
-            util::check_dir_is_empty(&config.output).with_context(|| error_context(self.name()))?;
-            self.renderer().render(&descriptor_set, &config.output)?;
+            self.load_renderer(index, config)?;
+            if !self
+                .renderer()
+                .has_files_to_render(descriptor_set, &options)
+            {
+                report_nothing_to_render(self.name(), config, self.app_config().error_on_empty)?;
+            }
+            let is_linked = is_linked_proto_output(self.app_config(), &config.output);
+            options.native_files = if is_linked {
+                native_files(&config.output).with_context(|| error_context(self.name()))?
+            } else {
+                vec![]
+            };
+            // With --stdout or --diff, render into a scratch directory so the output directory is
+            // left untouched.
+            let scratch_dir = if self.app_config().stdout || self.app_config().diff {
+                Some(tempdir().with_context(|| error_context(self.name()))?)
+            } else {
+                None
+            };
+            let output = match &scratch_dir {
+                Some(dir) => dir.path().to_path_buf(),
+                None => config.output.clone(),
+            };
+            util::create_dir_or_error(&output).with_context(|| error_context(self.name()))?;
+            // A linked output directory already holds the files protoc generated into it, and with
+            // --append or --since the output of earlier runs is expected.
+            let is_incremental = options.append || self.app_config().since.is_some();
+            if (!is_linked && !is_incremental) || scratch_dir.is_some() {
+                util::check_dir_is_empty(&output).with_context(|| error_context(self.name()))?;
+            }
+            if !self.app_config().allow_overwrite {
+                self.renderer()
+                    .check_output_paths(&descriptor_set, &output, &options)?;
+            }
+            self.renderer().render(&descriptor_set, &output, &options)?;
+            if let Some(dir) = &scratch_dir {
+                if self.app_config().stdout {
+                    util::write_single_file(dir.path(), &mut io::stdout().lock())
+                        .with_context(|| format!("{} --stdout", self.name()))?;
+                } else {
+                    differs |=
+                        diff::diff_dirs(dir.path(), &config.output, &mut io::stdout().lock())
+                            .with_context(|| format!("{} --diff", self.name()))?;
+                }
+            }
         }
-        Ok(())
+        if differs {
+            warn!("{} output differs from the existing files.", self.name());
+        }
+        Ok(differs)
+    }
+}
+
+/// The options of every render in the run with `config`, without the per-set native files.
+fn render_options(config: &Config) -> RenderOptions {
+    RenderOptions {
+        overlay_targets: config.overlay_targets.clone(),
+        progress: config.progress,
+        render_imports: config.include_imports,
+        jobs: config.jobs,
+        append: config.append,
+        ..Default::default()
     }
 }
 
+/// With `--link-proto-output`, a `--proto` output may also be rendered to by other generators.
+fn is_linked_proto_output(config: &Config, output: &Path) -> bool {
+    config.link_proto_output && config.protos.iter().any(|proto| proto.output == output)
+}
+
+/// Every file in `dir`, relative to it, which protoc has already generated into it.
+fn native_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.path().strip_prefix(dir)?.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Usually means the inputs didn't match any proto files, or they are all in `ignored_files`.
+fn report_nothing_to_render(name: &str, config: &InOutConfig, error_on_empty: bool) -> Result<()> {
+    let message = format!(
+        "{} has no proto files to render with '{}'. Check the inputs and the `ignored_files` config.",
+        name,
+        config.input.display_normalized(),
+    );
+    if error_on_empty {
+        return Err(anyhow!(message));
+    }
+    warn!("{}", message);
+    Ok(())
+}
+
 fn error_context(name: &str) -> String {
     format!("InOutGenerator '{}' out dir", name)
 }
@@ -53,10 +157,13 @@ fn log_render_start(name: &str, config: &InOutConfig) {
 #[cfg(test)]
 mod tests {
     use crate::in_out_generator::InOutGenerator;
-    use crate::render::Render;
-    use crate::{util, Config, InOutConfig};
+    use crate::render::{Render, RenderOptions};
+    use crate::{util, Config, ConfigFormat, InOutConfig, Lang, LangConfig};
     use anyhow::Result;
+    use prost::encoding::{self, WireType};
+    use prost::Message;
     use prost_types::{FileDescriptorProto, FileDescriptorSet};
+    use std::cell::RefCell;
     use std::fs;
     use std::path::{Path, PathBuf};
     use tempfile::tempdir;
@@ -64,11 +171,11 @@ mod tests {
     #[test]
     fn no_in_out_configs_is_ok() {
         assert!(TestGenerator {
-            renderer: TestRenderer {},
+            renderer: TestRenderer::default(),
             config: &Default::default(),
             in_out_configs: vec![]
         }
-        .generate()
+        .generate_from_descriptor_set(&FileDescriptorSet { file: vec![] })
         .is_ok());
     }
 
@@ -90,6 +197,118 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn linked_proto_output_lists_native_files() -> Result<()> {
+        let test_dir = tempdir()?;
+        let input_dir = test_dir.path().join("input");
+        let output_dir = test_dir.path().join("output");
+        let config_path = "test";
+        let native_dir = output_dir.join(config_path);
+        util::create_dir_or_error(&native_dir.join("sub"))?;
+        fs::write(native_dir.join("Native.cs"), "")?;
+        fs::write(native_dir.join("sub").join("Other.cs"), "")?;
+        let mut config = Config::default();
+        config.link_proto_output = true;
+        config.protos = vec![LangConfig {
+            lang: Lang::CSharp,
+            output: native_dir.clone(),
+        }];
+        let mut generator =
+            TestGenerator::with_in_out(&config, &input_dir, &output_dir, &[config_path]);
+        generator.generate_from_descriptor_set(&FileDescriptorSet { file: vec![] })?;
+
+        assert_eq!(
+            *generator.renderer.native_files.borrow(),
+            vec![
+                PathBuf::from("Native.cs"),
+                PathBuf::from("sub").join("Other.cs")
+            ]
+        );
+        assert!(native_dir.join("testfile.test").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn empty_descriptor_set_errors_on_empty() -> Result<()> {
+        let test_dir = tempdir()?;
+        let input_dir = test_dir.path().join("input");
+        let output_dir = test_dir.path().join("output");
+        let descriptor_set = FileDescriptorSet { file: vec![] };
+        let mut config = Config::default();
+        TestGenerator::with_in_out(&config, &input_dir, &output_dir, &["warned"])
+            .generate_from_descriptor_set(&descriptor_set)?;
+
+        config.error_on_empty = true;
+        let error = TestGenerator::with_in_out(&config, &input_dir, &output_dir, &["failed"])
+            .generate_from_descriptor_set(&descriptor_set)
+            .unwrap_err();
+        assert!(error.to_string().contains("no proto files to render"));
+        Ok(())
+    }
+
+    #[test]
+    fn renders_from_buf_image() -> Result<()> {
+        let test_dir = tempdir()?;
+        let input_dir = test_dir.path().join("input");
+        let output_dir = test_dir.path().join("output");
+        let image_path = test_dir.path().join("image.binpb");
+        fs::write(&image_path, buf_image(&["a.proto", "b.proto"]))?;
+        let mut config = Config::default();
+        config.buf_image = Some(image_path);
+        config.error_on_empty = true;
+
+        let descriptor_set = util::load_descriptor_set(&config)?;
+        let names = descriptor_set
+            .file
+            .iter()
+            .map(|file| file.name.as_deref().unwrap_or_default())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a.proto", "b.proto"]);
+
+        TestGenerator::with_in_out(&config, &input_dir, &output_dir, &["image"])
+            .generate_from_descriptor_set(&descriptor_set)?;
+        assert!(output_dir.join("image").join("testfile.test").exists());
+        Ok(())
+    }
+
+    /// Encodes an image like `buf build`, where each file has buf's `ImageFileExtension` as
+    /// field 8042, which is unknown to `FileDescriptorProto`.
+    fn buf_image(file_names: &[&str]) -> Vec<u8> {
+        let mut image = Vec::new();
+        for name in file_names {
+            let mut file = FileDescriptorProto {
+                name: Some(name.to_string()),
+                ..Default::default()
+            }
+            .encode_to_vec();
+            encoding::encode_key(8042, WireType::LengthDelimited, &mut file);
+            encoding::encode_varint(0, &mut file);
+            encoding::encode_key(1, WireType::LengthDelimited, &mut image);
+            encoding::encode_varint(file.len() as u64, &mut image);
+            image.extend(file);
+        }
+        image
+    }
+
+    #[test]
+    fn renders_in_out_sets_in_order() -> Result<()> {
+        let test_dir = tempdir()?;
+        let input_dir = test_dir.path().join("input");
+        let output_dir = test_dir.path().join("output");
+        let descriptor_set = FileDescriptorSet { file: vec![] };
+        let in_out = ["test2", "test0", "test1"];
+        let config = Config::default();
+        let mut generator = TestGenerator::with_in_out(&config, &input_dir, &output_dir, &in_out);
+        generator.generate_from_descriptor_set(&descriptor_set)?;
+
+        let expected = in_out
+            .iter()
+            .map(|path| input_dir.join(path))
+            .collect::<Vec<PathBuf>>();
+        assert_eq!(generator.renderer.loaded, expected);
+        Ok(())
+    }
+
     #[test]
     fn renders_output_for_each_in_out_set() -> Result<()> {
         let test_dir = tempdir()?;
@@ -112,19 +331,70 @@ mod tests {
         Ok(())
     }
 
-    struct TestRenderer {}
+    #[test]
+    fn diff_leaves_output_untouched() -> Result<()> {
+        let test_dir = tempdir()?;
+        let input_dir = test_dir.path().join("input");
+        let output_dir = test_dir.path().join("output");
+        let descriptor_set = FileDescriptorSet { file: vec![] };
+        let mut config = Config::default();
+        config.diff = true;
+        let mut generator = TestGenerator::with_in_out(&config, &input_dir, &output_dir, &["test"]);
+        util::create_dir_or_error(&output_dir.join("test"))?;
+        fs::File::create(output_dir.join("test").join("testfile.test"))?;
+        assert!(!generator.generate_from_descriptor_set(&descriptor_set)?);
+
+        fs::write(output_dir.join("test").join("testfile.test"), "changed")?;
+        assert!(generator.generate_from_descriptor_set(&descriptor_set)?);
+        assert_eq!(
+            fs::read_to_string(output_dir.join("test").join("testfile.test"))?,
+            "changed"
+        );
+        Ok(())
+    }
+
+    #[derive(Default)]
+    struct TestRenderer {
+        loaded: Vec<PathBuf>,
+        native_files: RefCell<Vec<PathBuf>>,
+    }
     impl Render for TestRenderer {
-        fn load(&mut self, _input_root: &Path, _overlays: &[PathBuf]) -> Result<()> {
+        fn load(
+            &mut self,
+            input_root: &Path,
+            _overlays: &[PathBuf],
+            _config_format: Option<ConfigFormat>,
+        ) -> Result<()> {
+            self.loaded.push(input_root.to_path_buf());
             Ok(())
         }
 
         fn reset(&mut self) {}
 
+        fn has_files_to_render(
+            &self,
+            descriptor_set: &FileDescriptorSet,
+            _options: &RenderOptions,
+        ) -> bool {
+            !descriptor_set.file.is_empty()
+        }
+
+        fn check_output_paths(
+            &self,
+            _descriptor_set: &FileDescriptorSet,
+            _output_path: &Path,
+            _options: &RenderOptions,
+        ) -> Result<()> {
+            Ok(())
+        }
+
         fn render(
             &self,
             _descriptor_set: &FileDescriptorSet,
             output_path: &Path,
+            options: &RenderOptions,
         ) -> anyhow::Result<()> {
+            *self.native_files.borrow_mut() = options.native_files.clone();
             fs::File::create(output_path.join("testfile.test"))?;
             Ok(())
         }
@@ -137,7 +407,7 @@ mod tests {
     impl<'a> TestGenerator<'a> {
         fn with_in_out(config: &'a Config, input: &Path, output: &Path, paths: &[&str]) -> Self {
             Self {
-                renderer: TestRenderer {},
+                renderer: TestRenderer::default(),
                 config: &config,
                 in_out_configs: paths
                     .iter()
@@ -0,0 +1,131 @@
+use crate::{util, Config};
+use anyhow::{Context, Result};
+use log::info;
+use prost::Message;
+use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use std::collections::{HashMap, HashSet};
+
+/// With `--since`, the names of the files of `descriptor_set` to render, read against the baseline
+/// descriptor set. None without `--since`, when every file is rendered.
+pub(crate) fn load_changed_files(
+    config: &Config,
+    descriptor_set: &FileDescriptorSet,
+) -> Result<Option<HashSet<String>>> {
+    let baseline = match &config.since {
+        None => return Ok(None),
+        Some(baseline) => util::read_descriptor_set(baseline).context("--since")?,
+    };
+    let changed = changed_files(descriptor_set, &baseline);
+    info!(
+        "--since: rendering {} of {} files which changed or depend on changes",
+        changed.len(),
+        descriptor_set.file.len()
+    );
+    Ok(Some(changed))
+}
+
+/// The names of the files of `descriptor_set` which were added or changed since `baseline`, along
+/// with every file that imports one of them, directly or transitively. Files are compared by name
+/// and serialized descriptor, so changed comments also count.
+pub(crate) fn changed_files(
+    descriptor_set: &FileDescriptorSet,
+    baseline: &FileDescriptorSet,
+) -> HashSet<String> {
+    let baseline = baseline
+        .file
+        .iter()
+        .filter_map(|file| Some((file.name.as_ref()?, file.encode_to_vec())))
+        .collect::<HashMap<_, _>>();
+    let mut changed = descriptor_set
+        .file
+        .iter()
+        .filter(|file| match file.name.as_ref() {
+            None => true,
+            Some(name) => baseline.get(name) != Some(&file.encode_to_vec()),
+        })
+        .map(|file| util::str_or_unknown(&file.name).to_owned())
+        .collect::<HashSet<_>>();
+    loop {
+        let dependents = descriptor_set
+            .file
+            .iter()
+            .filter(|file| !changed.contains(util::str_or_unknown(&file.name)))
+            .filter(|file| imports_any(file, &changed))
+            .map(|file| util::str_or_unknown(&file.name).to_owned())
+            .collect::<Vec<_>>();
+        if dependents.is_empty() {
+            break;
+        }
+        changed.extend(dependents);
+    }
+    changed
+}
+
+fn imports_any(file: &FileDescriptorProto, names: &HashSet<String>) -> bool {
+    file.dependency
+        .iter()
+        .any(|dependency| names.contains(dependency))
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+    use std::collections::HashSet;
+
+    use crate::incremental::changed_files;
+
+    #[test]
+    fn changed_file_and_dependents() {
+        let baseline = FileDescriptorSet {
+            file: vec![
+                file("a.proto", &[]),
+                file("b.proto", &["a.proto"]),
+                file("c.proto", &["b.proto"]),
+                file("d.proto", &["a.proto"]),
+            ],
+        };
+        let mut current = baseline.clone();
+        current.file[1].message_type.push(DescriptorProto {
+            name: Some("Added".to_owned()),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            names(changed_files(&current, &baseline)),
+            vec!["b.proto", "c.proto"]
+        );
+    }
+
+    #[test]
+    fn added_file() {
+        let baseline = FileDescriptorSet {
+            file: vec![file("a.proto", &[])],
+        };
+        let mut current = baseline.clone();
+        current.file.push(file("new.proto", &["a.proto"]));
+
+        assert_eq!(names(changed_files(&current, &baseline)), vec!["new.proto"]);
+    }
+
+    #[test]
+    fn unchanged_set_is_empty() {
+        let baseline = FileDescriptorSet {
+            file: vec![file("a.proto", &[]), file("b.proto", &["a.proto"])],
+        };
+        assert!(changed_files(&baseline, &baseline).is_empty());
+    }
+
+    fn file(name: &str, dependencies: &[&str]) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_owned()),
+            dependency: dependencies.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn names(changed: HashSet<String>) -> Vec<String> {
+        let mut names = changed.into_iter().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{util, InOutConfig};
+use anyhow::Result;
+use std::path::PathBuf;
+
+#[derive(Clone)]
+pub struct InlineTemplateConfig {
+    pub template: String,
+    pub output: PathBuf,
+}
+
+impl InlineTemplateConfig {
+    pub fn from_config(
+        template: &str,
+        output: &str,
+        output_root: Option<&PathBuf>,
+    ) -> Result<Self> {
+        Ok(InlineTemplateConfig {
+            template: template.to_owned(),
+            output: util::path_as_absolute(output, output_root)?,
+        })
+    }
+}
+
+/// Inline templates have no input folder, so the input is empty.
+impl From<&InlineTemplateConfig> for InOutConfig {
+    fn from(x: &InlineTemplateConfig) -> Self {
+        InOutConfig {
+            input: PathBuf::new(),
+            output: x.output.clone(),
+            overlays: vec![],
+        }
+    }
+}
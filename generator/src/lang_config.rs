@@ -4,16 +4,21 @@ use anyhow::Result;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+const LANG_PLACEHOLDER: &str = "{lang}";
+
 pub struct LangConfig {
     pub lang: Lang,
     pub output: PathBuf,
 }
 
 impl LangConfig {
+    /// Any `{lang}` in `output` is replaced with the language name, as in `Lang::as_config`.
     pub fn from_config(lang: &str, output: &str, output_root: Option<&PathBuf>) -> Result<Self> {
-        let output_path = util::path_as_absolute(output, output_root)?;
+        let lang = Lang::from_str(lang)?;
+        let output = output.replace(LANG_PLACEHOLDER, &lang.as_config());
+        let output_path = util::path_as_absolute(&output, output_root)?;
         Ok(LangConfig {
-            lang: Lang::from_str(lang)?,
+            lang,
             output: output_path,
         })
     }
@@ -46,6 +51,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn from_config_with_lang_placeholder() -> Result<()> {
+        let output_root = current_dir()?;
+        let config = LangConfig::from_config(
+            &Lang::Java.as_config(),
+            "generated/{lang}/src",
+            Some(&output_root),
+        )?;
+        assert_eq!(
+            config.output,
+            output_root.join("generated").join("java").join("src")
+        );
+        Ok(())
+    }
+
     #[test]
     fn from_config_with_unsupported_lang() -> Result<()> {
         assert!(LangConfig::from_config(
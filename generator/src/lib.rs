@@ -1,14 +1,21 @@
 #![forbid(unsafe_code)]
 
+mod checksums;
 mod config;
+mod config_format;
+mod deprecation;
+mod diff;
 mod dir_init;
 mod encode;
 mod encode_config;
 mod idl;
 mod in_out_config;
 mod in_out_generator;
+mod incremental;
+mod inline_template_config;
 mod lang;
 mod lang_config;
+mod lint;
 mod protoc;
 mod render;
 mod renderer;
@@ -16,14 +23,23 @@ mod script_config;
 mod util;
 
 use crate::dir_init::{initialize_script_dir, initialize_template_dir};
-use crate::renderer::DEFAULT_CONFIG_FILE_NAME;
+use crate::render::{Render, RenderOptions};
+use crate::renderer::{MapDetection, DEFAULT_CONFIG_FILE_NAME};
 use crate::util::DisplayNormalized;
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 pub use config::Config;
+pub use config_format::ConfigFormat;
 pub use idl::Idl;
 pub use in_out_config::InOutConfig;
 pub use lang::Lang;
 pub use lang_config::LangConfig;
+use log::warn;
+pub use prost_types::FileDescriptorSet;
+pub use renderer::context;
+pub use renderer::scripted::ScriptedRenderer;
+pub use renderer::template::{TemplateRenderer, TeraRenderer};
+pub use renderer::{Renderer, RendererConfig, TemplateEngine};
+use std::path::Path;
 
 pub fn generate() -> Result<()> {
     env_logger::init();
@@ -36,7 +52,26 @@ pub fn generate_with_config(config: Config) -> Result<()> {
     generate_internal(&config)
 }
 
+/// Render an already-built descriptor set directly to `output`, skipping protoc and CLI parsing
+/// entirely. The renderer should already have its templates or scripts loaded, and `config` is
+/// used in place of any config file it may have loaded.
+pub fn render_descriptor_set<R: Renderer>(
+    descriptor_set: &FileDescriptorSet,
+    config: &RendererConfig,
+    mut renderer: R,
+    output: &Path,
+) -> Result<()> {
+    renderer.set_config(config.clone());
+    util::create_dir_or_error(output)?;
+    let options = RenderOptions::default();
+    renderer.check_output_paths(descriptor_set, output, &options)?;
+    renderer.render(descriptor_set, output, &options)
+}
+
 fn generate_internal(config: &Config) -> Result<()> {
+    if config.list_languages {
+        return list_languages();
+    }
     if let Some(init_target) = &config.init_script_target {
         return initialize_script_dir(&init_target);
     }
@@ -46,11 +81,176 @@ fn generate_internal(config: &Config) -> Result<()> {
     match config.idl {
         Idl::Proto => {
             protoc::generate(&config)?;
-            renderer::template::generate(&config)?;
-            renderer::scripted::generate(&config)?;
-            encode::generate(&config)?;
+            if config.renders_descriptor_set() {
+                let descriptor_set = util::load_descriptor_set(&config)?;
+                check_descriptor_set(&config, &descriptor_set)?;
+                lint::generate(&config, &descriptor_set)?;
+                // Every generator prints its diff before --diff fails.
+                let mut differs = renderer::template::generate(&config, &descriptor_set)?;
+                differs |= renderer::descriptor_set::generate(&config, &descriptor_set)?;
+                differs |= renderer::scripted::generate(&config, &descriptor_set)?;
+                if differs {
+                    return Err(anyhow!(
+                        "--diff: generated output differs from the existing files."
+                    ));
+                }
+            }
+            // --diff only compares rendered output, and leaves everything on disk as it was.
+            if !config.diff {
+                encode::generate(&config)?;
+                checksums::generate(&config)?;
+            }
         }
     };
 
     Ok(())
 }
+
+/// Checks run once on the loaded descriptor set, before anything is linted or rendered from it.
+fn check_descriptor_set(config: &Config, descriptor_set: &FileDescriptorSet) -> Result<()> {
+    report_empty_descriptor_set(descriptor_set, config.error_on_empty)?;
+    if config.fail_on_deprecated {
+        // The check runs once for every renderer, so hand-written map entries are recognized
+        // whatever each renderer's `map_detection` is.
+        deprecation::check_deprecated_usage(descriptor_set, MapDetection::Heuristic)
+            .context("--fail-on-deprecated")?;
+    }
+    Ok(())
+}
+
+/// Usually means the inputs didn't match any proto files.
+fn report_empty_descriptor_set(
+    descriptor_set: &FileDescriptorSet,
+    error_on_empty: bool,
+) -> Result<()> {
+    if !descriptor_set.file.is_empty() {
+        return Ok(());
+    }
+    let message = "The descriptor set has no proto files. Check the inputs.";
+    if error_on_empty {
+        return Err(anyhow!(message));
+    }
+    warn!("{}", message);
+    Ok(())
+}
+
+fn list_languages() -> Result<()> {
+    for lang in protoc::supported_languages() {
+        println!("{}", lang.as_config());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        check_descriptor_set, render_descriptor_set, report_empty_descriptor_set, Config,
+        RendererConfig, TemplateRenderer,
+    };
+    use anyhow::Result;
+    use prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MessageOptions,
+    };
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn render_in_memory_descriptor_set() -> Result<()> {
+        let input = tempdir()?;
+        let output = tempdir()?;
+        fs::write(input.path().join("file.hbs"), "hello {{source_file}}")?;
+        let mut renderer = TemplateRenderer::new();
+        renderer.load_templates(input.path())?;
+
+        let config = RendererConfig {
+            file_extension: "txt".to_owned(),
+            generated_header: Some(vec![]),
+            ..Default::default()
+        };
+        let descriptor_set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("test.proto".to_owned()),
+                ..Default::default()
+            }],
+        };
+        render_descriptor_set(&descriptor_set, &config, renderer, output.path())?;
+
+        let contents = fs::read_to_string(output.path().join("test.txt"))?;
+        assert_eq!(contents, "hello test.proto");
+        Ok(())
+    }
+
+    #[test]
+    fn template_knows_its_output_path() -> Result<()> {
+        let input = tempdir()?;
+        let output = tempdir()?;
+        fs::write(input.path().join("file.hbs"), "{{output_path}}")?;
+        fs::write(input.path().join("metadata.hbs"), "{{output_path}}")?;
+        let mut renderer = TemplateRenderer::new();
+        renderer.load_templates(input.path())?;
+
+        let config = RendererConfig {
+            file_extension: "txt".to_owned(),
+            generated_header: Some(vec![]),
+            ..Default::default()
+        };
+        let descriptor_set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("sub/SomeFile.proto".to_owned()),
+                ..Default::default()
+            }],
+        };
+        render_descriptor_set(&descriptor_set, &config, renderer, output.path())?;
+
+        let file_path = output.path().join("sub").join("some-file.txt");
+        assert_eq!(fs::read_to_string(file_path)?, "sub/some-file.txt");
+        let metadata_path = output.path().join("sub").join("metadata.txt");
+        assert_eq!(fs::read_to_string(metadata_path)?, "sub/metadata.txt");
+        Ok(())
+    }
+
+    #[test]
+    fn empty_descriptor_set_warns_or_errors() {
+        let descriptor_set = FileDescriptorSet { file: vec![] };
+        assert!(report_empty_descriptor_set(&descriptor_set, false).is_ok());
+        let error = report_empty_descriptor_set(&descriptor_set, true).unwrap_err();
+        assert!(error.to_string().contains("no proto files"));
+    }
+
+    #[test]
+    fn fail_on_deprecated_checks_field_types() -> Result<()> {
+        let descriptor_set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("test.proto".to_owned()),
+                message_type: vec![
+                    DescriptorProto {
+                        name: Some("Old".to_owned()),
+                        options: Some(MessageOptions {
+                            deprecated: Some(true),
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    },
+                    DescriptorProto {
+                        name: Some("New".to_owned()),
+                        field: vec![FieldDescriptorProto {
+                            name: Some("old".to_owned()),
+                            type_name: Some(".Old".to_owned()),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            }],
+        };
+        let mut config = Config::default();
+        check_descriptor_set(&config, &descriptor_set)?;
+
+        config.fail_on_deprecated = true;
+        let error = check_descriptor_set(&config, &descriptor_set).unwrap_err();
+        assert!(format!("{:#}", error).contains("New.old"));
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use crate::renderer::case::Case;
+use crate::renderer::{find_existing_config_path, load_config};
+use crate::{util, Config};
+use anyhow::{bail, Context, Result};
+use log::info;
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Naming rules checked by `--lint`, read from the `lint` section of a template or script config.
+/// Each rule is the case a name must already be in, or null to skip the check.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LintConfig {
+    /// default: `UpperCamelCase`
+    pub message_name: Option<Case>,
+    /// default: `lower_snake_case`
+    pub field_name: Option<Case>,
+    /// default: `UpperCamelCase`
+    pub enum_name: Option<Case>,
+    /// default: `UPPER_SNAKE_CASE`
+    pub enum_value_name: Option<Case>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            message_name: Some(Case::UpperCamel),
+            field_name: Some(Case::LowerSnake),
+            enum_name: Some(Case::UpperCamel),
+            enum_value_name: Some(Case::UpperSnake),
+        }
+    }
+}
+
+/// With `--lint`, checks the names in the descriptor set against the `lint` rules of every
+/// --template and --script config, or the default rules if there are none, and errors with every
+/// violation found.
+pub fn generate(config: &Config, descriptor_set: &FileDescriptorSet) -> Result<()> {
+    if !config.lint {
+        return Ok(());
+    }
+    let mut violations = BTreeSet::new();
+    for lint_config in collect_lint_configs(config)? {
+        violations.extend(lint_descriptor_set(descriptor_set, &lint_config));
+    }
+    if !violations.is_empty() {
+        let violations = violations.into_iter().collect::<Vec<_>>();
+        bail!(
+            "--lint found {} naming violation(s):\n{}",
+            violations.len(),
+            violations.join("\n")
+        );
+    }
+    info!("--lint found no naming violations");
+    Ok(())
+}
+
+fn collect_lint_configs(config: &Config) -> Result<Vec<LintConfig>> {
+    let mut lint_configs = Vec::new();
+    for in_out in config.templates.iter().chain(&config.scripts) {
+        let path = find_existing_config_path(&in_out.input)?;
+        let renderer_config = load_config(&path, &[], config.config_format).context("--lint")?;
+        lint_configs.push(renderer_config.lint);
+    }
+    if lint_configs.is_empty() {
+        lint_configs.push(LintConfig::default());
+    }
+    Ok(lint_configs)
+}
+
+/// Every violation of `lint_config` in the descriptor set, as `file: kind 'name' ...` lines.
+pub fn lint_descriptor_set(
+    descriptor_set: &FileDescriptorSet,
+    lint_config: &LintConfig,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    for file in &descriptor_set.file {
+        let mut linter = Linter {
+            file_name: util::str_or_unknown(&file.name),
+            config: lint_config,
+            violations: &mut violations,
+        };
+        for message in &file.message_type {
+            linter.lint_message(message);
+        }
+        for proto in &file.enum_type {
+            linter.lint_enum(proto);
+        }
+    }
+    violations
+}
+
+struct Linter<'a> {
+    file_name: &'a str,
+    config: &'a LintConfig,
+    violations: &'a mut Vec<String>,
+}
+
+impl Linter<'_> {
+    fn lint_message(&mut self, message: &DescriptorProto) {
+        // Map entries are named by protoc.
+        if message.options.as_ref().and_then(|o| o.map_entry) == Some(true) {
+            return;
+        }
+        self.check("message", &message.name, self.config.message_name);
+        for field in &message.field {
+            self.check("field", &field.name, self.config.field_name);
+        }
+        for nested in &message.nested_type {
+            self.lint_message(nested);
+        }
+        for proto in &message.enum_type {
+            self.lint_enum(proto);
+        }
+    }
+
+    fn lint_enum(&mut self, proto: &EnumDescriptorProto) {
+        self.check("enum", &proto.name, self.config.enum_name);
+        for value in &proto.value {
+            self.check("enum value", &value.name, self.config.enum_value_name);
+        }
+    }
+
+    fn check(&mut self, kind: &str, name: &Option<String>, case: Option<Case>) {
+        let (name, case) = match (name, case) {
+            (Some(name), Some(case)) => (name, case),
+            _ => return,
+        };
+        let expected = case.rename(name);
+        if &expected != name {
+            self.violations.push(format!(
+                "{}: {} '{}' is not {:?} case, expected '{}'",
+                self.file_name, kind, name, case, expected
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, FileDescriptorSet,
+    };
+
+    use crate::lint::{lint_descriptor_set, LintConfig};
+
+    #[test]
+    fn conforming_set_passes() {
+        let set = descriptor_set("SomeMessage", "some_field", "SOME_VALUE");
+        assert!(lint_descriptor_set(&set, &LintConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn violating_field_name_fails() {
+        let set = descriptor_set("SomeMessage", "someField", "SOME_VALUE");
+        let violations = lint_descriptor_set(&set, &LintConfig::default());
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("file.proto"), "{}", violations[0]);
+        assert!(
+            violations[0].contains("field 'someField'"),
+            "{}",
+            violations[0]
+        );
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let set = descriptor_set("some_message", "someField", "someValue");
+        assert_eq!(lint_descriptor_set(&set, &LintConfig::default()).len(), 3);
+    }
+
+    #[test]
+    fn disabled_rule_is_skipped() {
+        let set = descriptor_set("SomeMessage", "someField", "SOME_VALUE");
+        let config = LintConfig {
+            field_name: None,
+            ..Default::default()
+        };
+        assert!(lint_descriptor_set(&set, &config).is_empty());
+    }
+
+    fn descriptor_set(message: &str, field: &str, value: &str) -> FileDescriptorSet {
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("file.proto".to_owned()),
+                message_type: vec![DescriptorProto {
+                    name: Some(message.to_owned()),
+                    field: vec![FieldDescriptorProto {
+                        name: Some(field.to_owned()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                enum_type: vec![EnumDescriptorProto {
+                    name: Some("SomeEnum".to_owned()),
+                    value: vec![EnumValueDescriptorProto {
+                        name: Some(value.to_owned()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+}
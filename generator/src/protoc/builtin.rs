@@ -39,22 +39,60 @@ fn register_builtin(config: &Config, protoc: &mut Protoc) -> Result<()> {
     Ok(())
 }
 
+/// Languages with their own includes can't share the main protoc invocation, so each is run in a
+/// separate protoc pass with the global includes plus its own.
+pub fn execute_lang_scoped(config: &Config, input_files: &[String]) -> Result<()> {
+    for proto in config
+        .protos
+        .iter()
+        .filter(|c| SUPPORTED_LANGUAGES.contains(&c.lang) && is_lang_scoped(config, c))
+    {
+        let mut protoc = Protoc::for_lang(config, &proto.lang)?;
+        protoc.add_args(&mut vec![proto_output(proto)?]);
+        protoc.add_input_files(&mut input_files.to_vec());
+        protoc
+            .execute(None)
+            .with_context(|| format!("protoc pass for '{}'", proto.lang.as_config()))?;
+    }
+    Ok(())
+}
+
+/// True if any language is generated by the main protoc invocation.
+pub fn has_any_unscoped_language(config: &Config) -> bool {
+    config
+        .protos
+        .iter()
+        .any(|c| SUPPORTED_LANGUAGES.contains(&c.lang) && !is_lang_scoped(config, c))
+}
+
 fn collect_proto_outputs(config: &Config) -> Result<Vec<String>> {
     let mut args = Vec::new();
     for proto in &config.protos {
-        if !SUPPORTED_LANGUAGES.contains(&proto.lang) {
+        if !SUPPORTED_LANGUAGES.contains(&proto.lang) || is_lang_scoped(config, proto) {
             continue;
         }
-        let arg = [proto.lang.as_config().as_str(), "_out"].concat();
-        let value = proto
-            .output
-            .to_str()
-            .ok_or(anyhow!("Output path is invalid: {:?}", proto.output))?;
-        args.push(arg_with_value(&arg, value));
+        args.push(proto_output(proto)?);
     }
     Ok(args)
 }
 
+fn proto_output(proto: &LangConfig) -> Result<String> {
+    let arg = [proto.lang.as_config().as_str(), "_out"].concat();
+    let value = proto
+        .output
+        .to_str()
+        .ok_or(anyhow!("Output path is invalid: {:?}", proto.output))?;
+    Ok(arg_with_value(&arg, value))
+}
+
+fn is_lang_scoped(config: &Config, proto: &LangConfig) -> bool {
+    config
+        .lang_includes
+        .get(&proto.lang)
+        .map(|includes| !includes.is_empty())
+        .unwrap_or(false)
+}
+
 fn has_any_supported_language(config: &Config) -> bool {
     let count = config
         .protos
@@ -69,11 +107,14 @@ mod tests {
     use crate::lang::Lang;
     use crate::lang_config::LangConfig;
     use crate::protoc::builtin::collect_proto_outputs;
+    use crate::protoc::builtin::execute_lang_scoped;
     use crate::protoc::builtin::has_any_supported_language;
     use crate::protoc::protoc::arg_with_value;
     use crate::Config;
     use anyhow::Result;
+    use std::fs;
     use std::path::PathBuf;
+    use tempfile::tempdir;
 
     #[test]
     fn proto_output() -> Result<()> {
@@ -94,6 +135,26 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn lang_scoped_output_not_in_main_pass() -> Result<()> {
+        let mut config = Config::default();
+        config
+            .lang_includes
+            .insert(Lang::Cpp, vec!["cpp/include".to_owned()]);
+        config.protos.push(LangConfig {
+            lang: Lang::Cpp,
+            output: PathBuf::from("cpp/path"),
+        });
+        config.protos.push(LangConfig {
+            lang: Lang::CSharp,
+            output: PathBuf::from("csharp/path"),
+        });
+        let args = collect_proto_outputs(&config)?;
+        assert!(!args.contains(&arg_with_value("cpp_out", "cpp/path")));
+        assert_arg_pair_exists(&args, "csharp_out", "csharp/path");
+        Ok(())
+    }
+
     #[test]
     fn ignores_unsupported_languages() -> Result<()> {
         let mut config = Config::default();
@@ -127,6 +188,38 @@ mod tests {
         assert!(!has_any_supported_language(&config));
     }
 
+    #[test]
+    fn lang_scoped_pass_uses_lang_includes() -> Result<()> {
+        let root = tempdir()?;
+        let input = root.path().join("input");
+        let include = root.path().join("cpp_include");
+        let output = root.path().join("out");
+        for dir in [&input, &include, &output] {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            input.join("main.proto"),
+            "syntax = \"proto3\";\nimport \"dep.proto\";\nmessage Main { Dep dep = 1; }\n",
+        )?;
+        fs::write(
+            include.join("dep.proto"),
+            "syntax = \"proto3\";\nmessage Dep {}\n",
+        )?;
+
+        let mut config = Config::default();
+        config.input = vec![input];
+        config
+            .lang_includes
+            .insert(Lang::Cpp, vec![include.to_str().unwrap().to_owned()]);
+        config.protos.push(LangConfig {
+            lang: Lang::Cpp,
+            output: output.clone(),
+        });
+        execute_lang_scoped(&config, &["main.proto".to_owned()])?;
+        assert!(output.join("main.pb.h").exists());
+        Ok(())
+    }
+
     /// Checks for --arg=value and --arg value. Asserts if neither are found.
     fn assert_arg_pair_exists(args: &Vec<String>, first: &str, second: &str) {
         if args.contains(&arg_with_value(first, second)) {
@@ -1,13 +1,35 @@
 use crate::{util, Config};
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::debug;
+use std::collections::HashMap;
 use std::path::Path;
 use util::DisplayNormalized;
 use walkdir::WalkDir;
 
+/// Proto files in every input root, relative to the root they were found in. These are the names
+/// protoc knows them by, so the same relative path in two roots is an error.
 pub fn collect(config: &Config) -> Result<Vec<String>> {
     let mut inputs = Vec::new();
-    for entry in WalkDir::new(&config.input).follow_links(false).into_iter() {
+    let mut roots = HashMap::<String, &Path>::new();
+    for root in &config.input {
+        for input in collect_in_root(root)? {
+            if let Some(first) = roots.insert(input.clone(), root) {
+                bail!(
+                    "Input '{}' exists in both '{}' and '{}'.",
+                    input,
+                    first.display_normalized(),
+                    root.display_normalized(),
+                );
+            }
+            inputs.push(input);
+        }
+    }
+    Ok(inputs)
+}
+
+fn collect_in_root(root: &Path) -> Result<Vec<String>> {
+    let mut inputs = Vec::new();
+    for entry in WalkDir::new(root).follow_links(false).into_iter() {
         let entry = entry.context("Failed to collect input.")?;
         if entry.file_type().is_dir() {
             continue;
@@ -21,7 +43,7 @@ pub fn collect(config: &Config) -> Result<Vec<String>> {
         );
         let input = entry
             .path()
-            .strip_prefix(&config.input)?
+            .strip_prefix(root)?
             .to_str()
             .ok_or(anyhow!("Failed to convert path to str: {:?}", entry.path()))?
             .to_owned();
@@ -76,6 +98,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn collects_from_multiple_inputs() -> Result<()> {
+        let dir0 = tempdir()?;
+        let dir1 = tempdir()?;
+        create_files_at(dir0.path(), &["aaa.proto", "a/bbb.proto"])?;
+        create_files_at(dir1.path(), &["ccc.proto", "a/ddd.proto"])?;
+        let mut config = Config::default();
+        config.input = vec![dir0.path().to_path_buf(), dir1.path().to_path_buf()];
+        let mut files = input::collect(&config)?;
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["a/bbb.proto", "a/ddd.proto", "aaa.proto", "ccc.proto"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn same_file_in_multiple_inputs_errors() -> Result<()> {
+        let dir0 = tempdir()?;
+        let dir1 = tempdir()?;
+        create_files_at(dir0.path(), &["a/aaa.proto"])?;
+        create_files_at(dir1.path(), &["a/aaa.proto"])?;
+        let mut config = Config::default();
+        config.input = vec![dir0.path().to_path_buf(), dir1.path().to_path_buf()];
+        assert!(input::collect(&config).is_err());
+        Ok(())
+    }
+
     mod paths_are_relative {
         use crate::protoc::input;
         use crate::protoc::input::tests::{
@@ -168,7 +219,7 @@ mod tests {
 
     fn config_with_input(path: &Path) -> Config {
         let mut config = Config::default();
-        config.input = path.to_path_buf();
+        config.input = vec![path.to_path_buf()];
         config
     }
 }
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 
 pub use crate::protoc::protoc::arg_with_value;
 pub use crate::protoc::protoc::Protoc;
-use crate::{Config, Lang};
+use crate::{util, Config, Lang};
 
 mod builtin;
 pub mod input;
@@ -10,13 +10,38 @@ mod proto_rust;
 mod protoc;
 
 pub fn generate(config: &Config) -> Result<()> {
-    if !config.requires_descriptor_set() && config.protos.is_empty() {
+    if !config.requires_descriptor_set()
+        && config.protos.is_empty()
+        && config.plugin_outs.is_empty()
+    {
         return Ok(());
     }
+    // The buf image is used in place of the descriptor set, and can't be used with --proto.
+    if config.buf_image.is_some() {
+        return Ok(());
+    }
+    let input_files = input::collect(config).context("Failed to collect input files.")?;
     let mut protoc = Protoc::new(config)?;
-    protoc.add_input_files(&mut input::collect(config).context("Failed to collect input files.")?);
+    protoc.add_input_files(&mut input_files.clone());
+    // --diff leaves every output directory untouched, so protoc writes only the descriptor set that
+    // renderers compare from.
+    if config.diff {
+        if config.renders_descriptor_set() {
+            protoc.execute(None)?;
+        }
+        return Ok(());
+    }
     builtin::register(config, &mut protoc)?;
-    protoc.execute(None)?;
+    for (_, output) in &config.plugin_outs {
+        util::create_dir_or_error(output)?;
+    }
+    if config.requires_descriptor_set()
+        || builtin::has_any_unscoped_language(config)
+        || !config.plugin_outs.is_empty()
+    {
+        protoc.execute(None)?;
+    }
+    builtin::execute_lang_scoped(config, &input_files)?;
     proto_rust::generate(config)?;
     Ok(())
 }
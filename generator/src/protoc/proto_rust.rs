@@ -25,6 +25,6 @@ pub fn generate(config: &Config) -> Result<()> {
     for extra_arg in &config.extra_protoc_args {
         prost_config.protoc_arg(util::unquote_arg(extra_arg));
     }
-    prost_config.compile_protos(&Vec::<PathBuf>::new(), &[&config.input])?;
+    prost_config.compile_protos(&Vec::<PathBuf>::new(), &config.input)?;
     Ok(())
 }
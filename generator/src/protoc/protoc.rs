@@ -1,25 +1,36 @@
-use crate::{util, Config};
+use crate::{util, Config, Lang};
 use anyhow::{anyhow, bail, Context, Result};
 use log::info;
 use std::fs;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
 use util::DisplayNormalized;
 
 const PROTOC_ARG_PROTO_PATH: &str = "proto_path";
 const PROTOC_ARG_DESCRIPTOR_SET_OUT: &str = "descriptor_set_out";
 const PROTOC_ARG_INCLUDE_SOURCE_INFO: &str = "include_source_info";
+const PROTOC_ARG_PLUGIN: &str = "plugin";
+const PROTOC_ARG_EXPERIMENTAL_EDITIONS: &str = "experimental_editions";
+const PLUGIN_EXECUTABLE_PREFIX: &str = "protoc-gen-";
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Longer command lines are passed to protoc in an `@argfile` instead. Conservatively below the
+/// smallest common limit, 8191 characters for the Windows command prompt.
+const MAX_COMMAND_LINE_LENGTH: usize = 8000;
 
 /// Manages collecting args and the invocation of `protoc`, the protobuf compiler.
 pub struct Protoc {
     args: Vec<String>,
     input_files: Vec<String>,
+    timeout: Option<Duration>,
 }
 
 impl Protoc {
     pub fn new(config: &Config) -> Result<Protoc> {
-        let mut args = collect_proto_paths(config)?;
+        let mut args = collect_proto_paths(config, None)?;
         let descriptor_set_path = config
             .descriptor_set_path
             .to_str()
@@ -32,10 +43,29 @@ impl Protoc {
             ));
             args.push(["--", PROTOC_ARG_INCLUDE_SOURCE_INFO].concat());
         }
+        // Plugins write into their output directories, which --diff leaves untouched.
+        if !config.diff {
+            args.append(&mut collect_plugin_args(config)?);
+        }
+        args.append(&mut collect_feature_args(config));
+        args.append(&mut collect_extra_protoc_args(config));
+        Ok(Self {
+            args,
+            input_files: Vec::new(),
+            timeout: config.protoc_timeout,
+        })
+    }
+
+    /// Protoc without a descriptor set output, with the `lang_includes` of `lang` added to the
+    /// usual proto paths. Used for languages which have their own additional includes.
+    pub fn for_lang(config: &Config, lang: &Lang) -> Result<Protoc> {
+        let mut args = collect_proto_paths(config, Some(lang))?;
+        args.append(&mut collect_feature_args(config));
         args.append(&mut collect_extra_protoc_args(config));
         Ok(Self {
             args,
             input_files: Vec::new(),
+            timeout: config.protoc_timeout,
         })
     }
 
@@ -58,9 +88,17 @@ impl Protoc {
             util::normalize_slashes(temp_args.join(" ")),
         );
 
+        let args = self
+            .args
+            .iter()
+            .map(String::as_str)
+            .chain(temp_args.iter().copied())
+            .map(str::to_owned)
+            .collect::<Vec<String>>();
+        // The argfile must outlive the protoc process.
+        let (args, _argfile) = command_args(args)?;
         let mut child = Command::new(&protoc_path)
-            .args(&self.args)
-            .args(temp_args)
+            .args(&args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
@@ -80,11 +118,11 @@ impl Protoc {
             });
         }
 
-        let output = child.wait_with_output()?;
-        if output.status.success() {
-            Ok(output.stdout)
+        let (status, stdout) = wait_with_timeout(child, self.timeout)?;
+        if status.success() {
+            Ok(stdout)
         } else {
-            Err(anyhow!("protoc exited with status {}", output.status))
+            Err(anyhow!("protoc exited with status {}", status))
         }
     }
 
@@ -98,24 +136,128 @@ impl Protoc {
     }
 }
 
-fn collect_proto_paths(config: &Config) -> Result<Vec<String>> {
-    if let Err(_) = fs::read_dir(&config.input) {
-        bail!(
-            "Invalid input: could not find the directory located at path '{:?}'.",
-            config.input
-        );
+/// Returns `args` as is, or if they would make the command line too long, a single `@argfile`
+/// argument along with the file, which protoc reads with one argument per line.
+fn command_args(args: Vec<String>) -> Result<(Vec<String>, Option<NamedTempFile>)> {
+    let length = args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+    if length <= MAX_COMMAND_LINE_LENGTH {
+        return Ok((args, None));
+    }
+    let mut argfile = NamedTempFile::new().context("Failed to create protoc argfile.")?;
+    for arg in &args {
+        if arg.contains('\n') {
+            bail!(
+                "protoc argument contains a newline, which an argfile can't pass: {}",
+                arg
+            );
+        }
+        writeln!(argfile, "{}", arg)?;
     }
-    let input = match config.input.to_str() {
-        None => bail!("Invalid input: Could not parse path to string."),
-        Some(input) => input,
+    argfile.flush()?;
+    let path = argfile
+        .path()
+        .to_str()
+        .ok_or(anyhow!("Temporary argfile path is not valid unicode."))?;
+    info!(
+        "command line is {} characters, passing {} args to protoc in argfile: {}",
+        length,
+        args.len(),
+        path
+    );
+    Ok((vec![["@", path].concat()], Some(argfile)))
+}
+
+/// Waits for `child` to exit and collects its stdout. If it's still running after `timeout`, it is
+/// killed and an error is returned.
+fn wait_with_timeout(mut child: Child, timeout: Option<Duration>) -> Result<(ExitStatus, Vec<u8>)> {
+    let timeout = match timeout {
+        None => {
+            let output = child.wait_with_output()?;
+            return Ok((output.status, output.stdout));
+        }
+        Some(timeout) => timeout,
     };
-    let mut args = vec![arg_with_value(PROTOC_ARG_PROTO_PATH, input)];
-    for include in &config.includes {
+    // Close stdin like `wait_with_output` does, so a process reading it to EOF doesn't hang.
+    drop(child.stdin.take());
+    // Read stdout while polling so protoc can't block on a full pipe.
+    let mut stdout = child.stdout.take();
+    let reader = thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(stdout) = &mut stdout {
+            stdout.read_to_end(&mut bytes)?;
+        }
+        Ok::<_, std::io::Error>(bytes)
+    });
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            // Best effort, the process may have exited in the meantime.
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("protoc did not finish within {:?} and was killed.", timeout);
+        }
+        thread::sleep(TIMEOUT_POLL_INTERVAL);
+    };
+    let stdout = reader
+        .join()
+        .map_err(|_| anyhow!("Failed to read protoc output."))??;
+    Ok((status, stdout))
+}
+
+/// `--proto_path` for every input and include, followed by the `lang_includes` of `lang`.
+fn collect_proto_paths(config: &Config, lang: Option<&Lang>) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    for input in &config.input {
+        if let Err(_) = fs::read_dir(input) {
+            bail!(
+                "Invalid input: could not find the directory located at path '{:?}'.",
+                input
+            );
+        }
+        let input = match input.to_str() {
+            None => bail!("Invalid input: Could not parse path to string."),
+            Some(input) => input,
+        };
+        args.push(arg_with_value(PROTOC_ARG_PROTO_PATH, input));
+    }
+    let lang_includes = lang
+        .and_then(|lang| config.lang_includes.get(lang))
+        .into_iter()
+        .flatten();
+    for include in config.includes.iter().chain(lang_includes) {
         args.push(arg_with_value(PROTOC_ARG_PROTO_PATH, include));
     }
     Ok(args)
 }
 
+/// `--plugin` for every registered plugin, and `--NAME_out` for every plugin output.
+fn collect_plugin_args(config: &Config) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    for (name, path) in &config.plugins {
+        let value = [PLUGIN_EXECUTABLE_PREFIX, name, "=", path].concat();
+        args.push(arg_with_value(PROTOC_ARG_PLUGIN, &value));
+    }
+    for (name, output) in &config.plugin_outs {
+        let output = output
+            .to_str()
+            .ok_or(anyhow!("Output path is invalid: {:?}", output))?;
+        args.push(arg_with_value(&[name, "_out"].concat(), output));
+    }
+    Ok(args)
+}
+
+/// Flags enabling protoc features which are off by default, i.e. `--editions`.
+fn collect_feature_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    if config.editions {
+        args.push(["--", PROTOC_ARG_EXPERIMENTAL_EDITIONS].concat());
+    }
+    args
+}
+
 fn collect_extra_protoc_args(config: &Config) -> Vec<String> {
     config
         .extra_protoc_args
@@ -138,19 +280,24 @@ pub fn arg_with_value(arg: &str, value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use crate::protoc::protoc::{
-        arg_with_value, collect_extra_protoc_args, collect_proto_paths, PROTOC_ARG_PROTO_PATH,
+        arg_with_value, collect_extra_protoc_args, collect_proto_paths, command_args, Protoc,
+        MAX_COMMAND_LINE_LENGTH, PROTOC_ARG_DESCRIPTOR_SET_OUT, PROTOC_ARG_PROTO_PATH,
     };
-    use crate::Config;
+    use crate::{Config, Lang};
     use anyhow::Result;
-    use std::env;
+    use prost::Message;
+    use prost_types::FileDescriptorSet;
     use std::path::PathBuf;
+    use std::time::Duration;
+    use std::{env, fs};
+    use tempfile::tempdir;
 
     #[test]
     fn proto_path() -> Result<()> {
         let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
         let mut config = Config::default();
-        config.input = PathBuf::from(&input);
-        let proto_paths = collect_proto_paths(&config)?;
+        config.input = vec![PathBuf::from(&input)];
+        let proto_paths = collect_proto_paths(&config, None)?;
         assert_eq!(
             proto_paths,
             vec![arg_with_value(PROTOC_ARG_PROTO_PATH, &input)]
@@ -158,12 +305,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn proto_path_for_each_input() -> Result<()> {
+        let input0 = tempdir()?;
+        let input1 = tempdir()?;
+        let mut config = Config::default();
+        config.input = vec![input0.path().to_path_buf(), input1.path().to_path_buf()];
+        let proto_paths = collect_proto_paths(&config, None)?;
+        assert_eq!(
+            proto_paths,
+            vec![
+                arg_with_value(PROTOC_ARG_PROTO_PATH, input0.path().to_str().unwrap()),
+                arg_with_value(PROTOC_ARG_PROTO_PATH, input1.path().to_str().unwrap()),
+            ]
+        );
+        Ok(())
+    }
+
     #[test]
     fn proto_path_missing() {
         let input = "definitely/missing/path";
         let mut config = Config::default();
-        config.input = PathBuf::from(input);
-        assert!(collect_proto_paths(&config).is_err());
+        config.input = vec![PathBuf::from(input)];
+        assert!(collect_proto_paths(&config, None).is_err());
     }
 
     #[test]
@@ -182,15 +346,231 @@ mod tests {
     fn collects_extra_includes() -> Result<()> {
         let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
         let mut config = Config::default();
-        config.input = PathBuf::from(&input);
+        config.input = vec![PathBuf::from(&input)];
         config.includes = vec!["include0".to_owned(), "include1".to_owned()];
-        let args = collect_proto_paths(&config)?;
+        let args = collect_proto_paths(&config, None)?;
         for include in config.includes {
             assert!(args.contains(&arg_with_value(PROTOC_ARG_PROTO_PATH, &include)));
         }
         Ok(())
     }
 
+    #[test]
+    fn for_lang_adds_lang_includes() -> Result<()> {
+        let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
+        let mut config = Config::default();
+        config.input = vec![PathBuf::from(&input)];
+        config.includes = vec!["global".to_owned()];
+        config
+            .lang_includes
+            .insert(Lang::Cpp, vec!["scoped".to_owned()]);
+        let scoped_include = arg_with_value(PROTOC_ARG_PROTO_PATH, "scoped");
+
+        let protoc = Protoc::for_lang(&config, &Lang::Cpp)?;
+        assert_eq!(
+            protoc.args[1..3],
+            [
+                arg_with_value(PROTOC_ARG_PROTO_PATH, "global"),
+                scoped_include.clone()
+            ]
+        );
+
+        let protoc = Protoc::for_lang(&config, &Lang::Java)?;
+        assert!(!protoc.args.contains(&scoped_include));
+        let protoc = Protoc::new(&config)?;
+        assert!(!protoc.args.contains(&scoped_include));
+        Ok(())
+    }
+
+    #[test]
+    fn passes_plugin_args() -> Result<()> {
+        let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
+        let output = tempdir()?;
+        let mut config = Config::default();
+        config.input = vec![PathBuf::from(&input)];
+        config.plugins = vec![("foo".to_owned(), "bin/protoc-gen-foo".to_owned())];
+        config.plugin_outs = vec![("foo".to_owned(), output.path().to_path_buf())];
+
+        let protoc = Protoc::new(&config)?;
+        assert!(protoc
+            .args
+            .contains(&"--plugin=protoc-gen-foo=bin/protoc-gen-foo".to_owned()));
+        assert!(protoc
+            .args
+            .contains(&arg_with_value("foo_out", output.path().to_str().unwrap())));
+        Ok(())
+    }
+
+    #[test]
+    fn diff_skips_plugin_outputs() -> Result<()> {
+        let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
+        let output = tempdir()?;
+        let mut config = Config::default();
+        config.input = vec![PathBuf::from(&input)];
+        config.plugins = vec![("foo".to_owned(), "bin/protoc-gen-foo".to_owned())];
+        config.plugin_outs = vec![("foo".to_owned(), output.path().to_path_buf())];
+        config.diff = true;
+
+        let protoc = Protoc::new(&config)?;
+        assert!(!protoc.args.iter().any(|arg| arg.starts_with("--foo_out")));
+        Ok(())
+    }
+
+    #[test]
+    fn passes_editions_flag() -> Result<()> {
+        let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
+        let mut config = Config::default();
+        config.input = vec![PathBuf::from(&input)];
+        let flag = "--experimental_editions".to_owned();
+        assert!(!Protoc::new(&config)?.args.contains(&flag));
+
+        config.editions = true;
+        assert!(Protoc::new(&config)?.args.contains(&flag));
+        assert!(Protoc::for_lang(&config, &Lang::Cpp)?.args.contains(&flag));
+        Ok(())
+    }
+
+    #[test]
+    fn with_timeout_from_config() -> Result<()> {
+        let input = env::current_dir().unwrap().to_str().unwrap().to_owned();
+        let mut config = Config::default();
+        config.input = vec![PathBuf::from(&input)];
+        config.protoc_timeout = Some(Duration::from_secs(5));
+        assert_eq!(Protoc::new(&config)?.timeout, config.protoc_timeout);
+        assert_eq!(
+            Protoc::for_lang(&config, &Lang::Cpp)?.timeout,
+            config.protoc_timeout
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn short_command_line_passes_args_directly() -> Result<()> {
+        let args = vec!["--proto_path=input".to_owned(), "file.proto".to_owned()];
+        let (command_args, argfile) = command_args(args.clone())?;
+        assert_eq!(command_args, args);
+        assert!(argfile.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn long_command_line_uses_argfile() -> Result<()> {
+        let mut args = vec!["--proto_path=input".to_owned()];
+        args.extend((0..1000).map(|i| format!("some/nested/directory/file_{}.proto", i)));
+        assert!(args.concat().len() > MAX_COMMAND_LINE_LENGTH);
+
+        let (command_args, argfile) = command_args(args.clone())?;
+        let argfile = argfile.expect("argfile");
+        assert_eq!(
+            command_args,
+            vec![format!("@{}", argfile.path().to_str().unwrap())]
+        );
+        let contents = fs::read_to_string(argfile.path())?;
+        assert_eq!(contents.lines().collect::<Vec<&str>>(), args);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn protoc_runs_with_argfile() -> Result<()> {
+        let input = tempdir()?;
+        let output = tempdir()?;
+        let dir = input
+            .path()
+            .join("a_long_directory_name_to_lengthen_the_command_line");
+        fs::create_dir_all(&dir)?;
+        let mut files = Vec::new();
+        for i in 0..200 {
+            let name = format!("message_file_with_a_long_name_{}.proto", i);
+            fs::write(
+                dir.join(&name),
+                format!("syntax = \"proto3\";\nmessage Message{} {{}}\n", i),
+            )?;
+            files.push(format!(
+                "a_long_directory_name_to_lengthen_the_command_line/{}",
+                name
+            ));
+        }
+        assert!(files.concat().len() > MAX_COMMAND_LINE_LENGTH);
+        let descriptor_set_path = output.path().join("descriptor_set.pb");
+
+        let mut config = Config::default();
+        config.input = vec![input.path().to_path_buf()];
+        let mut protoc = Protoc::new(&config)?;
+        protoc.add_args(&mut vec![arg_with_value(
+            PROTOC_ARG_DESCRIPTOR_SET_OUT,
+            descriptor_set_path.to_str().unwrap(),
+        )]);
+        protoc.add_input_files(&mut files.clone());
+        protoc.execute(None)?;
+
+        let descriptor_set = FileDescriptorSet::decode(fs::read(&descriptor_set_path)?.as_slice())?;
+        let names = descriptor_set
+            .file
+            .iter()
+            .filter_map(|file| file.name.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(names, files);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    mod wait_with_timeout {
+        use crate::protoc::protoc::wait_with_timeout;
+        use anyhow::Result;
+        use std::process::{Command, Stdio};
+        use std::time::{Duration, Instant};
+
+        #[test]
+        fn kills_process_after_timeout() -> Result<()> {
+            let child = Command::new("sleep")
+                .arg("10")
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let start = Instant::now();
+            let result = wait_with_timeout(child, Some(Duration::from_millis(100)));
+            assert!(result.is_err());
+            assert!(start.elapsed() < Duration::from_secs(5));
+            Ok(())
+        }
+
+        #[test]
+        fn collects_output_within_timeout() -> Result<()> {
+            let child = Command::new("echo")
+                .arg("hello")
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let (status, stdout) = wait_with_timeout(child, Some(Duration::from_secs(5)))?;
+            assert!(status.success());
+            assert_eq!(String::from_utf8(stdout)?, "hello\n");
+            Ok(())
+        }
+
+        #[test]
+        fn closes_open_stdin() -> Result<()> {
+            let child = Command::new("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let (status, stdout) = wait_with_timeout(child, Some(Duration::from_secs(5)))?;
+            assert!(status.success());
+            assert!(stdout.is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn no_timeout_waits() -> Result<()> {
+            let child = Command::new("echo")
+                .arg("hello")
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let (status, stdout) = wait_with_timeout(child, None)?;
+            assert!(status.success());
+            assert_eq!(String::from_utf8(stdout)?, "hello\n");
+            Ok(())
+        }
+    }
+
     fn quote_arg(arg: &str) -> String {
         ["\"", arg, "\""].concat()
     }
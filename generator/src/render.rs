@@ -1,12 +1,89 @@
+use crate::renderer::RendererConfig;
+use crate::ConfigFormat;
 use anyhow::Result;
 use prost_types::FileDescriptorSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+/// Options of a single run, set from the command line rather than a renderer's config file.
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    /// Use the overlays for these targets for every context instead of each context's own target.
+    pub overlay_targets: Vec<String>,
+    /// Report progress to stderr as each file is rendered.
+    pub progress: bool,
+    /// Render files matching the config's `import_prefixes` instead of skipping them.
+    pub render_imports: bool,
+    /// Maximum number of files rendered in parallel. 0 and 1 both render one file at a time.
+    pub jobs: usize,
+    /// Append to output files which already exist instead of overwriting them.
+    pub append: bool,
+    /// Files generated by protoc into the output directory, relative to it, to list in metadata.
+    pub native_files: Vec<PathBuf>,
+    /// SHA-256 of each proto file's source, keyed by its name in the descriptor set.
+    pub source_file_hashes: HashMap<String, String>,
+    /// With `--since`, the names of the proto files rendered to their own output files. Outputs
+    /// which combine several files are always rendered from every file.
+    pub changed_files: Option<HashSet<String>>,
+}
+
+impl RenderOptions {
+    /// `config` with the overlay targets of this run, if there are any.
+    pub(crate) fn apply<'a>(&self, config: &'a RendererConfig) -> Cow<'a, RendererConfig> {
+        if self.overlay_targets.is_empty() {
+            return Cow::Borrowed(config);
+        }
+        let mut config = config.clone();
+        config
+            .overlays
+            .set_active_targets(self.overlay_targets.clone());
+        Cow::Owned(config)
+    }
+
+    /// False if the proto file `name` is left out of per-file rendering by `--since`.
+    pub(crate) fn is_changed_file(&self, name: &str) -> bool {
+        match &self.changed_files {
+            None => true,
+            Some(changed) => changed.contains(name),
+        }
+    }
+
+    /// True if the proto file `name` is skipped by `config` in this run.
+    pub(crate) fn is_ignored_file(&self, config: &RendererConfig, name: &str) -> bool {
+        config.is_ignored_file(name, self.render_imports)
+    }
+}
+
 pub trait Render {
-    /// Load any necessary files from the `input_root` directory and overlays as specified.
-    fn load(&mut self, input_root: &Path, overlays: &[PathBuf]) -> Result<()>;
+    /// Load any necessary files from the `input_root` directory and overlays as specified. The
+    /// config file is parsed as `config_format` if set, otherwise as YAML.
+    fn load(
+        &mut self,
+        input_root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()>;
     /// Reset is called between runs with different input/outputs.
     fn reset(&mut self);
+    /// False if every file in the descriptor set is ignored by the loaded config, or there are none.
+    fn has_files_to_render(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        options: &RenderOptions,
+    ) -> bool;
+    /// Errors if two distinct inputs would be rendered to the same file in `output_path`.
+    fn check_output_paths(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()>;
     /// Do the actual rendering to the `output_path` directory.
-    fn render(&self, descriptor_set: &FileDescriptorSet, output_path: &Path) -> Result<()>;
+    fn render(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()>;
 }
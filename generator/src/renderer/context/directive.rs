@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use log::warn;
+use prost_types::FileDescriptorProto;
+
+use crate::util;
+
+const DIRECTIVE_PREFIX: &str = "protox:";
+const SKIP: &str = "skip";
+const RENAME: &str = "rename";
+
+/// Field number of `message_type` in `FileDescriptorProto`, used in source code info paths.
+pub(crate) const FILE_MESSAGE_TYPE: i32 = 4;
+/// Field number of `field` in `DescriptorProto`, used in source code info paths.
+pub(crate) const MESSAGE_FIELD: i32 = 2;
+/// Field number of `nested_type` in `DescriptorProto`, used in source code info paths.
+pub(crate) const MESSAGE_NESTED_TYPE: i32 = 3;
+
+/// Directives found in the leading comment of a message or field. Each directive is on its own
+/// comment line:
+/// ```txt
+///   // protox:skip           The element is left out of the context entirely.
+///   // protox:rename=Name    The element's name is `Name` instead of the computed name.
+/// ```
+///
+/// A rename takes precedence over the case conversion and name overrides in the config, but the
+/// result is still escaped if it is in `reserved_keywords`. Overlays still target the element by
+/// its name in the proto file.
+///
+/// Unknown or malformed directives, and directives on any other element such as an enum, are
+/// ignored with a warning.
+#[derive(Default, Debug, PartialEq)]
+pub(crate) struct Directives {
+    skip: bool,
+    rename: Option<String>,
+}
+
+impl Directives {
+    /// Warns about unknown directives, naming `file_name`, so that typos don't silently do nothing.
+    pub fn parse(comment: &str, file_name: &str) -> Self {
+        let mut directives = Self::default();
+        for line in comment.lines().map(str::trim) {
+            let directive = match line.strip_prefix(DIRECTIVE_PREFIX) {
+                None => continue,
+                Some(directive) => directive.trim(),
+            };
+            let (name, value) = match directive.split_once('=') {
+                None => (directive, None),
+                Some((name, value)) => (name.trim(), Some(value.trim())),
+            };
+            match (name, value) {
+                (SKIP, None) => directives.skip = true,
+                (RENAME, Some(value)) if !value.is_empty() => {
+                    directives.rename = Some(value.to_owned())
+                }
+                _ => warn!(
+                    "Ignoring invalid comment directive '{}' in file '{}'",
+                    line, file_name
+                ),
+            }
+        }
+        directives
+    }
+}
+
+/// Directives for every element of a file with a leading comment, by source code info path.
+#[derive(Default)]
+pub(crate) struct FileDirectives {
+    by_path: HashMap<Vec<i32>, Directives>,
+}
+
+impl FileDirectives {
+    /// Empty if the descriptor set was generated without source code info.
+    pub fn from_file(file: &FileDescriptorProto) -> Self {
+        let file_name = util::str_or_unknown(&file.name);
+        let mut by_path = HashMap::new();
+        let locations = match &file.source_code_info {
+            None => return Self { by_path },
+            Some(info) => &info.location,
+        };
+        for location in locations {
+            let comment = match &location.leading_comments {
+                None => continue,
+                Some(comment) => comment,
+            };
+            let directives = Directives::parse(comment, file_name);
+            if directives == Directives::default() {
+                continue;
+            }
+            if !is_message_or_field_path(&location.path) {
+                warn!(
+                    "Ignoring comment directives on {:?} in file '{}': only messages and fields support them",
+                    location.path, file_name
+                );
+                continue;
+            }
+            by_path.insert(location.path.clone(), directives);
+        }
+        Self { by_path }
+    }
+
+    pub fn is_skipped(&self, path: &[i32]) -> bool {
+        self.by_path.get(path).map_or(false, |d| d.skip)
+    }
+
+    pub fn rename(&self, path: &[i32]) -> Option<&str> {
+        self.by_path.get(path).and_then(|d| d.rename.as_deref())
+    }
+}
+
+/// True if `path` is the source code info path of a message or a field of one, at any depth.
+fn is_message_or_field_path(path: &[i32]) -> bool {
+    match path {
+        [FILE_MESSAGE_TYPE, _, members @ ..] => is_message_member_path(members),
+        _ => false,
+    }
+}
+
+/// True if `path`, relative to a message, is empty or leads to a field or nested message.
+fn is_message_member_path(path: &[i32]) -> bool {
+    match path {
+        [] | [MESSAGE_FIELD, _] => true,
+        [MESSAGE_NESTED_TYPE, _, members @ ..] => is_message_member_path(members),
+        _ => false,
+    }
+}
+
+/// Source code info path of the element at `index` in the list with field number `field`.
+pub(crate) fn child_path(parent: &[i32], field: i32, index: usize) -> Vec<i32> {
+    let mut path = parent.to_vec();
+    path.push(field);
+    path.push(index as i32);
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::source_code_info::Location;
+    use prost_types::{FileDescriptorProto, SourceCodeInfo};
+
+    use crate::renderer::context::directive::{Directives, FileDirectives};
+
+    #[test]
+    fn parses_directives_among_other_lines() {
+        let directives = Directives::parse(
+            " Some description.\n protox:skip\n protox:rename = Other\n",
+            "file.proto",
+        );
+        assert!(directives.skip);
+        assert_eq!(directives.rename, Some("Other".to_owned()));
+    }
+
+    #[test]
+    fn no_directives() {
+        assert_eq!(
+            Directives::parse(" Mentions protox: inline.\n", "file.proto"),
+            Directives::default()
+        );
+    }
+
+    #[test]
+    fn invalid_directives_are_ignored() {
+        for comment in [
+            " protox:unknown\n",
+            " protox:rename\n",
+            " protox:rename=\n",
+            " protox:skip=true\n",
+        ] {
+            assert_eq!(
+                Directives::parse(comment, "file.proto"),
+                Directives::default()
+            );
+        }
+        let directives = Directives::parse(" protox:unknown\n protox:skip\n", "file.proto");
+        assert!(directives.skip);
+    }
+
+    #[test]
+    fn ignores_directives_on_enums() {
+        let file = FileDescriptorProto {
+            source_code_info: Some(SourceCodeInfo {
+                location: vec![
+                    location(&[5, 0], " protox:skip\n"),
+                    location(&[4, 0, 4, 0], " protox:rename=Renamed\n"),
+                    location(&[4, 0, 4, 0, 2, 0], " protox:skip\n"),
+                ],
+            }),
+            ..Default::default()
+        };
+        let directives = FileDirectives::from_file(&file);
+        assert!(!directives.is_skipped(&[5, 0]));
+        assert_eq!(directives.rename(&[4, 0, 4, 0]), None);
+        assert!(!directives.is_skipped(&[4, 0, 4, 0, 2, 0]));
+    }
+
+    #[test]
+    fn from_file_by_path() {
+        let file = FileDescriptorProto {
+            source_code_info: Some(SourceCodeInfo {
+                location: vec![
+                    location(&[4, 0], " protox:skip\n"),
+                    location(&[4, 1, 2, 0], " protox:rename=Renamed\n"),
+                    location(&[4, 2], " Just a comment.\n"),
+                ],
+            }),
+            ..Default::default()
+        };
+        let directives = FileDirectives::from_file(&file);
+        assert!(directives.is_skipped(&[4, 0]));
+        assert!(!directives.is_skipped(&[4, 1]));
+        assert_eq!(directives.rename(&[4, 1, 2, 0]), Some("Renamed"));
+        assert_eq!(directives.rename(&[4, 2]), None);
+    }
+
+    fn location(path: &[i32], comment: &str) -> Location {
+        Location {
+            path: path.to_vec(),
+            leading_comments: Some(comment.to_owned()),
+            ..Default::default()
+        }
+    }
+}
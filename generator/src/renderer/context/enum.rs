@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::renderer::context::overlayed::Overlayed;
 use anyhow::{anyhow, Result};
 use log::debug;
+use prost::Extendable;
 use prost_types::{EnumDescriptorProto, EnumOptions, EnumValueDescriptorProto, EnumValueOptions};
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -17,6 +18,14 @@ pub struct EnumContext {
     // Values defined by this enum.
     values: Vec<EnumValueContext>,
 
+    /// One of the values has number 0, which proto3 requires and which is the default value.
+    /// Proto2 enums may not have one.
+    has_zero_value: bool,
+
+    /// Name of the value with number 0, if any. When several values share 0 via `allow_alias`,
+    /// the first declared is used.
+    zero_value_name: Option<String>,
+
     /// Proto enum options are serialized as an object like so:
     /// ```json
     /// {
@@ -52,10 +61,21 @@ pub struct EnumContext {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct EnumValueContext {
     name: String,
+
+    /// The value's integer, e.g. `{{name}} = {{number}}`.
     number: i32,
 
-    /// Currently only supported in scripted renderer.
-    #[serde(skip)]
+    /// Names of the other values with the same number, in declaration order. Only non-empty for
+    /// enums with `allow_alias`.
+    aliases: Vec<String>,
+
+    /// This is the first declared value with its number. Values without aliases are always
+    /// canonical.
+    is_canonical: bool,
+
+    /// Only custom key-value options are serialized for templates, e.g. `{{options.some_key}}`.
+    /// See `protox.enum_value_key_value` in `protox/proto_options/protos`.
+    #[serde(serialize_with = "serialize_enum_value_options", skip_deserializing)]
     options: Option<EnumValueOptions>,
 
     // Config overlays applied to this File.
@@ -71,9 +91,16 @@ impl EnumContext {
         config: &RendererConfig,
     ) -> Result<Self> {
         log_new_enum(&proto.name);
+        let values = values(&proto, package, config)?;
+        let zero_value_name = values
+            .iter()
+            .find(|value| value.number == 0)
+            .map(|value| value.name.clone());
         let context = Self {
             name: name(&proto, config)?,
-            values: values(&proto, package, config)?,
+            values,
+            has_zero_value: zero_value_name.is_some(),
+            zero_value_name,
             options: proto.options.clone(),
             overlays: config
                 .overlays
@@ -88,6 +115,12 @@ impl EnumContext {
     pub fn values(&self) -> &[EnumValueContext] {
         &self.values
     }
+    pub fn has_zero_value(&self) -> bool {
+        self.has_zero_value
+    }
+    pub fn zero_value_name(&self) -> Option<&String> {
+        self.zero_value_name.as_ref()
+    }
     pub fn options(&self) -> &Option<EnumOptions> {
         &self.options
     }
@@ -107,6 +140,8 @@ impl EnumValueContext {
         Ok(EnumValueContext {
             name: case.rename(&name),
             number,
+            aliases: vec![],
+            is_canonical: true,
             options: proto.options.clone(),
             overlays: config
                 .overlays
@@ -120,6 +155,12 @@ impl EnumValueContext {
     pub fn number(&self) -> i32 {
         self.number
     }
+    pub fn aliases(&self) -> &Vec<String> {
+        &self.aliases
+    }
+    pub fn is_canonical(&self) -> bool {
+        self.is_canonical
+    }
     pub fn options(&self) -> &Option<EnumValueOptions> {
         &self.options
     }
@@ -147,7 +188,7 @@ fn full_name(package: Option<&String>, name: &Option<String>) -> Option<String>
 
 fn name(proto: &EnumDescriptorProto, config: &RendererConfig) -> Result<String> {
     let name = util::str_or_error(&proto.name, || "Enum has no 'name'".to_owned())?;
-    Ok(config.case_config.enum_name.rename(name))
+    Ok(config.escape_keyword(config.case_config.enum_name.rename(name)))
 }
 
 fn values(
@@ -164,9 +205,29 @@ fn values(
             config,
         )?);
     }
+    set_aliases(&mut values);
     Ok(values)
 }
 
+fn set_aliases(values: &mut [EnumValueContext]) {
+    let mut names_by_number = HashMap::<i32, Vec<String>>::new();
+    for value in values.iter() {
+        names_by_number
+            .entry(value.number)
+            .or_default()
+            .push(value.name.clone());
+    }
+    for value in values.iter_mut() {
+        let names = &names_by_number[&value.number];
+        value.is_canonical = names[0] == value.name;
+        value.aliases = names
+            .iter()
+            .filter(|name| **name != value.name)
+            .cloned()
+            .collect();
+    }
+}
+
 fn error_invalid_value(name: &Option<String>) -> anyhow::Error {
     anyhow!(
         "Enum '{}' has a value missing name and/or number.",
@@ -188,10 +249,34 @@ fn serialize_enum_options<S: Serializer>(
     serializer.collect_map(map)
 }
 
+/// Custom key-value data from the `protox.enum_value_key_value` option.
+pub fn enum_value_key_values(options: &EnumValueOptions) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::new();
+    if let Ok(key_values) = options.extension_data(proto_options::ENUM_VALUE_KEY_VALUE) {
+        for kv in key_values {
+            map.insert(kv.key.clone(), kv.value.clone());
+        }
+    }
+    map
+}
+
+fn serialize_enum_value_options<S: Serializer>(
+    options: &Option<EnumValueOptions>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let map = options
+        .as_ref()
+        .map(enum_value_key_values)
+        .unwrap_or_default();
+    debug!("Serializing enum value options: {:?}", map);
+    serializer.collect_map(map)
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
-    use prost_types::{EnumDescriptorProto, EnumValueDescriptorProto};
+    use prost::Extendable;
+    use prost_types::{EnumDescriptorProto, EnumValueDescriptorProto, EnumValueOptions};
     use std::collections::HashMap;
 
     use crate::renderer::case::Case;
@@ -261,6 +346,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn proto3_zero_value() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = EnumDescriptorProto::default();
+        proto.name = Some("EnumName".to_owned());
+        proto.value.push(named_enum_value("Unspecified", 0));
+        proto.value.push(named_enum_value("Other", 1));
+        let context = EnumContext::new(&proto, None, &config)?;
+        assert!(context.has_zero_value());
+        assert_eq!(context.zero_value_name(), Some(&"Unspecified".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn proto2_without_zero_value() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = EnumDescriptorProto::default();
+        proto.name = Some("EnumName".to_owned());
+        proto.value.push(named_enum_value("First", 1));
+        proto.value.push(named_enum_value("Second", 2));
+        let context = EnumContext::new(&proto, None, &config)?;
+        assert!(!context.has_zero_value());
+        assert_eq!(context.zero_value_name(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn aliases() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = EnumDescriptorProto::default();
+        proto.name = Some("EnumName".to_owned());
+        proto.value.push(named_enum_value("Started", 1));
+        proto.value.push(named_enum_value("Running", 1));
+        proto.value.push(named_enum_value("Stopped", 2));
+        let context = EnumContext::new(&proto, None, &config)?;
+        let values = context.values();
+        assert!(values[0].is_canonical());
+        assert_eq!(values[0].aliases(), &vec!["Running".to_owned()]);
+        assert!(!values[1].is_canonical());
+        assert_eq!(values[1].aliases(), &vec!["Started".to_owned()]);
+        assert!(values[2].is_canonical());
+        assert!(values[2].aliases().is_empty());
+        Ok(())
+    }
+
     #[test]
     fn overlay_enum() -> Result<()> {
         let proto = EnumDescriptorProto {
@@ -314,6 +444,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn serializes_enum_value_key_values() -> Result<()> {
+        let mut options = EnumValueOptions::default();
+        options.set_extension_data(
+            &proto_options::ENUM_VALUE_KEY_VALUE,
+            vec![proto_options::KeyValue {
+                key: "display".to_owned(),
+                value: "Value A".to_owned(),
+            }],
+        )?;
+        let mut proto = named_enum_value("ValueName", 1);
+        proto.options = Some(options);
+        let context = EnumValueContext::new(&proto, None, &RendererConfig::default())?;
+        let json = serde_json::to_value(&context)?;
+        assert_eq!(json["options"]["display"], "Value A");
+        Ok(())
+    }
+
     fn enum_value(number: i32) -> EnumValueDescriptorProto {
         EnumValueDescriptorProto {
             name: Some(number.to_string()),
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use prost::bytes::Buf;
+use prost::encoding::{decode_key, skip_field, DecodeContext};
+use prost::Message;
+
+/// Every options message in descriptor.proto declares `extensions 1000 to max;`.
+const FIRST_EXTENSION_NUMBER: u32 = 1000;
+
+/// Raw wire-format bytes of the extension fields set on `options` which protox doesn't know about,
+/// i.e. custom options that aren't registered in `proto_options::create_extension_registry`.
+/// Registered options are read through their own context fields instead. Scripts can decode the
+/// bytes as they see fit.
+pub fn extension_bytes<M: Message>(options: &M) -> Result<Vec<u8>> {
+    unregistered_extension_fields(&options.encode_to_vec())
+        .context("Failed to read unknown options")
+}
+
+fn unregistered_extension_fields(encoded: &[u8]) -> Result<Vec<u8>> {
+    let registered = proto_options::registered_field_numbers();
+    let mut buf = encoded;
+    let mut bytes = Vec::new();
+    while buf.has_remaining() {
+        let start = encoded.len() - buf.remaining();
+        let (number, wire_type) = decode_key(&mut buf)?;
+        skip_field(wire_type, number, &mut buf, DecodeContext::default())?;
+        if number >= FIRST_EXTENSION_NUMBER && !registered.contains(&number) {
+            let end = encoded.len() - buf.remaining();
+            bytes.extend_from_slice(&encoded[start..end]);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use prost::encoding::string;
+    use prost::{Extendable, Message};
+    use prost_types::FieldOptions;
+
+    use crate::renderer::context::extension_bytes::{
+        extension_bytes, unregistered_extension_fields,
+    };
+
+    #[test]
+    fn empty_without_extensions() -> Result<()> {
+        let options = FieldOptions {
+            deprecated: Some(true),
+            ..Default::default()
+        };
+        assert!(extension_bytes(&options)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn skips_registered_extensions() -> Result<()> {
+        let mut options = FieldOptions::default();
+        options.set_extension_data(&proto_options::NATIVE_TYPE, "i32".to_owned())?;
+        assert!(extension_bytes(&options)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn contains_only_unknown_extension_fields() -> Result<()> {
+        let mut options = FieldOptions {
+            deprecated: Some(true),
+            ..Default::default()
+        };
+        options.set_extension_data(&proto_options::NATIVE_TYPE, "i32".to_owned())?;
+        let mut unknown = Vec::new();
+        string::encode(50000, &"custom".to_owned(), &mut unknown);
+        let mut encoded = options.encode_to_vec();
+        encoded.extend_from_slice(&unknown);
+
+        assert_eq!(unregistered_extension_fields(&encoded)?, unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_field_errors() {
+        let mut encoded = Vec::new();
+        string::encode(50000, &"custom".to_owned(), &mut encoded);
+        encoded.pop();
+        assert!(unregistered_extension_fields(&encoded).is_err());
+    }
+}
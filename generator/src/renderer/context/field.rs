@@ -1,22 +1,45 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use log::debug;
-use prost_types::field_descriptor_proto::Label;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::field_options::{CType, JsType};
 use prost_types::{FieldDescriptorProto, FieldOptions};
+use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 
+use crate::renderer::context::file::try_insert_option;
 use crate::renderer::context::message;
 use crate::renderer::context::overlayed::Overlayed;
+use crate::renderer::context::proto_type;
 use crate::renderer::context::proto_type::ProtoType;
 use crate::renderer::RendererConfig;
 use crate::util;
 
+const ARRAY_TYPE_PLACEHOLDER: &str = "{type}";
+
+const TYPE_KIND_SCALAR: &str = "scalar";
+const TYPE_KIND_ENUM: &str = "enum";
+const TYPE_KIND_MESSAGE: &str = "message";
+const TYPE_KIND_GROUP: &str = "group";
+const TYPE_KIND_MAP: &str = "map";
+
+const WIRE_TYPE_BITS: u32 = 3;
+const WIRE_TYPE_VARINT: u32 = 0;
+const WIRE_TYPE_64_BIT: u32 = 1;
+const WIRE_TYPE_LENGTH_DELIMITED: u32 = 2;
+const WIRE_TYPE_START_GROUP: u32 = 3;
+const WIRE_TYPE_32_BIT: u32 = 5;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FieldContext {
     // Name of the field.
     field_name: String,
 
+    /// Simple name of the message containing this field, as written in the proto file.
+    message_name: Option<String>,
+
     /// Type as defined by type config or literal type name. Only valid if `is_map` is false.
     ///
     /// If `is_map` is true, use `*_key_type` and `*_value_type` fields instead.
@@ -40,12 +63,30 @@ pub struct FieldContext {
     /// This field's type is an array of the type specified in `fully_qualified_type` and `relative_type`.
     is_array: bool,
 
+    /// When `is_array` is true and `array_type` is set in the config, the `array_type` template
+    /// filled with `relative_type`, e.g. `Vec<Name>`.
+    array_type: Option<String>,
+
     /// This field's type is a map. Use the `*_key_type` and `*_value_type` fields.
     is_map: bool,
 
     /// This field is part of a oneof type.
     is_oneof: bool,
 
+    /// Name of the oneof this field is a member of, as written in the proto file. None for
+    /// regular fields and proto3 `optional` fields, whose oneof is only synthetic.
+    oneof_name: Option<String>,
+
+    /// This field is declared `optional` in proto2, is an implicit-presence proto3 field, or is an
+    /// explicit `optional` proto3 field. False for `required` and `repeated` fields.
+    is_optional: bool,
+
+    /// This field is declared `required`. Always false in proto3.
+    is_required: bool,
+
+    /// This field is declared `repeated`. Also true for maps, which are repeated entries on the wire.
+    is_repeated: bool,
+
     /// When `is_map` is true, equivalent to `fully_qualified_type` for the key type of the map.
     fully_qualified_key_type: Option<String>,
 
@@ -58,6 +99,50 @@ pub struct FieldContext {
     /// When `is_map` is true, equivalent to `relative_type` for the value type of the map.
     relative_value_type: Option<String>,
 
+    /// When `is_map` is true, the field number of the key in the map entry message (always 1).
+    map_key_number: Option<i32>,
+
+    /// When `is_map` is true, the field number of the value in the map entry message (always 2).
+    map_value_number: Option<i32>,
+
+    /// Raw numeric proto type of this field, as in `FieldDescriptorProto.type`.
+    proto_type_number: Option<i32>,
+
+    /// Canonical name of `proto_type_number`, e.g. `TYPE_MESSAGE` or `TYPE_INT32`.
+    proto_type_name: Option<String>,
+
+    /// Broad kind of this field's type: `scalar`, `enum`, `message`, `group` or `map`.
+    ///
+    /// Proto2 `group` fields are typed by a message protoc generates from the group's body, which
+    /// is found in the `nested_messages` of the containing message.
+    type_kind: String,
+
+    /// For proto2 extension fields, the message being extended, relative to the owning file's
+    /// package. None for regular fields.
+    extendee: Option<String>,
+
+    /// For bytes fields with a default value, the default decoded from protoc's C-escaped string
+    /// into raw bytes, e.g. `\x01\x02` becomes `[1, 2]`. Empty for all other fields.
+    default_value_bytes: Vec<u8>,
+
+    /// Friendly name of the `jstype` field option: `normal`, `string` or `number`. Tells JS/TS
+    /// targets how to represent 64-bit integers.
+    jstype_name: String,
+
+    /// The type of this field is the containing message, or reaches it through the fields of other
+    /// messages. Useful for breaking cycles in value-type languages, e.g. by boxing the field.
+    is_recursive: bool,
+
+    /// Repeated scalar values of this field are written in the packed encoding, from the explicit
+    /// `packed` option, or otherwise the proto3 default, which packs every repeated scalar numeric
+    /// or enum field.
+    is_packed: bool,
+
+    /// Tag written as a varint before each value of this field, `field_number << 3 | wire_type`.
+    /// The wire type is 0 for varints, 1 for 64-bit, 2 for length-delimited values including
+    /// packed repeated fields, 3 for groups and 5 for 32-bit.
+    wire_tag: u32,
+
     /// Proto field options are serialized as an object like so:
     /// ```json
     /// {
@@ -76,8 +161,10 @@ pub struct FieldContext {
     /// ```
     /// Note that for boolean values one #if is enough to check both that it exists and is true.
     ///
-    /// (NOT YET SUPPORTED) Built-in proto option names and types can be seen here:
+    /// Built-in proto option names and types can be seen here:
     /// https://docs.rs/prost-types/latest/prost_types/struct.FieldOptions.html
+    /// Of these, `deprecated`, `packed`, `lazy`, `jstype`, `ctype` and `weak` are serialized.
+    /// `jstype` and `ctype` are serialized as their enum value names, e.g. `JS_STRING`.
     ///
     /// Additionally, a few protox-specific options are supported. See the proto files at
     /// `protox/proto_options/protos` for more info.
@@ -91,20 +178,23 @@ pub struct FieldContext {
 }
 
 impl FieldContext {
+    /// `proto3` is true if the file declaring the field uses proto3 syntax, which packs repeated
+    /// scalar numeric fields by default.
     pub fn new(
         field: &FieldDescriptorProto,
         package: Option<&String>,
         message_name: Option<&String>,
         map_data: &message::MapData,
+        proto3: bool,
         config: &RendererConfig,
     ) -> Result<Self> {
         log_new_field(&field.name);
         match &field.type_name {
-            None => FieldContext::new_basic(field, package, message_name, config),
+            None => FieldContext::new_basic(field, package, message_name, proto3, config),
             Some(type_name) => match map_data.get(type_name) {
-                None => FieldContext::new_basic(field, package, message_name, config),
+                None => FieldContext::new_basic(field, package, message_name, proto3, config),
                 Some(entry_data) => {
-                    FieldContext::new_map(field, package, message_name, entry_data, config)
+                    FieldContext::new_map(field, package, message_name, entry_data, proto3, config)
                 }
             },
         }
@@ -114,21 +204,43 @@ impl FieldContext {
         field: &FieldDescriptorProto,
         package: Option<&String>,
         message_name: Option<&String>,
+        proto3: bool,
         config: &RendererConfig,
     ) -> Result<Self> {
         let type_path = ProtoType::from_field(field)?.to_type_path(config)?;
         let parent_prefix = config.field_relative_parent_prefix.as_ref();
+        let relative_type = type_path.relative_to(namespace(package, config), parent_prefix);
         let context = Self {
             field_name: field_name(field, &config)?,
+            message_name: message_name.cloned(),
             fully_qualified_type: Some(type_path.to_string()),
-            relative_type: Some(type_path.relative_to(package, parent_prefix)),
+            array_type: array_type(field, &relative_type, config),
+            relative_type: Some(relative_type),
             is_array: is_array(field),
             is_map: false,
             is_oneof: is_oneof(field),
+            oneof_name: None,
+            is_optional: is_optional(field),
+            is_required: has_label(field, Label::Required),
+            is_repeated: has_label(field, Label::Repeated),
             fully_qualified_key_type: None,
             fully_qualified_value_type: None,
             relative_key_type: None,
             relative_value_type: None,
+            map_key_number: None,
+            map_value_number: None,
+            proto_type_number: field.r#type,
+            proto_type_name: proto_type_name(field),
+            type_kind: type_kind(field).to_owned(),
+            extendee: extendee(field, package, config)?,
+            default_value_bytes: default_value_bytes(field)?,
+            jstype_name: jstype_name(field.options.as_ref()).to_owned(),
+            is_recursive: false,
+            is_packed: is_packed(field, proto3),
+            wire_tag: wire_tag(
+                field.number,
+                wire_type(field.r#type, is_packed(field, proto3)),
+            ),
             options: field.options.clone(),
             overlays: overlays(package, message_name, &field.name, config),
         };
@@ -140,6 +252,7 @@ impl FieldContext {
         package: Option<&String>,
         message_name: Option<&String>,
         entry: &message::MapEntryData,
+        proto3: bool,
         config: &RendererConfig,
     ) -> Result<Self> {
         let key_type_path = entry.key.to_type_path(config)?;
@@ -147,15 +260,39 @@ impl FieldContext {
         let parent_prefix = config.field_relative_parent_prefix.as_ref();
         let context = Self {
             field_name: field_name(field, &config)?,
+            message_name: message_name.cloned(),
             fully_qualified_type: None,
             relative_type: None,
             is_array: false,
+            array_type: None,
             is_map: true,
             is_oneof: is_oneof(field),
+            oneof_name: None,
+            is_optional: is_optional(field),
+            is_required: has_label(field, Label::Required),
+            is_repeated: has_label(field, Label::Repeated),
             fully_qualified_key_type: Some(key_type_path.to_string()),
             fully_qualified_value_type: Some(value_type_path.to_string()),
-            relative_key_type: Some(key_type_path.relative_to(package, parent_prefix)),
-            relative_value_type: Some(value_type_path.relative_to(package, parent_prefix)),
+            relative_key_type: Some(
+                key_type_path.relative_to(namespace(package, config), parent_prefix),
+            ),
+            relative_value_type: Some(
+                value_type_path.relative_to(namespace(package, config), parent_prefix),
+            ),
+            map_key_number: Some(entry.key_number),
+            map_value_number: Some(entry.value_number),
+            proto_type_number: field.r#type,
+            proto_type_name: proto_type_name(field),
+            type_kind: TYPE_KIND_MAP.to_owned(),
+            extendee: extendee(field, package, config)?,
+            default_value_bytes: default_value_bytes(field)?,
+            jstype_name: jstype_name(field.options.as_ref()).to_owned(),
+            is_recursive: false,
+            is_packed: is_packed(field, proto3),
+            wire_tag: wire_tag(
+                field.number,
+                wire_type(field.r#type, is_packed(field, proto3)),
+            ),
             options: field.options.clone(),
             overlays: overlays(package, message_name, &field.name, config),
         };
@@ -165,6 +302,9 @@ impl FieldContext {
     pub fn name(&self) -> &str {
         &self.field_name
     }
+    pub fn message_name(&self) -> Option<&String> {
+        self.message_name.as_ref()
+    }
     pub fn fully_qualified_type(&self) -> Option<&String> {
         self.fully_qualified_type.as_ref()
     }
@@ -174,12 +314,30 @@ impl FieldContext {
     pub fn is_array(&self) -> bool {
         self.is_array
     }
+    pub fn array_type(&self) -> Option<&String> {
+        self.array_type.as_ref()
+    }
     pub fn is_map(&self) -> bool {
         self.is_map
     }
     pub fn is_oneof(&self) -> bool {
         self.is_oneof
     }
+    pub fn oneof_name(&self) -> Option<&String> {
+        self.oneof_name.as_ref()
+    }
+    pub(crate) fn set_oneof_name(&mut self, oneof_name: Option<String>) {
+        self.oneof_name = oneof_name;
+    }
+    pub fn is_optional(&self) -> bool {
+        self.is_optional
+    }
+    pub fn is_required(&self) -> bool {
+        self.is_required
+    }
+    pub fn is_repeated(&self) -> bool {
+        self.is_repeated
+    }
     pub fn fully_qualified_key_type(&self) -> Option<&String> {
         self.fully_qualified_key_type.as_ref()
     }
@@ -192,6 +350,48 @@ impl FieldContext {
     pub fn relative_value_type(&self) -> Option<&String> {
         self.relative_value_type.as_ref()
     }
+    pub fn map_key_number(&self) -> Option<i32> {
+        self.map_key_number
+    }
+    pub fn map_value_number(&self) -> Option<i32> {
+        self.map_value_number
+    }
+    pub fn proto_type_number(&self) -> Option<i32> {
+        self.proto_type_number
+    }
+    pub fn proto_type_name(&self) -> Option<&String> {
+        self.proto_type_name.as_ref()
+    }
+    pub fn type_kind(&self) -> &str {
+        &self.type_kind
+    }
+    pub fn extendee(&self) -> Option<&String> {
+        self.extendee.as_ref()
+    }
+    pub fn default_value_bytes(&self) -> &Vec<u8> {
+        &self.default_value_bytes
+    }
+    pub fn jstype_name(&self) -> &str {
+        &self.jstype_name
+    }
+    pub fn is_recursive(&self) -> bool {
+        self.is_recursive
+    }
+    pub(crate) fn set_is_recursive(&mut self, is_recursive: bool) {
+        self.is_recursive = is_recursive;
+    }
+    pub fn is_packed(&self) -> bool {
+        self.is_packed
+    }
+    pub fn wire_tag(&self) -> u32 {
+        self.wire_tag
+    }
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.field_name = name;
+    }
+    pub(crate) fn set_overlays(&mut self, overlays: HashMap<String, serde_yaml::Value>) {
+        self.overlays = overlays;
+    }
     pub fn options(&self) -> Option<&FieldOptions> {
         self.options.as_ref()
     }
@@ -234,13 +434,166 @@ fn overlays(
 fn field_name(field: &FieldDescriptorProto, config: &RendererConfig) -> Result<String> {
     let field_name = util::str_or_error(&field.name, || "Field has no 'name'".to_owned())?;
     let case = config.case_config.field_name;
-    let renamed = case.rename(field_name);
+    let renamed = apply_regex_overrides(case.rename(field_name), config);
     let result = config
         .field_name_override
         .get(&renamed)
         .map(String::clone)
         .unwrap_or(renamed);
-    Ok(result)
+    Ok(config.escape_keyword(result))
+}
+
+fn apply_regex_overrides(field_name: String, config: &RendererConfig) -> String {
+    config
+        .field_name_regex_override
+        .iter()
+        .fold(field_name, |name, rule| rule.apply(&name))
+}
+
+fn proto_type_name(field: &FieldDescriptorProto) -> Option<String> {
+    field
+        .r#type
+        .and_then(proto_type::proto_type_id_name)
+        .map(str::to_owned)
+}
+
+/// Map fields are `TYPE_MESSAGE` on the wire, so they are detected from the map entry instead.
+fn type_kind(field: &FieldDescriptorProto) -> &'static str {
+    match field.r#type {
+        Some(t) if t == Type::Group as i32 => TYPE_KIND_GROUP,
+        Some(t) if t == Type::Message as i32 => TYPE_KIND_MESSAGE,
+        Some(t) if t == Type::Enum as i32 => TYPE_KIND_ENUM,
+        Some(_) => TYPE_KIND_SCALAR,
+        // Without a type protoc hasn't resolved the type name yet, so it is a message or enum.
+        None if field.type_name.is_some() => TYPE_KIND_MESSAGE,
+        None => TYPE_KIND_SCALAR,
+    }
+}
+
+fn extendee(
+    field: &FieldDescriptorProto,
+    package: Option<&String>,
+    config: &RendererConfig,
+) -> Result<Option<String>> {
+    let extendee = match &field.extendee {
+        None => return Ok(None),
+        Some(extendee) => extendee,
+    };
+    let type_path = ProtoType::TypeName(extendee.to_owned()).to_type_path(config)?;
+    let parent_prefix = config.field_relative_parent_prefix.as_ref();
+    Ok(Some(
+        type_path.relative_to(namespace(package, config), parent_prefix),
+    ))
+}
+
+/// The package that types are relative to, mapped like the types themselves, or
+/// `relative_root_package` if set.
+fn namespace<'a>(package: Option<&'a String>, config: &'a RendererConfig) -> Option<Cow<'a, str>> {
+    if let Some(root) = &config.relative_root_package {
+        return Some(Cow::Borrowed(root));
+    }
+    package.map(|package| config.package_namespace(package))
+}
+
+pub fn jstype_name(options: Option<&FieldOptions>) -> &'static str {
+    match options.and_then(|options| options.jstype) {
+        Some(jstype) if jstype == JsType::JsString as i32 => "string",
+        Some(jstype) if jstype == JsType::JsNumber as i32 => "number",
+        _ => "normal",
+    }
+}
+
+/// Name of the `jstype` option value in descriptor.proto, or the raw number if it is unknown.
+fn jstype_option_name(jstype: i32) -> serde_json::Value {
+    let name = match JsType::from_i32(jstype) {
+        Some(JsType::JsNormal) => "JS_NORMAL",
+        Some(JsType::JsString) => "JS_STRING",
+        Some(JsType::JsNumber) => "JS_NUMBER",
+        None => return jstype.into(),
+    };
+    name.into()
+}
+
+/// Name of the `ctype` option value in descriptor.proto, or the raw number if it is unknown.
+fn ctype_option_name(ctype: i32) -> serde_json::Value {
+    let name = match CType::from_i32(ctype) {
+        Some(CType::String) => "STRING",
+        Some(CType::Cord) => "CORD",
+        Some(CType::StringPiece) => "STRING_PIECE",
+        None => return ctype.into(),
+    };
+    name.into()
+}
+
+fn default_value_bytes(field: &FieldDescriptorProto) -> Result<Vec<u8>> {
+    if field.r#type != Some(Type::Bytes as i32) {
+        return Ok(vec![]);
+    }
+    match &field.default_value {
+        None => Ok(vec![]),
+        Some(default_value) => unescape_c_bytes(default_value)
+            .with_context(|| format!("Invalid bytes default value: '{}'", default_value)),
+    }
+}
+
+/// Decodes the C-style escaping protoc uses for bytes default values.
+fn unescape_c_bytes(escaped: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut iter = escaped.bytes().peekable();
+    while let Some(byte) = iter.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let escape = iter.next().ok_or(anyhow!("Trailing escape character"))?;
+        let value = match escape {
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'\\' | b'\'' | b'"' | b'?' => escape,
+            b'0'..=b'7' => {
+                let mut value = (escape - b'0') as u32;
+                for _ in 0..2 {
+                    match iter.peek() {
+                        Some(&digit) if (b'0'..=b'7').contains(&digit) => {
+                            value = value * 8 + (digit - b'0') as u32;
+                            iter.next();
+                        }
+                        _ => break,
+                    }
+                }
+                if value > u8::MAX as u32 {
+                    bail!("Octal escape out of range: {:o}", value);
+                }
+                value as u8
+            }
+            b'x' => {
+                let mut value = 0;
+                let mut digits = 0;
+                while digits < 2 {
+                    match iter.peek().and_then(|digit| (*digit as char).to_digit(16)) {
+                        Some(digit) => {
+                            value = value * 16 + digit;
+                            iter.next();
+                            digits += 1;
+                        }
+                        None => break,
+                    }
+                }
+                if digits == 0 {
+                    bail!("Hex escape has no digits");
+                }
+                value as u8
+            }
+            _ => bail!("Unknown escape sequence: '\\{}'", escape as char),
+        };
+        bytes.push(value);
+    }
+    Ok(bytes)
 }
 
 fn is_array(field: &FieldDescriptorProto) -> bool {
@@ -250,29 +603,114 @@ fn is_array(field: &FieldDescriptorProto) -> bool {
         .unwrap_or(false)
 }
 
+fn array_type(
+    field: &FieldDescriptorProto,
+    relative_type: &str,
+    config: &RendererConfig,
+) -> Option<String> {
+    if !is_array(field) {
+        return None;
+    }
+    let template = config.array_type.as_ref()?;
+    Some(template.replace(ARRAY_TYPE_PLACEHOLDER, relative_type))
+}
+
 fn is_oneof(field: &FieldDescriptorProto) -> bool {
     field.oneof_index.is_some()
 }
 
+/// Effective packing of `field`, where `proto3` is true if its file uses proto3 syntax.
+fn is_packed(field: &FieldDescriptorProto, proto3: bool) -> bool {
+    if !has_label(field, Label::Repeated) || !is_packable(field) {
+        return false;
+    }
+    match field.options.as_ref().and_then(|options| options.packed) {
+        Some(packed) => packed,
+        None => proto3,
+    }
+}
+
+fn wire_tag(number: Option<i32>, wire_type: u32) -> u32 {
+    (number.unwrap_or(0) as u32) << WIRE_TYPE_BITS | wire_type
+}
+
+fn wire_type(proto_type: Option<i32>, is_packed: bool) -> u32 {
+    if is_packed {
+        return WIRE_TYPE_LENGTH_DELIMITED;
+    }
+    match proto_type.and_then(Type::from_i32) {
+        Some(Type::Fixed64) | Some(Type::Sfixed64) | Some(Type::Double) => WIRE_TYPE_64_BIT,
+        Some(Type::Fixed32) | Some(Type::Sfixed32) | Some(Type::Float) => WIRE_TYPE_32_BIT,
+        Some(Type::String) | Some(Type::Bytes) | Some(Type::Message) => WIRE_TYPE_LENGTH_DELIMITED,
+        Some(Type::Group) => WIRE_TYPE_START_GROUP,
+        // Unresolved types are treated as messages, as in `type_kind`.
+        None => WIRE_TYPE_LENGTH_DELIMITED,
+        Some(_) => WIRE_TYPE_VARINT,
+    }
+}
+
+/// Only scalar numeric and enum types can use the packed encoding.
+fn is_packable(field: &FieldDescriptorProto) -> bool {
+    match field.r#type.and_then(Type::from_i32) {
+        None | Some(Type::String) | Some(Type::Bytes) | Some(Type::Message) | Some(Type::Group) => {
+            false
+        }
+        Some(_) => true,
+    }
+}
+
+fn has_label(field: &FieldDescriptorProto, label: Label) -> bool {
+    field.label == Some(label as i32)
+}
+
+/// Fields without a label are treated as optional, matching protoc's default.
+fn is_optional(field: &FieldDescriptorProto) -> bool {
+    field.proto3_optional.unwrap_or(false)
+        || field.label.is_none()
+        || has_label(field, Label::Optional)
+}
+
+macro_rules! insert_field_option {
+    ($name: ident, $map: ident, $opt: ident) => {
+        try_insert_option($map, stringify!($name), &$opt.$name)?;
+    };
+}
+
 fn serialize_field_options<S: Serializer>(
-    _options: &Option<FieldOptions>,
+    options: &Option<FieldOptions>,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    // let options = match options {
-    //     None => return serializer.serialize_none(),
-    //     Some(options) => options,
-    // };
-    let map = HashMap::<String, String>::new();
-    // todo builtin options
+    let mut map = HashMap::new();
+    if let Some(options) = options {
+        insert_builtin_field_options(&mut map, options)
+            .map_err(|err| S::Error::custom(field_options_error(err)))?;
+    }
     debug!("Serializing field options: {:?}", map);
     serializer.collect_map(map)
 }
 
+fn field_options_error(err: impl Error) -> String {
+    format!("error in serialize_field_options: {}", err)
+}
+
+fn insert_builtin_field_options(
+    map: &mut HashMap<String, serde_json::Value>,
+    options: &FieldOptions,
+) -> Result<(), serde_json::Error> {
+    insert_field_option!(deprecated, map, options);
+    insert_field_option!(packed, map, options);
+    insert_field_option!(lazy, map, options);
+    try_insert_option(map, "jstype", &options.jstype.map(jstype_option_name))?;
+    try_insert_option(map, "ctype", &options.ctype.map(ctype_option_name))?;
+    insert_field_option!(weak, map, options);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use prost::Extendable;
-    use prost_types::field_descriptor_proto::Label;
+    use prost_types::field_descriptor_proto::{Label, Type};
     use prost_types::{FieldDescriptorProto, FieldOptions};
     use std::collections::HashMap;
 
@@ -282,7 +720,7 @@ mod tests {
     use crate::renderer::context::message::MapData;
     use crate::renderer::overlay_config::OverlayConfig;
     use crate::renderer::primitive;
-    use crate::renderer::RendererConfig;
+    use crate::renderer::{RegexOverride, RendererConfig};
 
     #[test]
     fn field_name() -> Result<()> {
@@ -291,11 +729,29 @@ mod tests {
         let mut field = FieldDescriptorProto::default();
         field.name = Some(name.clone());
         field.type_name = Some(primitive::FLOAT.to_owned());
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert_eq!(context.field_name.to_owned(), name);
         Ok(())
     }
 
+    #[test]
+    fn message_name() -> Result<()> {
+        let config = RendererConfig::default();
+        let message_name = "Message".to_owned();
+        let field = field_with_required();
+        let context = FieldContext::new(
+            &field,
+            None,
+            Some(&message_name),
+            &message::MapData::new(),
+            false,
+            &config,
+        )?;
+        assert_eq!(context.message_name, Some(message_name));
+        Ok(())
+    }
+
     #[test]
     fn override_field_name() -> Result<()> {
         let old_name = "bad_name".to_owned();
@@ -309,11 +765,84 @@ mod tests {
         let mut field = FieldDescriptorProto::default();
         field.name = Some(old_name);
         field.type_name = Some(primitive::FLOAT.to_owned());
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert_eq!(context.field_name.to_owned(), new_name);
         Ok(())
     }
 
+    #[test]
+    fn regex_override_field_name() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.field_name_regex_override = vec![
+            RegexOverride::new("^m_(.*)$", "$1")?,
+            RegexOverride::new("(.*)_id$", "${1}_key")?,
+        ];
+        let mut field = FieldDescriptorProto::default();
+        field.name = Some("m_owner_id".to_owned());
+        field.type_name = Some(primitive::FLOAT.to_owned());
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
+        assert_eq!(context.field_name, "owner_key");
+        Ok(())
+    }
+
+    #[test]
+    fn exact_override_wins_over_regex_override() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.field_name_regex_override = vec![RegexOverride::new("(.*)_id$", "$1")?];
+        // Exact overrides match against the regex result.
+        config
+            .field_name_override
+            .insert("owner".to_owned(), "exact".to_owned());
+        let mut field = FieldDescriptorProto::default();
+        field.name = Some("owner_id".to_owned());
+        field.type_name = Some(primitive::FLOAT.to_owned());
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
+        assert_eq!(context.field_name, "exact");
+        Ok(())
+    }
+
+    mod reserved_keywords {
+        use anyhow::Result;
+        use prost_types::FieldDescriptorProto;
+
+        use crate::renderer::context::field::FieldContext;
+        use crate::renderer::context::message;
+        use crate::renderer::primitive;
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn colliding_name_is_escaped() -> Result<()> {
+            assert_eq!(field_name_with_rust_keywords("type")?, "type_");
+            Ok(())
+        }
+
+        #[test]
+        fn escaped_after_case_conversion() -> Result<()> {
+            assert_eq!(field_name_with_rust_keywords("Type")?, "type_");
+            Ok(())
+        }
+
+        #[test]
+        fn non_colliding_name_is_untouched() -> Result<()> {
+            assert_eq!(field_name_with_rust_keywords("kind")?, "kind");
+            Ok(())
+        }
+
+        fn field_name_with_rust_keywords(name: &str) -> Result<String> {
+            let mut config = RendererConfig::default();
+            config.reserved_keywords = vec!["type".to_owned(), "match".to_owned()];
+            let mut field = FieldDescriptorProto::default();
+            field.name = Some(name.to_owned());
+            field.type_name = Some(primitive::FLOAT.to_owned());
+            let context =
+                FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
+            Ok(context.field_name)
+        }
+    }
+
     #[test]
     fn field_name_case_change() -> Result<()> {
         let mut config = RendererConfig::default();
@@ -322,7 +851,8 @@ mod tests {
         let mut field = FieldDescriptorProto::default();
         field.name = Some(name.clone());
         field.type_name = Some(primitive::FLOAT.to_owned());
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert_eq!(context.field_name.to_owned(), "TEST_NAME");
         Ok(())
     }
@@ -338,7 +868,8 @@ mod tests {
         options.set_extension_data(&proto_options::NATIVE_TYPE, expected_type.to_owned())?;
         field.options = Some(options);
 
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert_eq!(context.relative_type, Some("custom_type".to_owned()));
         Ok(())
     }
@@ -383,7 +914,8 @@ mod tests {
             let mut field = FieldDescriptorProto::default();
             field.name = Some("field_name".to_owned());
             field.type_name = Some(proto_type_name.to_owned());
-            let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+            let context =
+                FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
             assert_eq!(
                 context.fully_qualified_type.as_ref(),
                 config.type_config.get(proto_type_name),
@@ -392,6 +924,206 @@ mod tests {
         }
     }
 
+    #[test]
+    fn proto_type_of_scalar_field() -> Result<()> {
+        let mut field = field_with_required();
+        field.r#type = Some(Type::Int32 as i32);
+        let context = FieldContext::new(
+            &field,
+            None,
+            None,
+            &message::MapData::new(),
+            false,
+            &RendererConfig::default(),
+        )?;
+        assert_eq!(context.proto_type_number, Some(5));
+        assert_eq!(
+            context.proto_type_name.as_ref().map(String::as_str),
+            Some("TYPE_INT32")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn proto_type_of_message_field() -> Result<()> {
+        let mut field = field_with_required();
+        field.r#type = Some(Type::Message as i32);
+        field.type_name = Some(".root.SomeMessage".to_owned());
+        let context = FieldContext::new(
+            &field,
+            None,
+            None,
+            &message::MapData::new(),
+            false,
+            &RendererConfig::default(),
+        )?;
+        assert_eq!(context.proto_type_number, Some(11));
+        assert_eq!(
+            context.proto_type_name.as_ref().map(String::as_str),
+            Some("TYPE_MESSAGE")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn type_kind() -> Result<()> {
+        let context_with_type = |proto_type: Type, type_name: Option<&str>| {
+            let mut field = field_with_required();
+            field.r#type = Some(proto_type as i32);
+            field.type_name = type_name.map(str::to_owned);
+            FieldContext::new(
+                &field,
+                None,
+                None,
+                &message::MapData::new(),
+                false,
+                &RendererConfig::default(),
+            )
+        };
+        assert_eq!(context_with_type(Type::Int32, None)?.type_kind(), "scalar");
+        assert_eq!(
+            context_with_type(Type::Enum, Some(".root.SomeEnum"))?.type_kind(),
+            "enum"
+        );
+        assert_eq!(
+            context_with_type(Type::Message, Some(".root.SomeMessage"))?.type_kind(),
+            "message"
+        );
+        assert_eq!(
+            context_with_type(Type::Group, Some(".root.SomeGroup"))?.type_kind(),
+            "group"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn overlay_target_switches_overlay_value() -> Result<()> {
+        let field = field_with_required();
+        let mut config = RendererConfig {
+            overlays: OverlayConfig::new(
+                HashMap::new(),
+                HashMap::from([
+                    (
+                        "debug".to_owned(),
+                        HashMap::from([(
+                            "some_key".to_owned(),
+                            serde_yaml::Value::String("debug_value".to_owned()),
+                        )]),
+                    ),
+                    (
+                        "release".to_owned(),
+                        HashMap::from([(
+                            "some_key".to_owned(),
+                            serde_yaml::Value::String("release_value".to_owned()),
+                        )]),
+                    ),
+                ]),
+            ),
+            ..Default::default()
+        };
+        let map_data = message::MapData::new();
+
+        config.overlays.set_active_targets(vec!["debug".to_owned()]);
+        let context = FieldContext::new(&field, None, None, &map_data, false, &config)?;
+        assert_eq!(
+            &context.overlays.get("some_key").expect("key did not exist"),
+            &"debug_value"
+        );
+
+        config
+            .overlays
+            .set_active_targets(vec!["release".to_owned()]);
+        let context = FieldContext::new(&field, None, None, &map_data, false, &config)?;
+        assert_eq!(
+            &context.overlays.get("some_key").expect("key did not exist"),
+            &"release_value"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn extendee() -> Result<()> {
+        let mut field = field_with_required();
+        field.extendee = Some(".root.sub.Extended".to_owned());
+        let context = FieldContext::new(
+            &field,
+            Some(&"root".to_owned()),
+            None,
+            &message::MapData::new(),
+            false,
+            &RendererConfig::default(),
+        )?;
+        assert_eq!(
+            context.extendee.as_ref().map(String::as_str),
+            Some("sub.Extended")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn no_extendee_for_normal_field() -> Result<()> {
+        let field = field_with_required();
+        let context = FieldContext::new(
+            &field,
+            Some(&"root".to_owned()),
+            None,
+            &message::MapData::new(),
+            false,
+            &RendererConfig::default(),
+        )?;
+        assert_eq!(context.extendee, None);
+        Ok(())
+    }
+
+    #[test]
+    fn default_value_bytes_hex() -> Result<()> {
+        let context = bytes_field_context(r"\x01\x02")?;
+        assert_eq!(context.default_value_bytes, vec![1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn default_value_bytes_octal_and_literal() -> Result<()> {
+        let context = bytes_field_context(r"\000\377a\n")?;
+        assert_eq!(context.default_value_bytes, vec![0, 255, b'a', b'\n']);
+        Ok(())
+    }
+
+    #[test]
+    fn default_value_bytes_invalid_escape_errors() {
+        assert!(bytes_field_context(r"\q").is_err());
+    }
+
+    #[test]
+    fn default_value_bytes_empty_for_non_bytes_field() -> Result<()> {
+        let mut field = field_with_required();
+        field.default_value = Some("1.5".to_owned());
+        let context = FieldContext::new(
+            &field,
+            None,
+            None,
+            &message::MapData::new(),
+            false,
+            &RendererConfig::default(),
+        )?;
+        assert!(context.default_value_bytes.is_empty());
+        Ok(())
+    }
+
+    fn bytes_field_context(default_value: &str) -> Result<FieldContext> {
+        let mut field = field_with_required();
+        field.r#type = Some(Type::Bytes as i32);
+        field.default_value = Some(default_value.to_owned());
+        FieldContext::new(
+            &field,
+            None,
+            None,
+            &message::MapData::new(),
+            false,
+            &RendererConfig::default(),
+        )
+    }
+
     #[test]
     fn package_separator_replaced_in_types() -> Result<()> {
         let mut field = FieldDescriptorProto::default();
@@ -404,6 +1136,7 @@ mod tests {
             Some(&"root".to_owned()),
             None,
             &message::MapData::new(),
+            false,
             &config,
         )?;
         assert_eq!(
@@ -417,12 +1150,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn package_namespace_map_applied_to_types() -> Result<()> {
+        let mut field = FieldDescriptorProto::default();
+        field.name = Some("test".to_owned());
+        field.type_name = Some(".root.sub.TypeName".to_owned());
+        let mut config = RendererConfig::default();
+        config
+            .package_namespace_map
+            .insert("root".to_owned(), "com.root".to_owned());
+        let context = FieldContext::new(
+            &field,
+            Some(&"root".to_owned()),
+            None,
+            &message::MapData::new(),
+            false,
+            &config,
+        )?;
+        assert_eq!(
+            context.relative_type.as_ref().map(String::as_str),
+            Some("sub.TypeName")
+        );
+        assert_eq!(
+            context.fully_qualified_type.as_ref().map(String::as_str),
+            Some("com.root.sub.TypeName")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn relative_root_package_used_instead_of_package() -> Result<()> {
+        let mut field = FieldDescriptorProto::default();
+        field.name = Some("test".to_owned());
+        field.type_name = Some(".root.sub.TypeName".to_owned());
+        let mut config = RendererConfig::default();
+        config.relative_root_package = Some("root".to_owned());
+        let context = FieldContext::new(
+            &field,
+            Some(&"root.sub.other".to_owned()),
+            None,
+            &message::MapData::new(),
+            false,
+            &config,
+        )?;
+        assert_eq!(
+            context.relative_type.as_ref().map(String::as_str),
+            Some("sub.TypeName")
+        );
+        Ok(())
+    }
+
     #[test]
     fn missing_name_errors() {
         let config = RendererConfig::default();
         let mut field = FieldDescriptorProto::default();
         field.type_name = Some(primitive::FLOAT.to_owned());
-        let result = FieldContext::new(&field, None, None, &message::MapData::new(), &config);
+        let result =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config);
         assert!(result.is_err());
     }
 
@@ -431,7 +1215,8 @@ mod tests {
         let config = RendererConfig::default();
         let mut field = FieldDescriptorProto::default();
         field.name = Some("field_name".to_owned());
-        let result = FieldContext::new(&field, None, None, &message::MapData::new(), &config);
+        let result =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config);
         assert!(result.is_err());
     }
 
@@ -442,7 +1227,8 @@ mod tests {
         let mut field = FieldDescriptorProto::default();
         field.name = Some("field_name".to_owned());
         field.type_name = Some("TypeName".to_owned());
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert_eq!(
             context.fully_qualified_type.as_ref().map(String::as_str),
             Some("TYPE_NAME")
@@ -457,7 +1243,8 @@ mod tests {
         let mut field = FieldDescriptorProto::default();
         field.name = Some("field_name".to_owned());
         field.r#type = Some(2);
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert_eq!(
             context.fully_qualified_type,
             Some(primitive::FLOAT.to_ascii_lowercase())
@@ -470,11 +1257,353 @@ mod tests {
         let mut field = field_with_required();
         field.label = Some(Label::Repeated as i32);
         let config = RendererConfig::default();
-        let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
         assert!(context.is_array);
+        assert_eq!(context.array_type, None);
         Ok(())
     }
 
+    #[test]
+    fn array_type() -> Result<()> {
+        assert_eq!(
+            array_type_with("Vec<{type}>")?,
+            Some("Vec<Item>".to_owned())
+        );
+        assert_eq!(array_type_with("{type}[]")?, Some("Item[]".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn no_array_type_for_single_field() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.array_type = Some("Vec<{type}>".to_owned());
+        let field = field_with_required();
+        let context =
+            FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
+        assert_eq!(context.array_type, None);
+        Ok(())
+    }
+
+    fn array_type_with(template: &str) -> Result<Option<String>> {
+        let mut config = RendererConfig::default();
+        config.array_type = Some(template.to_owned());
+        let mut field = field_with_required();
+        field.label = Some(Label::Repeated as i32);
+        field.type_name = Some(".root.Item".to_owned());
+        let package = "root".to_owned();
+        let context = FieldContext::new(
+            &field,
+            Some(&package),
+            None,
+            &message::MapData::new(),
+            false,
+            &config,
+        )?;
+        Ok(context.array_type)
+    }
+
+    mod jstype {
+        use anyhow::Result;
+        use prost_types::field_options::JsType;
+        use prost_types::FieldOptions;
+
+        use crate::renderer::context::field::tests::field_with_required;
+        use crate::renderer::context::{message, FieldContext};
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn without_options() -> Result<()> {
+            let field = field_with_required();
+            let context = FieldContext::new(
+                &field,
+                None,
+                None,
+                &message::MapData::new(),
+                false,
+                &RendererConfig::default(),
+            )?;
+            assert_eq!(context.jstype_name(), "normal");
+            Ok(())
+        }
+
+        #[test]
+        fn normal() -> Result<()> {
+            assert_eq!(jstype_name(JsType::JsNormal)?, "normal");
+            Ok(())
+        }
+
+        #[test]
+        fn string() -> Result<()> {
+            assert_eq!(jstype_name(JsType::JsString)?, "string");
+            Ok(())
+        }
+
+        #[test]
+        fn number() -> Result<()> {
+            assert_eq!(jstype_name(JsType::JsNumber)?, "number");
+            Ok(())
+        }
+
+        fn jstype_name(jstype: JsType) -> Result<String> {
+            let mut field = field_with_required();
+            field.options = Some(FieldOptions {
+                jstype: Some(jstype as i32),
+                ..Default::default()
+            });
+            let context = FieldContext::new(
+                &field,
+                None,
+                None,
+                &message::MapData::new(),
+                false,
+                &RendererConfig::default(),
+            )?;
+            Ok(context.jstype_name().to_owned())
+        }
+    }
+
+    mod builtin_options {
+        use anyhow::Result;
+        use prost_types::field_options::{CType, JsType};
+        use prost_types::FieldOptions;
+
+        use crate::renderer::context::field::tests::field_with_required;
+        use crate::renderer::context::{message, FieldContext};
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn enum_options_serialize_as_names() -> Result<()> {
+            let options = serialized_options(FieldOptions {
+                jstype: Some(JsType::JsString as i32),
+                ctype: Some(CType::StringPiece as i32),
+                deprecated: Some(true),
+                ..Default::default()
+            })?;
+            assert_eq!(options["jstype"], "JS_STRING");
+            assert_eq!(options["ctype"], "STRING_PIECE");
+            assert_eq!(options["deprecated"], true);
+            Ok(())
+        }
+
+        #[test]
+        fn unknown_enum_option_serializes_as_number() -> Result<()> {
+            let options = serialized_options(FieldOptions {
+                jstype: Some(99),
+                ..Default::default()
+            })?;
+            assert_eq!(options["jstype"], 99);
+            assert!(options.get("ctype").is_none());
+            Ok(())
+        }
+
+        fn serialized_options(options: FieldOptions) -> Result<serde_json::Value> {
+            let mut field = field_with_required();
+            field.options = Some(options);
+            let context = FieldContext::new(
+                &field,
+                None,
+                None,
+                &message::MapData::new(),
+                false,
+                &RendererConfig::default(),
+            )?;
+            Ok(serde_json::to_value(&context)?["options"].clone())
+        }
+    }
+
+    mod cardinality {
+        use anyhow::Result;
+        use prost_types::field_descriptor_proto::Label;
+        use prost_types::FieldDescriptorProto;
+
+        use crate::renderer::context::field::tests::field_with_required;
+        use crate::renderer::context::{message, FieldContext};
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn proto2_required() -> Result<()> {
+            let mut field = field_with_required();
+            field.label = Some(Label::Required as i32);
+            let context = context(&field)?;
+            assert!(context.is_required);
+            assert!(!context.is_optional);
+            assert!(!context.is_repeated);
+            Ok(())
+        }
+
+        #[test]
+        fn proto3_repeated() -> Result<()> {
+            let mut field = field_with_required();
+            field.label = Some(Label::Repeated as i32);
+            let context = context(&field)?;
+            assert!(context.is_repeated);
+            assert!(!context.is_optional);
+            assert!(!context.is_required);
+            Ok(())
+        }
+
+        #[test]
+        fn proto3_optional() -> Result<()> {
+            let mut field = field_with_required();
+            field.label = Some(Label::Optional as i32);
+            field.proto3_optional = Some(true);
+            field.oneof_index = Some(0);
+            let context = context(&field)?;
+            assert!(context.is_optional);
+            assert!(!context.is_required);
+            assert!(!context.is_repeated);
+            Ok(())
+        }
+
+        #[test]
+        fn proto3_implicit() -> Result<()> {
+            let mut field = field_with_required();
+            field.label = Some(Label::Optional as i32);
+            let context = context(&field)?;
+            assert!(context.is_optional);
+            assert!(!context.is_required);
+            assert!(!context.is_repeated);
+            Ok(())
+        }
+
+        fn context(field: &FieldDescriptorProto) -> Result<FieldContext> {
+            let config = RendererConfig::default();
+            FieldContext::new(field, None, None, &message::MapData::new(), false, &config)
+        }
+    }
+
+    mod packed {
+        use anyhow::Result;
+        use prost_types::field_descriptor_proto::{Label, Type};
+        use prost_types::{FieldDescriptorProto, FieldOptions};
+
+        use crate::renderer::context::field::is_packed;
+        use crate::renderer::context::field::tests::field_with_required;
+        use crate::renderer::context::{message, FieldContext};
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn explicitly_packed() -> Result<()> {
+            let mut field = repeated(Type::Int32);
+            field.options = Some(FieldOptions {
+                packed: Some(true),
+                ..Default::default()
+            });
+            let context = FieldContext::new(
+                &field,
+                None,
+                None,
+                &message::MapData::new(),
+                false,
+                &RendererConfig::default(),
+            )?;
+            assert!(context.is_packed());
+            Ok(())
+        }
+
+        #[test]
+        fn proto3_repeated_int_packed_by_default() {
+            let field = repeated(Type::Int32);
+            assert!(is_packed(&field, true));
+            assert!(!is_packed(&field, false));
+        }
+
+        #[test]
+        fn explicitly_unpacked_under_proto3() {
+            let mut field = repeated(Type::Int32);
+            field.options = Some(FieldOptions {
+                packed: Some(false),
+                ..Default::default()
+            });
+            assert!(!is_packed(&field, true));
+        }
+
+        #[test]
+        fn string_is_never_packed() {
+            let mut field = repeated(Type::String);
+            field.options = Some(FieldOptions {
+                packed: Some(true),
+                ..Default::default()
+            });
+            assert!(!is_packed(&field, true));
+        }
+
+        #[test]
+        fn singular_is_never_packed() {
+            assert!(!is_packed(&field_with_required(), true));
+        }
+
+        fn repeated(proto_type: Type) -> FieldDescriptorProto {
+            let mut field = field_with_required();
+            field.r#type = Some(proto_type as i32);
+            field.label = Some(Label::Repeated as i32);
+            field
+        }
+    }
+
+    mod wire_tag {
+        use anyhow::Result;
+        use prost_types::field_descriptor_proto::{Label, Type};
+        use prost_types::FieldDescriptorProto;
+
+        use crate::renderer::context::field::tests::field_with_required;
+        use crate::renderer::context::{message, FieldContext};
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn int32_is_varint() -> Result<()> {
+            assert_eq!(context(Type::Int32, 1)?.wire_tag(), 8);
+            Ok(())
+        }
+
+        #[test]
+        fn string_is_length_delimited() -> Result<()> {
+            assert_eq!(context(Type::String, 2)?.wire_tag(), 18);
+            Ok(())
+        }
+
+        #[test]
+        fn fixed_width() -> Result<()> {
+            assert_eq!(context(Type::Fixed64, 3)?.wire_tag(), 25);
+            assert_eq!(context(Type::Float, 3)?.wire_tag(), 29);
+            Ok(())
+        }
+
+        #[test]
+        fn packed_is_length_delimited() -> Result<()> {
+            let mut field = field(Type::Int32, 4);
+            field.label = Some(Label::Repeated as i32);
+            assert_eq!(new(&field, false)?.wire_tag(), 32);
+            let context = new(&field, true)?;
+            assert!(context.is_packed());
+            assert_eq!(context.wire_tag(), 34);
+            Ok(())
+        }
+
+        fn context(proto_type: Type, number: i32) -> Result<FieldContext> {
+            new(&field(proto_type, number), false)
+        }
+
+        fn field(proto_type: Type, number: i32) -> FieldDescriptorProto {
+            let mut field = field_with_required();
+            field.r#type = Some(proto_type as i32);
+            field.number = Some(number);
+            field
+        }
+
+        fn new(field: &FieldDescriptorProto, proto3: bool) -> Result<FieldContext> {
+            FieldContext::new(
+                field,
+                None,
+                None,
+                &message::MapData::new(),
+                proto3,
+                &RendererConfig::default(),
+            )
+        }
+    }
+
     mod map {
         use anyhow::Result;
         use prost_types::FieldDescriptorProto;
@@ -497,11 +1626,14 @@ mod tests {
                 MapEntryData {
                     key: ProtoType::Type(int_proto_type),
                     value: ProtoType::TypeName(".root.sub.inner.TypeName".to_owned()),
+                    key_number: 1,
+                    value_number: 2,
                 },
             );
 
             let expected_key = primitive_type_name(int_proto_type, &config)?;
-            let context = FieldContext::new(&field, Some(&package), None, &map_data, &config)?;
+            let context =
+                FieldContext::new(&field, Some(&package), None, &map_data, false, &config)?;
             assert!(context.is_map);
             assert_eq!(
                 context.fully_qualified_key_type,
@@ -531,12 +1663,14 @@ mod tests {
                 MapEntryData {
                     key: ProtoType::Type(int_proto_type),
                     value: ProtoType::Type(float_proto_type),
+                    key_number: 1,
+                    value_number: 2,
                 },
             );
 
             let expected_key = primitive_type_name(int_proto_type, &config)?;
             let expected_value = primitive_type_name(float_proto_type, &config)?;
-            let context = FieldContext::new(&field, None, None, &map_data, &config)?;
+            let context = FieldContext::new(&field, None, None, &map_data, false, &config)?;
             assert!(context.is_map);
             assert_eq!(
                 context.fully_qualified_key_type,
@@ -548,6 +1682,8 @@ mod tests {
             );
             assert_eq!(context.relative_key_type, Some(expected_key.to_owned()));
             assert_eq!(context.relative_value_type, Some(expected_value.to_owned()));
+            assert_eq!(context.map_key_number, Some(1));
+            assert_eq!(context.map_value_number, Some(2));
             Ok(())
         }
 
@@ -555,7 +1691,8 @@ mod tests {
         fn non_map_has_no_map_fields() -> Result<()> {
             let field = field_with_required();
             let config = RendererConfig::default();
-            let context = FieldContext::new(&field, None, None, &message::MapData::new(), &config)?;
+            let context =
+                FieldContext::new(&field, None, None, &message::MapData::new(), false, &config)?;
             assert!(!context.is_map);
             assert!(context.fully_qualified_key_type.is_none());
             assert!(context.fully_qualified_value_type.is_none());
@@ -578,7 +1715,7 @@ mod tests {
         let config = RendererConfig::default();
         let mut field = field_with_required();
         field.oneof_index = Some(0);
-        let context = FieldContext::new(&field, None, None, &MapData::new(), &config)?;
+        let context = FieldContext::new(&field, None, None, &MapData::new(), false, &config)?;
         assert!(context.is_oneof);
         Ok(())
     }
@@ -610,6 +1747,7 @@ mod tests {
             Some(&package),
             Some(&message_name),
             &message::MapData::default(),
+            false,
             &config,
         )?;
         assert_eq!(
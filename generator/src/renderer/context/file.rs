@@ -6,20 +6,34 @@ use prost_types::{FileDescriptorProto, FileOptions};
 use serde::ser::Error;
 use serde::{Deserialize, Serialize, Serializer};
 
+use crate::renderer::context::directive::{self, FileDirectives};
 use crate::renderer::context::overlayed::Overlayed;
-use crate::renderer::context::{EnumContext, ImportContext, MessageContext};
+use crate::renderer::context::type_graph::TypeGraph;
+use crate::renderer::context::{EnumContext, ImportContext, MapValueTypes, MessageContext};
 use crate::renderer::proto::TypePath;
 use crate::renderer::RendererConfig;
 use crate::util;
 
+const SYNTAX_PROTO2: &str = "proto2";
+const SYNTAX_PROTO3: &str = "proto3";
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FileContext {
     /// Relative path to the proto file this context is based on.
     source_file: String,
 
+    /// Lowercase hex SHA-256 of the proto file's source, e.g. to record which version of the
+    /// proto a file was generated from. Empty when the source isn't available, e.g. when
+    /// rendering from a --buf-image.
+    source_file_hash: String,
+
     /// Package defined in the file.
     package: String,
 
+    /// Package split into its individual components, e.g. for building nested namespaces.
+    /// Empty if the file has no package.
+    package_components: Vec<String>,
+
     /// Other proto file imports of this proto file.
     imports: Vec<ImportContext>,
 
@@ -29,6 +43,19 @@ pub struct FileContext {
     /// Messages defined in this proto file.
     messages: Vec<MessageContext>,
 
+    /// Number of top-level enums in `enums`.
+    enum_count: usize,
+
+    /// Number of top-level messages in `messages`.
+    message_count: usize,
+
+    /// Set for files using protobuf editions (see `--editions`), to the descriptor's `syntax`,
+    /// which protoc sets to "editions" for them. None for proto2 and proto3 files.
+    ///
+    /// The edition itself, e.g. 2023, is a newer descriptor field which the prost-types used by
+    /// protox doesn't decode yet.
+    edition: Option<String>,
+
     /// Proto file options are serialized as an object like so:
     /// ```json
     /// {
@@ -47,12 +74,25 @@ pub struct FileContext {
     ///
     /// Built-in proto option names and types can be seen here:
     /// https://docs.rs/prost-types/latest/prost_types/struct.FileOptions.html
+    /// All of these except the deprecated `java_generate_equals_and_hash` and
+    /// `uninterpreted_option` are serialized, e.g. `{{options.go_package}}`.
     ///
     /// Additionally, a few protox-specific options are supported. See the proto files at
     /// `protox/proto_options/protos` for more info.
     #[serde(serialize_with = "serialize_file_options", skip_deserializing)]
     options: Option<FileOptions>,
 
+    /// Keys from the renderer config file which are not otherwise used by protox, for user
+    /// settings, e.g. `{{config_extra.my_setting}}`.
+    config_extra: HashMap<String, serde_yaml::Value>,
+
+    /// The renderer's configured `file_extension`, e.g. `rs`.
+    file_extension: String,
+
+    /// Path of the file being rendered, relative to the output directory, e.g. `sub/file.rs`.
+    /// When `one_file_per_package` is true, this is the package file. Empty outside of rendering.
+    output_path: String,
+
     // Config overlays applied to this File.
     // Only available in scripted renderer.
     #[serde(skip)]
@@ -61,17 +101,40 @@ pub struct FileContext {
 
 impl FileContext {
     pub fn new(proto: &FileDescriptorProto, config: &RendererConfig) -> Result<Self> {
+        let map_values =
+            MapValueTypes::from_files(std::slice::from_ref(proto), config.map_detection);
+        Self::with_map_value_types(proto, config, &map_values)
+    }
+
+    /// Like `new`, but with the map value types of every file in the descriptor set, so that
+    /// messages used as map values in other files are detected.
+    pub(crate) fn with_map_value_types(
+        proto: &FileDescriptorProto,
+        config: &RendererConfig,
+        map_values: &MapValueTypes,
+    ) -> Result<Self> {
         debug!(
             "Creating file context: {}",
             util::str_or_unknown(&proto.name)
         );
+        let package = package(proto, &config);
+        let enums = enums(proto, proto.package.as_ref(), config)?;
+        let messages = messages(proto, proto.package.as_ref(), config, map_values)?;
         let context = Self {
             source_file: source_file(proto)?,
-            package: package(proto, &config),
-            imports: imports(proto, &config.ignored_imports)?,
-            enums: enums(proto, proto.package.as_ref(), config)?,
-            messages: messages(proto, proto.package.as_ref(), config)?,
+            source_file_hash: String::new(),
+            package_components: package_components(&package, &config.package_separator),
+            package,
+            imports: imports(proto, config)?,
+            enum_count: enums.len(),
+            message_count: messages.len(),
+            edition: edition(proto),
+            enums,
+            messages,
             options: proto.options.clone(),
+            config_extra: config.extra.clone(),
+            file_extension: config.file_extension.clone(),
+            output_path: String::new(),
             overlays: config.overlays.by_target_opt_clone(&proto.name),
         };
         Ok(context)
@@ -80,9 +143,15 @@ impl FileContext {
     pub fn source_file(&self) -> &str {
         &self.source_file
     }
+    pub fn source_file_hash(&self) -> &str {
+        &self.source_file_hash
+    }
     pub fn package(&self) -> &str {
         &self.package
     }
+    pub fn package_components(&self) -> &Vec<String> {
+        &self.package_components
+    }
     pub fn imports(&self) -> &Vec<ImportContext> {
         &self.imports
     }
@@ -92,9 +161,58 @@ impl FileContext {
     pub fn messages(&self) -> &Vec<MessageContext> {
         &self.messages
     }
+    pub fn enum_count(&self) -> usize {
+        self.enum_count
+    }
+    pub fn message_count(&self) -> usize {
+        self.message_count
+    }
+    pub fn edition(&self) -> Option<&String> {
+        self.edition.as_ref()
+    }
     pub fn options(&self) -> &Option<FileOptions> {
         &self.options
     }
+    pub fn file_extension(&self) -> &str {
+        &self.file_extension
+    }
+    pub fn output_path(&self) -> &str {
+        &self.output_path
+    }
+    pub(crate) fn set_output_path(&mut self, output_path: String) {
+        self.output_path = output_path;
+    }
+    pub(crate) fn set_source_file_hash(&mut self, source_file_hash: String) {
+        self.source_file_hash = source_file_hash;
+    }
+    /// Sets the output path of each import found in `output_paths`, keyed by proto file name, to
+    /// the path it is rendered to in this run.
+    pub(crate) fn set_import_output_paths(
+        &mut self,
+        output_paths: &HashMap<String, String>,
+        config: &RendererConfig,
+    ) {
+        for import in &mut self.imports {
+            if let Some(path) = output_paths.get(import.file_path()) {
+                import.set_output_file_path(path, config.output_include_base.as_deref());
+            }
+        }
+        sort_imports(&mut self.imports, config);
+    }
+    pub(crate) fn clear_enums(&mut self) {
+        self.enums.clear();
+        self.enum_count = 0;
+    }
+    pub(crate) fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.message_count = 0;
+    }
+    pub fn config_extra(&self, key: &str) -> serde_yaml::Value {
+        self.config_extra
+            .get(key)
+            .map(Clone::clone)
+            .unwrap_or(serde_yaml::Value::Null)
+    }
 }
 
 impl Overlayed for FileContext {
@@ -113,7 +231,8 @@ fn package(file: &FileDescriptorProto, config: &RendererConfig) -> String {
     match &file.package {
         None => String::new(),
         Some(package) => {
-            let mut type_path = TypePath::from_package(package);
+            let package = config.package_namespace(package);
+            let mut type_path = TypePath::from_package(&package);
             type_path.set_separator(&config.package_separator);
             type_path.set_package_case(Some(config.case_config.package));
             type_path.to_string()
@@ -121,17 +240,48 @@ fn package(file: &FileDescriptorProto, config: &RendererConfig) -> String {
     }
 }
 
-fn imports(file: &FileDescriptorProto, ignored_imports: &[String]) -> Result<Vec<ImportContext>> {
+fn package_components(package: &str, separator: &str) -> Vec<String> {
+    if package.is_empty() {
+        return vec![];
+    }
+    if separator.is_empty() {
+        return vec![package.to_owned()];
+    }
+    package.split(separator).map(str::to_owned).collect()
+}
+
+fn edition(file: &FileDescriptorProto) -> Option<String> {
+    match file.syntax.as_deref() {
+        None | Some(SYNTAX_PROTO2) | Some(SYNTAX_PROTO3) => None,
+        Some(syntax) => Some(syntax.to_owned()),
+    }
+}
+
+fn imports(file: &FileDescriptorProto, config: &RendererConfig) -> Result<Vec<ImportContext>> {
     let mut imports = Vec::new();
-    for import in &file.dependency {
-        if ignored_imports.contains(import) {
+    for (i, import) in file.dependency.iter().enumerate() {
+        if config.ignored_imports.contains(import) {
             continue;
         }
-        imports.push(ImportContext::new(import)?);
+        // Public and weak dependencies are listed as indices into `dependency`.
+        let mut context = ImportContext::new(import)?;
+        context.set_is_public(file.public_dependency.contains(&(i as i32)));
+        context.set_is_weak(file.weak_dependency.contains(&(i as i32)));
+        context.set_file_extension(&config.file_extension);
+        context.set_include_base(config.output_include_base.as_deref());
+        imports.push(context);
     }
+    sort_imports(&mut imports, config);
     Ok(imports)
 }
 
+fn sort_imports(imports: &mut Vec<ImportContext>, config: &RendererConfig) {
+    if config.sort_imports {
+        imports.sort_by(|a, b| a.output_file_path().cmp(b.output_file_path()));
+        imports.dedup_by(|a, b| a.output_file_path() == b.output_file_path());
+    }
+}
+
 fn enums(
     file: &FileDescriptorProto,
     package: Option<&String>,
@@ -148,10 +298,32 @@ fn messages(
     file: &FileDescriptorProto,
     package: Option<&String>,
     config: &RendererConfig,
+    map_values: &MapValueTypes,
 ) -> Result<Vec<MessageContext>> {
+    let type_graph = TypeGraph::from_file(file);
+    let directives = FileDirectives::from_file(file);
+    let proto3 = file.syntax.as_deref() == Some(SYNTAX_PROTO3);
     let mut messages = Vec::new();
-    for message in &file.message_type {
-        messages.push(MessageContext::new(message, package, config)?);
+    for (i, message) in file.message_type.iter().enumerate() {
+        let path = directive::child_path(&[], directive::FILE_MESSAGE_TYPE, i);
+        if directives.is_skipped(&path) {
+            continue;
+        }
+        let context = MessageContext::with_type_graph(
+            message,
+            package,
+            package.map(String::as_str),
+            proto3,
+            config,
+            &type_graph,
+            map_values,
+            &directives,
+            &path,
+        )?;
+        if context.is_map_entry() {
+            continue;
+        }
+        messages.push(context);
     }
     Ok(messages)
 }
@@ -207,7 +379,7 @@ fn insert_builtin_file_options(
     Ok(())
 }
 
-fn try_insert_option<T: Serialize>(
+pub(super) fn try_insert_option<T: Serialize>(
     map: &mut HashMap<String, serde_json::Value>,
     name: impl Into<String>,
     value: &Option<T>,
@@ -221,13 +393,17 @@ fn try_insert_option<T: Serialize>(
 #[cfg(test)]
 mod tests {
     use crate::renderer::case::Case;
-    use crate::renderer::context::FileContext;
+    use crate::renderer::context::{FileContext, MapValueTypes, MessageContext};
     use crate::renderer::overlay_config::OverlayConfig;
     use crate::renderer::renderer_config::CaseConfig;
     use crate::renderer::{overlay_config, RendererConfig};
     use anyhow::Result;
     use prost::ExtensionSet;
-    use prost_types::{FileDescriptorProto, FileOptions};
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+        FileOptions, MessageOptions,
+    };
     use std::collections::{HashMap, HashSet};
 
     #[test]
@@ -264,6 +440,449 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn package_namespace_map() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config
+            .package_namespace_map
+            .insert("example".to_owned(), "com.example".to_owned());
+        config
+            .package_namespace_map
+            .insert("example.sub".to_owned(), "org.sub".to_owned());
+        let package = |package: &str| -> Result<String> {
+            let file = FileDescriptorProto {
+                name: Some("file_name".to_owned()),
+                package: Some(package.to_owned()),
+                ..Default::default()
+            };
+            Ok(FileContext::new(&file, &config)?.package)
+        };
+        assert_eq!(package("example")?, "com.example");
+        assert_eq!(package("example.other")?, "com.example.other");
+        assert_eq!(package("example.sub.inner")?, "org.sub.inner");
+        assert_eq!(package("examples")?, "examples");
+        assert_eq!(package("unmapped")?, "unmapped");
+        Ok(())
+    }
+
+    #[test]
+    fn package_components() -> Result<()> {
+        let config = RendererConfig {
+            package_separator: "::".to_string(),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            package: Some("some.package.name".to_owned()),
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &config)?;
+        assert_eq!(context.package_components, vec!["some", "package", "name"]);
+        Ok(())
+    }
+
+    #[test]
+    fn package_components_empty_without_package() -> Result<()> {
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert!(context.package_components.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn excludes_map_entry_messages() -> Result<()> {
+        let map_entry = DescriptorProto {
+            name: Some("SomeMapEntry".to_owned()),
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let message = DescriptorProto {
+            name: Some("MessageName".to_owned()),
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            message_type: vec![map_entry, message],
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert_eq!(context.messages.len(), 1);
+        assert_eq!(context.messages[0].name(), "MessageName");
+        Ok(())
+    }
+
+    #[test]
+    fn excludes_map_entry_nested_in_owner() -> Result<()> {
+        let entry_field = |name: &str, number| FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(Type::String as i32),
+            ..Default::default()
+        };
+        let map_entry = DescriptorProto {
+            name: Some("ValuesEntry".to_owned()),
+            field: vec![entry_field("key", 1), entry_field("value", 2)],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let owner = DescriptorProto {
+            name: Some("Owner".to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("values".to_owned()),
+                number: Some(1),
+                label: Some(Label::Repeated as i32),
+                r#type: Some(Type::Message as i32),
+                type_name: Some(".pkg.Owner.ValuesEntry".to_owned()),
+                ..Default::default()
+            }],
+            nested_type: vec![map_entry.clone()],
+            ..Default::default()
+        };
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            package: Some("pkg".to_owned()),
+            message_type: vec![owner],
+            ..Default::default()
+        };
+        let config = RendererConfig::default();
+        let context = FileContext::new(&file, &config)?;
+        assert_eq!(context.messages.len(), 1);
+        assert_eq!(context.messages[0].name(), "Owner");
+        assert!(context.messages[0].nested_messages().is_empty());
+
+        let entry = MessageContext::new(&map_entry, Some(&"pkg".to_owned()), &config)?;
+        assert!(entry.is_map_entry());
+        Ok(())
+    }
+
+    #[test]
+    fn sorted_and_deduplicated_imports() -> Result<()> {
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            dependency: vec![
+                "b/second.proto".to_owned(),
+                "a/first.proto".to_owned(),
+                "b/second.proto".to_owned(),
+                "c.proto".to_owned(),
+            ],
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert_eq!(context.imports.len(), 4);
+
+        let config = RendererConfig {
+            sort_imports: true,
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &config)?;
+        let imports = context
+            .imports
+            .iter()
+            .map(|import| import.file_path())
+            .collect::<Vec<_>>();
+        assert_eq!(imports, vec!["a/first.proto", "b/second.proto", "c.proto"]);
+        Ok(())
+    }
+
+    #[test]
+    fn edition_only_for_editions_files() -> Result<()> {
+        let mut file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert_eq!(context.edition(), None);
+
+        file.syntax = Some("editions".to_owned());
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert_eq!(context.edition().map(String::as_str), Some("editions"));
+        Ok(())
+    }
+
+    #[test]
+    fn proto3_fields_packed_by_default() -> Result<()> {
+        let message = DescriptorProto {
+            name: Some("MessageName".to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("values".to_owned()),
+                r#type: Some(Type::Int32 as i32),
+                label: Some(Label::Repeated as i32),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            message_type: vec![message],
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert!(!context.messages[0].fields()[0].is_packed());
+
+        file.syntax = Some("proto3".to_owned());
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert!(context.messages[0].fields()[0].is_packed());
+        Ok(())
+    }
+
+    #[test]
+    fn public_and_weak_imports() -> Result<()> {
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            dependency: vec![
+                "ignored.proto".to_owned(),
+                "normal.proto".to_owned(),
+                "public.proto".to_owned(),
+                "weak.proto".to_owned(),
+            ],
+            public_dependency: vec![2],
+            weak_dependency: vec![3],
+            ..Default::default()
+        };
+        let config = RendererConfig {
+            ignored_imports: vec!["ignored.proto".to_owned()],
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &config)?;
+        let imports = context
+            .imports
+            .iter()
+            .map(|import| (import.file_path(), import.is_public(), import.is_weak()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            imports,
+            vec![
+                ("normal.proto", false, false),
+                ("public.proto", true, false),
+                ("weak.proto", false, true),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn message_and_enum_counts() -> Result<()> {
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            message_type: vec![
+                message_with_field("First", ".Second"),
+                message_with_field("Second", ".First"),
+            ],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Kind".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert_eq!(context.message_count(), 2);
+        assert_eq!(context.enum_count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn two_message_cycle_is_recursive() -> Result<()> {
+        let file = FileDescriptorProto {
+            name: Some("file_name".to_owned()),
+            package: Some("pkg".to_owned()),
+            message_type: vec![
+                message_with_field("First", ".pkg.Second"),
+                message_with_field("Second", ".pkg.First"),
+                message_with_field("Third", ".pkg.First"),
+            ],
+            ..Default::default()
+        };
+        let context = FileContext::new(&file, &RendererConfig::default())?;
+        assert!(context.messages[0].fields()[0].is_recursive());
+        assert!(context.messages[1].fields()[0].is_recursive());
+        assert!(!context.messages[2].fields()[0].is_recursive());
+        Ok(())
+    }
+
+    #[test]
+    fn used_as_map_value_in_other_file() -> Result<()> {
+        let values = FileDescriptorProto {
+            name: Some("values".to_owned()),
+            package: Some("pkg".to_owned()),
+            message_type: vec![
+                message_with_field("MapOnly", ".pkg.Other"),
+                message_with_field("Normal", ".pkg.Other"),
+            ],
+            ..Default::default()
+        };
+        let mut owner = message_with_field("Owner", ".pkg.Normal");
+        owner.nested_type.push(DescriptorProto {
+            name: Some("ValuesEntry".to_owned()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("key".to_owned()),
+                    number: Some(1),
+                    type_name: Some(".pkg.Other".to_owned()),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("value".to_owned()),
+                    number: Some(2),
+                    type_name: Some(".pkg.MapOnly".to_owned()),
+                    ..Default::default()
+                },
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let users = FileDescriptorProto {
+            name: Some("users".to_owned()),
+            package: Some("pkg".to_owned()),
+            message_type: vec![owner],
+            ..Default::default()
+        };
+        let files = vec![values, users];
+        let config = RendererConfig::default();
+        let map_values = MapValueTypes::from_files(&files, config.map_detection);
+        let context = FileContext::with_map_value_types(&files[0], &config, &map_values)?;
+        assert!(context.messages[0].used_as_map_value());
+        assert!(!context.messages[1].used_as_map_value());
+        Ok(())
+    }
+
+    fn message_with_field(name: &str, type_name: &str) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_owned()),
+            field: vec![FieldDescriptorProto {
+                name: Some("field".to_owned()),
+                type_name: Some(type_name.to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    mod comment_directives {
+        use anyhow::Result;
+        use prost_types::source_code_info::Location;
+        use prost_types::{
+            DescriptorProto, FieldDescriptorProto, FileDescriptorProto, OneofDescriptorProto,
+            SourceCodeInfo,
+        };
+
+        use crate::renderer::context::file::tests::message_with_field;
+        use crate::renderer::context::FileContext;
+        use crate::renderer::RendererConfig;
+
+        #[test]
+        fn skipped_field_is_removed() -> Result<()> {
+            let mut message = message_with_field("Message", ".Other");
+            message.field.push(FieldDescriptorProto {
+                name: Some("kept".to_owned()),
+                type_name: Some(".Other".to_owned()),
+                ..Default::default()
+            });
+            let file = file_with_comments(vec![message], &[(&[4, 0, 2, 0], " protox:skip\n")]);
+            let context = FileContext::new(&file, &RendererConfig::default())?;
+            let fields = context.messages[0].fields();
+            assert_eq!(fields.len(), 1);
+            assert_eq!(fields[0].name(), "kept");
+            assert_eq!(context.messages[0].regular_fields().len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn skipped_optional_field_keeps_synthetic_oneof() -> Result<()> {
+            let mut message = message_with_field("Message", ".Other");
+            message.field[0].oneof_index = Some(0);
+            message.field[0].proto3_optional = Some(true);
+            message.oneof_decl.push(OneofDescriptorProto {
+                name: Some("_field".to_owned()),
+                options: None,
+            });
+            let file = file_with_comments(vec![message], &[(&[4, 0, 2, 0], " protox:skip\n")]);
+            let context = FileContext::new(&file, &RendererConfig::default())?;
+            let message = &context.messages[0];
+            assert!(message.oneofs().is_empty());
+            assert!(!message.has_oneof());
+            assert!(message.all_oneofs()[0].is_synthetic());
+            Ok(())
+        }
+
+        #[test]
+        fn skipped_message_is_removed() -> Result<()> {
+            let file = file_with_comments(
+                vec![
+                    message_with_field("Skipped", ".Other"),
+                    message_with_field("Kept", ".Other"),
+                ],
+                &[(&[4, 0], " protox:skip\n")],
+            );
+            let context = FileContext::new(&file, &RendererConfig::default())?;
+            assert_eq!(context.messages.len(), 1);
+            assert_eq!(context.messages[0].name(), "Kept");
+            Ok(())
+        }
+
+        #[test]
+        fn renamed_message_and_field() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.field_name_override = vec![("field".to_owned(), "overridden".to_owned())]
+                .into_iter()
+                .collect();
+            let file = file_with_comments(
+                vec![message_with_field("Message", ".Other")],
+                &[
+                    (&[4, 0], " Description.\n protox:rename=new_message\n"),
+                    (&[4, 0, 2, 0], " protox:rename=NewField\n"),
+                ],
+            );
+            let context = FileContext::new(&file, &config)?;
+            assert_eq!(context.messages[0].name(), "new_message");
+            assert_eq!(context.messages[0].fields()[0].name(), "NewField");
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_directive_errors() {
+            let file = file_with_comments(
+                vec![message_with_field("Message", ".Other")],
+                &[(&[4, 0], " protox:skp\n")],
+            );
+            assert!(FileContext::new(&file, &RendererConfig::default()).is_err());
+        }
+
+        fn file_with_comments(
+            messages: Vec<DescriptorProto>,
+            comments: &[(&[i32], &str)],
+        ) -> FileDescriptorProto {
+            FileDescriptorProto {
+                name: Some("file_name".to_owned()),
+                message_type: messages,
+                source_code_info: Some(SourceCodeInfo {
+                    location: comments
+                        .iter()
+                        .map(|(path, comment)| Location {
+                            path: path.to_vec(),
+                            leading_comments: Some(comment.to_string()),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }),
+                ..Default::default()
+            }
+        }
+    }
+
     #[test]
     fn missing_name_errors() {
         let config = RendererConfig::default();
@@ -19,6 +19,26 @@ pub struct ImportContext {
     /// Name of the file including extension.
     /// e.g. file_name.ext
     file_name_with_ext: String,
+
+    /// This is an `import public`, re-exported to files which import the importing file.
+    is_public: bool,
+
+    /// This is an `import weak`, which may be missing at runtime.
+    is_weak: bool,
+
+    /// The renderer's configured `file_extension`, e.g. `rs`.
+    file_extension: String,
+
+    /// Path of the file rendered from the import, relative to the output directory, e.g. with the
+    /// renderer's `file_extension` and file name case. Imports which aren't rendered in the same
+    /// run, e.g. ignored files, use `file_path` with the `file_extension` instead of `.proto`.
+    /// e.g. path/to/file_name.rs
+    output_file_path: String,
+
+    /// `output_file_path` under the renderer's `output_include_base`, i.e. the path to include the
+    /// file rendered from the import with. Same as `output_file_path` without a base.
+    /// e.g. base/path/to/file_name.rs
+    include_path: String,
 }
 
 impl ImportContext {
@@ -29,6 +49,11 @@ impl ImportContext {
             file_path: relative_path.display_normalized(),
             file_name: util::file_name_or_error(&relative_path.with_extension(""))?,
             file_name_with_ext: util::file_name_or_error(&relative_path)?,
+            is_public: false,
+            is_weak: false,
+            file_extension: String::new(),
+            output_file_path: relative_path.with_extension("").display_normalized(),
+            include_path: relative_path.with_extension("").display_normalized(),
         };
         Ok(context)
     }
@@ -42,6 +67,49 @@ impl ImportContext {
     pub fn file_name_with_ext(&self) -> &str {
         &self.file_name_with_ext
     }
+    pub fn is_public(&self) -> bool {
+        self.is_public
+    }
+    pub fn is_weak(&self) -> bool {
+        self.is_weak
+    }
+    pub fn file_extension(&self) -> &str {
+        &self.file_extension
+    }
+    pub fn output_file_path(&self) -> &str {
+        &self.output_file_path
+    }
+    pub fn include_path(&self) -> &str {
+        &self.include_path
+    }
+    pub(crate) fn set_is_public(&mut self, is_public: bool) {
+        self.is_public = is_public;
+    }
+    pub(crate) fn set_is_weak(&mut self, is_weak: bool) {
+        self.is_weak = is_weak;
+    }
+    pub(crate) fn set_file_extension(&mut self, file_extension: &str) {
+        self.file_extension = file_extension.to_owned();
+        self.output_file_path = PathBuf::from(&self.file_path)
+            .with_extension(file_extension)
+            .display_normalized();
+        self.include_path = self.output_file_path.clone();
+    }
+    /// The path the import is rendered to, see `output_file_path`. Must be called after
+    /// `set_file_extension`, and updates `include_path` for `base`.
+    pub(crate) fn set_output_file_path(&mut self, path: &str, base: Option<&str>) {
+        self.output_file_path = path.to_owned();
+        self.set_include_base(base);
+    }
+    /// Must be called after `set_file_extension`.
+    pub(crate) fn set_include_base(&mut self, base: Option<&str>) {
+        self.include_path = match base {
+            None => self.output_file_path.clone(),
+            Some(base) => PathBuf::from(base)
+                .join(&self.output_file_path)
+                .display_normalized(),
+        };
+    }
 }
 
 #[cfg(test)]
@@ -73,4 +141,34 @@ mod tests {
         assert_eq!(context.file_name_with_ext, "file_name.txt");
         Ok(())
     }
+
+    #[test]
+    fn output_file_path_uses_file_extension() -> Result<()> {
+        let mut context = ImportContext::new("root/sub/file_name.proto")?;
+        context.set_file_extension("rs");
+        assert_eq!(context.file_extension(), "rs");
+        assert_eq!(context.output_file_path(), "root/sub/file_name.rs");
+        assert_eq!(context.file_path(), "root/sub/file_name.proto");
+        Ok(())
+    }
+
+    #[test]
+    fn include_path_under_base() -> Result<()> {
+        let mut context = ImportContext::new("foo.proto")?;
+        context.set_file_extension("ext");
+        assert_eq!(context.include_path(), "foo.ext");
+        context.set_include_base(Some("base"));
+        assert_eq!(context.include_path(), "base/foo.ext");
+        Ok(())
+    }
+
+    #[test]
+    fn output_file_path_as_rendered() -> Result<()> {
+        let mut context = ImportContext::new("sub/SomeFile.proto")?;
+        context.set_file_extension("txt");
+        context.set_output_file_path("sub/some-file.txt", Some("base"));
+        assert_eq!(context.output_file_path(), "sub/some-file.txt");
+        assert_eq!(context.include_path(), "base/sub/some-file.txt");
+        Ok(())
+    }
 }
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+use prost_types::{DescriptorProto, FileDescriptorProto};
+
+use crate::renderer::MapDetection;
+
+const MAP_VALUE_NUMBER: i32 = 2;
+
+/// Types used as the value of a map field anywhere in a set of files, keyed by fully-qualified
+/// proto name with a leading '.', e.g. `.pkg.Message`.
+///
+/// Unlike `TypeGraph`, a message can be used as a map value in any file which imports it, so this
+/// is built from the whole descriptor set when rendering.
+#[derive(Default)]
+pub struct MapValueTypes {
+    types: HashSet<String>,
+}
+
+impl MapValueTypes {
+    pub fn from_files<'a>(
+        files: impl IntoIterator<Item = &'a FileDescriptorProto>,
+        map_detection: MapDetection,
+    ) -> Self {
+        let mut map_values = Self::default();
+        for file in files {
+            for message in &file.message_type {
+                map_values.insert_message(message, map_detection);
+            }
+        }
+        map_values
+    }
+
+    pub fn contains(&self, type_name: &str) -> bool {
+        self.types.contains(type_name)
+    }
+
+    fn insert_message(&mut self, message: &DescriptorProto, map_detection: MapDetection) {
+        for nested in &message.nested_type {
+            if map_detection.is_map_entry(Some(message), nested) {
+                let value_type = nested
+                    .field
+                    .iter()
+                    .find(|field| field.number == Some(MAP_VALUE_NUMBER))
+                    .and_then(|field| field.type_name.clone());
+                self.types.extend(value_type);
+            } else {
+                self.insert_message(nested, map_detection);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto, MessageOptions};
+
+    use crate::renderer::context::map_values::MapValueTypes;
+    use crate::renderer::MapDetection;
+
+    #[test]
+    fn collects_map_value_types_across_files() {
+        let mut outer = DescriptorProto {
+            name: Some("Outer".to_owned()),
+            ..Default::default()
+        };
+        outer
+            .nested_type
+            .push(map_entry("ValuesEntry", ".other.Value"));
+        let mut inner = DescriptorProto {
+            name: Some("Inner".to_owned()),
+            ..Default::default()
+        };
+        inner
+            .nested_type
+            .push(map_entry("DeepEntry", ".other.Deep"));
+        outer.nested_type.push(inner);
+        let files = vec![
+            FileDescriptorProto {
+                message_type: vec![outer],
+                ..Default::default()
+            },
+            FileDescriptorProto::default(),
+        ];
+        let map_values = MapValueTypes::from_files(&files, MapDetection::Option);
+        assert!(map_values.contains(".other.Value"));
+        assert!(map_values.contains(".other.Deep"));
+        assert!(!map_values.contains(".Outer"));
+    }
+
+    fn map_entry(name: &str, value_type: &str) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_owned()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("key".to_owned()),
+                    number: Some(1),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("value".to_owned()),
+                    number: Some(2),
+                    type_name: Some(value_type.to_owned()),
+                    ..Default::default()
+                },
+            ],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+}
@@ -2,13 +2,15 @@ use std::collections::HashMap;
 
 use anyhow::{anyhow, Context, Result};
 use log::debug;
+use prost_types::field_descriptor_proto::{Label, Type};
 use prost_types::{DescriptorProto, FieldDescriptorProto, MessageOptions};
 use serde::{Deserialize, Serialize, Serializer};
 
-use crate::renderer::case::Case;
+use crate::renderer::context::directive::{self, FileDirectives};
 use crate::renderer::context::overlayed::Overlayed;
 use crate::renderer::context::proto_type::ProtoType;
-use crate::renderer::context::FieldContext;
+use crate::renderer::context::type_graph::{self, TypeGraph};
+use crate::renderer::context::{EnumContext, FieldContext, MapValueTypes, OneofContext};
 use crate::renderer::proto::PACKAGE_SEPARATOR;
 use crate::renderer::RendererConfig;
 use crate::util;
@@ -21,6 +23,54 @@ pub struct MessageContext {
     /// Fields available in this message.
     fields: Vec<FieldContext>,
 
+    /// Fields in this message which are not part of a oneof, in declaration order. Proto3
+    /// `optional` fields are included since their oneof is only synthetic.
+    regular_fields: Vec<FieldContext>,
+
+    /// Oneofs declared in this message, excluding the synthetic oneofs protoc generates for
+    /// proto3 `optional` fields.
+    oneofs: Vec<OneofContext>,
+
+    /// Every oneof in this message including synthetic ones, in the same order as the
+    /// descriptor, so that a field's oneof index refers to its position here.
+    all_oneofs: Vec<OneofContext>,
+
+    /// Enums declared inside this message.
+    nested_enums: Vec<EnumContext>,
+
+    /// Messages declared inside this message, excluding map entries. Includes the messages protoc
+    /// generates for proto2 `group` fields, named after the group.
+    nested_messages: Vec<MessageContext>,
+
+    /// This message is the value type of a map field somewhere in the descriptor set.
+    used_as_map_value: bool,
+
+    /// This message is the synthetic entry type protoc generates for a map field.
+    /// Map entries are not included in the messages of a file.
+    is_map_entry: bool,
+
+    /// One of the fields is a map, e.g. for deciding which collections to import.
+    has_map_field: bool,
+
+    /// One of the fields is repeated. Map fields don't count, see `has_map_field`.
+    has_repeated_field: bool,
+
+    /// The message declares a oneof, not counting the synthetic oneofs of proto3 `optional`
+    /// fields.
+    has_oneof: bool,
+
+    /// Deterministic id of this message, for formats which identify messages by number. This is
+    /// the 32-bit FNV-1a hash of the UTF-8 bytes of the fully-qualified proto name without a
+    /// leading '.', e.g. `pkg.Outer.Inner`, so other tools can compute the same id.
+    stable_id: u32,
+
+    /// Minimum number of bytes this message serializes to, for pre-allocating buffers. Only
+    /// proto2 `required` fields are always serialized, so each contributes its tag and smallest
+    /// value, i.e. the fixed size for fixed-width types, and one byte for varints and length
+    /// prefixes. Nested required messages only count their length prefix. Repeated, optional and
+    /// proto3 fields contribute 0.
+    min_serialized_size: usize,
+
     /// Proto message options are serialized as an object like so:
     /// ```json
     /// {
@@ -58,15 +108,89 @@ impl MessageContext {
         message: &DescriptorProto,
         package: Option<&String>,
         config: &RendererConfig,
+    ) -> Result<Self> {
+        let type_graph = TypeGraph::from_messages(package, std::slice::from_ref(message));
+        Self::with_type_graph(
+            message,
+            package,
+            package.map(String::as_str),
+            false,
+            config,
+            &type_graph,
+            &MapValueTypes::default(),
+            &FileDirectives::default(),
+            &[],
+        )
+    }
+
+    /// Like `new`, but with a graph of all message types in the file so that fields which cycle
+    /// through other messages are detected as recursive, the map value types of the descriptor
+    /// set, and with the comment directives of the file, where `path` is the source code info path
+    /// of this message. `scope` is the fully-qualified name of the package or message this
+    /// message is declared in, and `proto3` is true if the file uses proto3 syntax.
+    pub(crate) fn with_type_graph(
+        message: &DescriptorProto,
+        package: Option<&String>,
+        scope: Option<&str>,
+        proto3: bool,
+        config: &RendererConfig,
+        type_graph: &TypeGraph,
+        map_values: &MapValueTypes,
+        directives: &FileDirectives,
+        path: &[i32],
     ) -> Result<Self> {
         log_new_message(&message.name);
+        let qualified_name = qualified_name(scope, message)?;
+        let type_name = type_graph::message_type_name(scope, &message.name);
+        let fields = fields(
+            message,
+            package,
+            &qualified_name,
+            &type_name,
+            proto3,
+            config,
+            type_graph,
+            directives,
+            path,
+        )?;
+        let all_oneofs = oneofs(message, &fields)?;
+        let min_serialized_size = fields.iter().map(|(field, _)| min_field_size(field)).sum();
+        let oneofs = all_oneofs
+            .iter()
+            .filter(|oneof| !oneof.is_synthetic())
+            .cloned()
+            .collect::<Vec<_>>();
         let context = Self {
-            name: name(message, config.case_config.message_name)?,
-            fields: fields(message, package, config)?,
+            name: name(message, config, directives.rename(path))?,
+            regular_fields: regular_fields(&fields),
+            has_map_field: fields.iter().any(|(_, field)| field.is_map()),
+            has_repeated_field: fields.iter().any(|(_, field)| field.is_array()),
+            has_oneof: !oneofs.is_empty(),
+            oneofs,
+            all_oneofs,
+            fields: fields.into_iter().map(|(_, context)| context).collect(),
+            nested_enums: nested_enums(message, &qualified_name, config)?,
+            nested_messages: nested_messages(
+                message,
+                package,
+                &qualified_name,
+                proto3,
+                config,
+                type_graph,
+                map_values,
+                directives,
+                path,
+            )?,
+            used_as_map_value: map_values.contains(&type_name),
+            // Nested entries are left out of `nested_messages` by their owner, so only entries
+            // without one get here.
+            is_map_entry: config.map_detection.is_map_entry(None, message),
+            stable_id: stable_id(&qualified_name),
+            min_serialized_size,
             options: message.options.clone(),
             overlays: config
                 .overlays
-                .by_target_opt_clone(&full_name(package, &message.name)),
+                .by_target_opt_clone(&overlay_target(package, &qualified_name)),
         };
         Ok(context)
     }
@@ -77,6 +201,46 @@ impl MessageContext {
     pub fn fields(&self) -> &Vec<FieldContext> {
         &self.fields
     }
+    /// The first declared field, e.g. a primary identifier, or None if there are no fields.
+    pub fn first_field(&self) -> Option<&FieldContext> {
+        self.fields.first()
+    }
+    pub fn regular_fields(&self) -> &Vec<FieldContext> {
+        &self.regular_fields
+    }
+    pub fn oneofs(&self) -> &Vec<OneofContext> {
+        &self.oneofs
+    }
+    pub fn all_oneofs(&self) -> &Vec<OneofContext> {
+        &self.all_oneofs
+    }
+    pub fn nested_enums(&self) -> &Vec<EnumContext> {
+        &self.nested_enums
+    }
+    pub fn nested_messages(&self) -> &Vec<MessageContext> {
+        &self.nested_messages
+    }
+    pub fn used_as_map_value(&self) -> bool {
+        self.used_as_map_value
+    }
+    pub fn is_map_entry(&self) -> bool {
+        self.is_map_entry
+    }
+    pub fn has_map_field(&self) -> bool {
+        self.has_map_field
+    }
+    pub fn has_repeated_field(&self) -> bool {
+        self.has_repeated_field
+    }
+    pub fn has_oneof(&self) -> bool {
+        self.has_oneof
+    }
+    pub fn stable_id(&self) -> u32 {
+        self.stable_id
+    }
+    pub fn min_serialized_size(&self) -> usize {
+        self.min_serialized_size
+    }
     pub fn options(&self) -> &Option<MessageOptions> {
         &self.options
     }
@@ -92,72 +256,264 @@ pub type MapData = HashMap<String, MapEntryData>;
 pub struct MapEntryData {
     pub key: ProtoType,
     pub value: ProtoType,
+    pub key_number: i32,
+    pub value_number: i32,
 }
 
 fn log_new_message(name: &Option<String>) {
     debug!("Creating message context: {}", util::str_or_unknown(name));
 }
 
-fn full_name(package: Option<&String>, name: &Option<String>) -> Option<String> {
-    Some(format!("{}.{}", package?, name.as_ref()?))
+/// Overlays target messages by their qualified name, and only in files with a package.
+fn overlay_target(package: Option<&String>, qualified_name: &str) -> Option<String> {
+    package.map(|_| qualified_name.to_owned())
 }
 
-fn name(message: &DescriptorProto, case: Case) -> Result<String> {
+/// Fully-qualified proto name of `message` without a leading '.', e.g. `pkg.Outer.Inner`.
+fn qualified_name(scope: Option<&str>, message: &DescriptorProto) -> Result<String> {
     let name = util::str_or_error(&message.name, || "Message has no 'name'".to_owned())?;
-    Ok(case.rename(name))
+    Ok(match scope {
+        Some(scope) if !scope.is_empty() => format!("{}.{}", scope, name),
+        _ => name.to_owned(),
+    })
+}
+
+/// 32-bit FNV-1a hash of `qualified_name`.
+fn stable_id(qualified_name: &str) -> u32 {
+    const OFFSET_BASIS: u32 = 0x811c9dc5;
+    const PRIME: u32 = 0x01000193;
+    qualified_name.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(PRIME)
+    })
 }
 
-fn fields(
+/// Minimum serialized size of `field` within its message, see `min_serialized_size`.
+fn min_field_size(field: &FieldDescriptorProto) -> usize {
+    if field.label != Some(Label::Required as i32) {
+        return 0;
+    }
+    let field_type = match field.r#type.and_then(Type::from_i32) {
+        None => return 0,
+        Some(field_type) => field_type,
+    };
+    // The wire type is in the low 3 bits, so it doesn't change the size of the tag.
+    let tag_size = varint_size((field.number.unwrap_or_default() as u64) << 3);
+    match field_type {
+        Type::Fixed32 | Type::Sfixed32 | Type::Float => tag_size + 4,
+        Type::Fixed64 | Type::Sfixed64 | Type::Double => tag_size + 8,
+        // Start and end group tags.
+        Type::Group => tag_size * 2,
+        _ => tag_size + 1,
+    }
+}
+
+fn varint_size(value: u64) -> usize {
+    (64 - (value | 1).leading_zeros() as usize + 6) / 7
+}
+
+fn name(
     message: &DescriptorProto,
+    config: &RendererConfig,
+    rename: Option<&str>,
+) -> Result<String> {
+    let name = util::str_or_error(&message.name, || "Message has no 'name'".to_owned())?;
+    let name = match rename {
+        None => config.case_config.message_name.rename(name),
+        Some(rename) => rename.to_owned(),
+    };
+    Ok(config.escape_keyword(name))
+}
+
+/// Pairs of (proto, context) for each field not skipped by a comment directive. `qualified_name`
+/// and `type_name` are the names of `message` without and with the leading '.'.
+fn fields<'a>(
+    message: &'a DescriptorProto,
     package: Option<&String>,
+    qualified_name: &str,
+    type_name: &str,
+    proto3: bool,
     config: &RendererConfig,
-) -> Result<Vec<FieldContext>> {
-    let map_data = collect_map_data(message, package)?;
+    type_graph: &TypeGraph,
+    directives: &FileDirectives,
+    path: &[i32],
+) -> Result<Vec<(&'a FieldDescriptorProto, FieldContext)>> {
+    let map_data = collect_map_data(message, type_name, config)?;
     let mut fields = Vec::new();
-    for field in &message.field {
-        fields.push(FieldContext::new(
+    for (i, field) in message.field.iter().enumerate() {
+        let field_path = directive::child_path(path, directive::MESSAGE_FIELD, i);
+        if directives.is_skipped(&field_path) {
+            continue;
+        }
+        let mut context = FieldContext::new(
             field,
             package,
             message.name.as_ref(),
             &map_data,
+            proto3,
             config,
-        )?);
+        )?;
+        context.set_is_recursive(is_recursive(field, type_name, type_graph));
+        context.set_oneof_name(oneof_name(message, field));
+        if let Some(rename) = directives.rename(&field_path) {
+            context.set_name(config.escape_keyword(rename.to_owned()));
+        }
+        if let Some(target) = overlay_target(package, qualified_name) {
+            context.set_overlays(field_overlays(&target, field, config));
+        }
+        fields.push((field, context));
     }
     Ok(fields)
 }
 
-fn collect_map_data(message: &DescriptorProto, package: Option<&String>) -> Result<MapData> {
+/// Overlays of `field`, targeted by the qualified name of the message it is declared in.
+fn field_overlays(
+    message_target: &str,
+    field: &FieldDescriptorProto,
+    config: &RendererConfig,
+) -> HashMap<String, serde_yaml::Value> {
+    let target = field
+        .name
+        .as_ref()
+        .map(|name| format!("{}.{}", message_target, name));
+    config.overlays.by_target_opt_clone(&target)
+}
+
+fn regular_fields(fields: &[(&FieldDescriptorProto, FieldContext)]) -> Vec<FieldContext> {
+    fields
+        .iter()
+        .filter(|(field, _)| !is_real_oneof_member(field))
+        .map(|(_, context)| context.clone())
+        .collect()
+}
+
+fn oneofs(
+    message: &DescriptorProto,
+    fields: &[(&FieldDescriptorProto, FieldContext)],
+) -> Result<Vec<OneofContext>> {
+    let mut oneofs = Vec::new();
+    for (i, oneof) in message.oneof_decl.iter().enumerate() {
+        oneofs.push(OneofContext::new(oneof, i, &message.field, fields)?);
+    }
+    Ok(oneofs)
+}
+
+fn is_real_oneof_member(field: &FieldDescriptorProto) -> bool {
+    field.oneof_index.is_some() && !field.proto3_optional.unwrap_or(false)
+}
+
+/// Name of the declared oneof `field` is a member of, if it isn't synthetic.
+fn oneof_name(message: &DescriptorProto, field: &FieldDescriptorProto) -> Option<String> {
+    if !is_real_oneof_member(field) {
+        return None;
+    }
+    let index = field.oneof_index? as usize;
+    message.oneof_decl.get(index)?.name.clone()
+}
+
+fn is_recursive(field: &FieldDescriptorProto, message_type_name: &str, graph: &TypeGraph) -> bool {
+    match &field.type_name {
+        None => false,
+        Some(type_name) => graph.reaches(type_name, message_type_name),
+    }
+}
+
+/// Nested enums are scoped by the message they are declared in, `qualified_name`.
+fn nested_enums(
+    message: &DescriptorProto,
+    qualified_name: &str,
+    config: &RendererConfig,
+) -> Result<Vec<EnumContext>> {
+    let scope = qualified_name.to_owned();
+    let mut enums = Vec::new();
+    for proto in &message.enum_type {
+        enums.push(EnumContext::new(proto, Some(&scope), config)?);
+    }
+    Ok(enums)
+}
+
+fn nested_messages(
+    message: &DescriptorProto,
+    package: Option<&String>,
+    scope: &str,
+    proto3: bool,
+    config: &RendererConfig,
+    type_graph: &TypeGraph,
+    map_values: &MapValueTypes,
+    directives: &FileDirectives,
+    path: &[i32],
+) -> Result<Vec<MessageContext>> {
+    let mut messages = Vec::new();
+    for (i, nested) in message.nested_type.iter().enumerate() {
+        let nested_path = directive::child_path(path, directive::MESSAGE_NESTED_TYPE, i);
+        if config.map_detection.is_map_entry(Some(message), nested)
+            || directives.is_skipped(&nested_path)
+        {
+            continue;
+        }
+        messages.push(MessageContext::with_type_graph(
+            nested,
+            package,
+            Some(scope),
+            proto3,
+            config,
+            type_graph,
+            map_values,
+            directives,
+            &nested_path,
+        )?);
+    }
+    Ok(messages)
+}
+
+/// Map entries of `message`, keyed by their type name in the fields which use them. Entries are
+/// declared in `message`, so their type names start with its `type_name`.
+fn collect_map_data(
+    message: &DescriptorProto,
+    type_name: &str,
+    config: &RendererConfig,
+) -> Result<MapData> {
     let message_name = util::str_or_error(&message.name, || {
         "collect_map_data: No message name.".to_owned()
     })?;
     let mut map_data = MapData::new();
-    for nested in message.nested_type.iter().filter(is_map) {
-        let (key, value) = find_map_key_value(nested, message_name)?;
+    for nested in message
+        .nested_type
+        .iter()
+        .filter(|nested| config.map_detection.is_map_entry(Some(message), nested))
+    {
+        let entry_data = map_entry_data(nested, message_name)?;
         let fully_qualified_nested_type =
-            fully_qualify_map_type(&nested_name(&nested, message_name)?, message_name, package);
-        map_data.insert(fully_qualified_nested_type, MapEntryData { key, value });
+            fully_qualify_map_type(&nested_name(&nested, message_name)?, type_name);
+        map_data.insert(fully_qualified_nested_type, entry_data);
     }
     Ok(map_data)
 }
 
-fn find_map_key_value(
-    nested: &DescriptorProto,
-    outer_msg_name: &str,
-) -> Result<(ProtoType, ProtoType)> {
+fn map_entry_data(nested: &DescriptorProto, outer_msg_name: &str) -> Result<MapEntryData> {
     static KEY_FIELD_NAME: &str = "key";
     static VALUE_FIELD_NAME: &str = "value";
-    let key = find_field_type(KEY_FIELD_NAME, &nested.field)
-        .with_context(|| error_context_failed_collect_map_data(outer_msg_name, &nested.name))?;
-    let value = find_field_type(VALUE_FIELD_NAME, &nested.field)
-        .with_context(|| error_context_failed_collect_map_data(outer_msg_name, &nested.name))?;
-    Ok((key, value))
+    // Fixed by the proto spec, but protoc always fills them in the entry descriptor anyway.
+    static KEY_FIELD_NUMBER: i32 = 1;
+    static VALUE_FIELD_NUMBER: i32 = 2;
+    let error_context = || error_context_failed_collect_map_data(outer_msg_name, &nested.name);
+    let key = find_field(KEY_FIELD_NAME, &nested.field).with_context(error_context)?;
+    let value = find_field(VALUE_FIELD_NAME, &nested.field).with_context(error_context)?;
+    Ok(MapEntryData {
+        key: ProtoType::from_field(key).with_context(error_context)?,
+        value: ProtoType::from_field(value).with_context(error_context)?,
+        key_number: key.number.unwrap_or(KEY_FIELD_NUMBER),
+        value_number: value.number.unwrap_or(VALUE_FIELD_NUMBER),
+    })
 }
 
-fn find_field_type(field_name: &str, fields: &[FieldDescriptorProto]) -> Result<ProtoType> {
+fn find_field<'a>(
+    field_name: &str,
+    fields: &'a [FieldDescriptorProto],
+) -> Result<&'a FieldDescriptorProto> {
     for field in fields {
         if let Some(name) = &field.name {
             if name == field_name {
-                return ProtoType::from_field(field);
+                return Ok(field);
             }
         }
     }
@@ -167,16 +523,6 @@ fn find_field_type(field_name: &str, fields: &[FieldDescriptorProto]) -> Result<
     ))
 }
 
-fn is_map(message: &&DescriptorProto) -> bool {
-    match &message.options {
-        None => false,
-        Some(options) => match options.map_entry {
-            None => false,
-            Some(is_map) => is_map,
-        },
-    }
-}
-
 fn nested_name(nested: &DescriptorProto, message_name: &str) -> Result<String> {
     nested.name.clone().ok_or(anyhow!(
         "Nested message has no name, outer message: {}",
@@ -184,15 +530,8 @@ fn nested_name(nested: &DescriptorProto, message_name: &str) -> Result<String> {
     ))
 }
 
-fn fully_qualify_map_type(entry_type: &str, outer_type: &str, package: Option<&String>) -> String {
-    let mut fully_qualified = String::new();
-    // All fully-qualified proto paths start with a separator.
-    fully_qualified.push(PACKAGE_SEPARATOR);
-    if let Some(package) = package {
-        fully_qualified.push_str(package);
-        fully_qualified.push(PACKAGE_SEPARATOR);
-    }
-    fully_qualified.push_str(outer_type);
+fn fully_qualify_map_type(entry_type: &str, outer_type_name: &str) -> String {
+    let mut fully_qualified = outer_type_name.to_owned();
     fully_qualified.push(PACKAGE_SEPARATOR);
     fully_qualified.push_str(entry_type);
     fully_qualified
@@ -226,11 +565,16 @@ fn serialize_message_options<S: Serializer>(
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
-    use prost_types::{DescriptorProto, FieldDescriptorProto};
+    use prost_types::field_descriptor_proto::{Label, Type};
+    use prost_types::{
+        DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, MessageOptions,
+        OneofDescriptorProto, OneofOptions,
+    };
     use std::collections::HashMap;
 
     use crate::renderer::case::Case;
     use crate::renderer::context::message::MessageContext;
+    use crate::renderer::context::overlayed::Overlayed;
     use crate::renderer::overlay_config::OverlayConfig;
     use crate::renderer::RendererConfig;
 
@@ -257,6 +601,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn name_with_reserved_keyword() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.reserved_keywords = vec!["Self".to_owned()];
+        config.keyword_escape = "Message".to_owned();
+        let mut message = DescriptorProto::default();
+        message.name = Some("self".to_owned());
+        let context = MessageContext::new(&message, None, &config)?;
+        assert_eq!(context.name, "SelfMessage");
+        Ok(())
+    }
+
     #[test]
     fn missing_name_errors() {
         let config = RendererConfig::default();
@@ -278,6 +634,540 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn regular_fields_exclude_oneof_members() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MessageName".to_owned());
+        proto.field.push(field("regular"));
+        proto.field.push(oneof_field("choice0", 0));
+        proto.field.push(oneof_field("choice1", 0));
+        let context = MessageContext::new(&proto, None, &config)?;
+        assert_eq!(context.fields.len(), 3);
+        assert_eq!(context.regular_fields.len(), 1);
+        assert_eq!(context.regular_fields[0].name(), "regular");
+        Ok(())
+    }
+
+    #[test]
+    fn regular_fields_include_proto3_optional() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MessageName".to_owned());
+        let mut optional = oneof_field("optional", 0);
+        optional.proto3_optional = Some(true);
+        proto.field.push(optional);
+        let context = MessageContext::new(&proto, None, &config)?;
+        assert_eq!(context.regular_fields.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn oneofs_exclude_synthetic() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MessageName".to_owned());
+        proto.field.push(oneof_field("choice0", 0));
+        proto.field.push(oneof_field("choice1", 0));
+        let mut optional = oneof_field("optional", 1);
+        optional.proto3_optional = Some(true);
+        proto.field.push(optional);
+        proto.oneof_decl.push(OneofDescriptorProto {
+            name: Some("choice".to_owned()),
+            options: Some(OneofOptions::default()),
+        });
+        proto.oneof_decl.push(OneofDescriptorProto {
+            name: Some("_optional".to_owned()),
+            options: None,
+        });
+        let context = MessageContext::new(&proto, None, &config)?;
+
+        assert_eq!(context.oneofs.len(), 1);
+        let real = &context.oneofs[0];
+        assert_eq!(real.name(), "choice");
+        assert!(!real.is_synthetic());
+        assert!(real.options().is_some());
+        let names = real.fields().iter().map(|f| f.name()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["choice0", "choice1"]);
+
+        assert_eq!(context.all_oneofs.len(), 2);
+        let synthetic = &context.all_oneofs[1];
+        assert_eq!(synthetic.name(), "_optional");
+        assert!(synthetic.is_synthetic());
+        assert!(synthetic.options().is_none());
+        assert_eq!(synthetic.fields()[0].name(), "optional");
+        Ok(())
+    }
+
+    #[test]
+    fn fields_know_their_oneof_name() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MessageName".to_owned());
+        proto.field.push(field("regular"));
+        proto.field.push(oneof_field("choice0", 0));
+        let mut optional = oneof_field("optional", 1);
+        optional.proto3_optional = Some(true);
+        proto.field.push(optional);
+        proto.oneof_decl.push(OneofDescriptorProto {
+            name: Some("choice".to_owned()),
+            options: None,
+        });
+        proto.oneof_decl.push(OneofDescriptorProto {
+            name: Some("_optional".to_owned()),
+            options: None,
+        });
+        let context = MessageContext::new(&proto, None, &config)?;
+
+        assert_eq!(context.fields[0].oneof_name(), None);
+        assert_eq!(context.fields[1].oneof_name(), Some(&"choice".to_owned()));
+        assert_eq!(context.fields[2].oneof_name(), None);
+        assert_eq!(
+            context.oneofs[0].fields()[0].oneof_name(),
+            Some(&"choice".to_owned())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn creates_nested_enums_from_proto() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MessageName".to_owned());
+        proto.enum_type.push(EnumDescriptorProto {
+            name: Some("InnerEnum".to_owned()),
+            ..Default::default()
+        });
+        let context = MessageContext::new(&proto, None, &config)?;
+        assert_eq!(
+            context.nested_enums.get(0).map(|e| e.name()),
+            Some("InnerEnum")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested_enum_overlay_is_scoped_by_message() -> Result<()> {
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MessageName".to_owned());
+        proto.enum_type.push(EnumDescriptorProto {
+            name: Some("InnerEnum".to_owned()),
+            ..Default::default()
+        });
+        let package = "some.package".to_owned();
+        let config = RendererConfig {
+            overlays: OverlayConfig::new(
+                HashMap::new(),
+                HashMap::from([(
+                    "some.package.MessageName.InnerEnum".to_owned(),
+                    HashMap::from([(
+                        "some_key".to_owned(),
+                        serde_yaml::Value::String("some_value".to_owned()),
+                    )]),
+                )]),
+            ),
+            ..Default::default()
+        };
+        let context = MessageContext::new(&proto, Some(&package), &config)?;
+        let nested = context.nested_enums.get(0).expect("missing nested enum");
+        assert_eq!(
+            &nested
+                .overlays()
+                .get("some_key")
+                .expect("key did not exist"),
+            &"some_value"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn direct_self_reference_is_recursive() -> Result<()> {
+        let package = "pkg".to_owned();
+        let proto = DescriptorProto {
+            name: Some("Node".to_owned()),
+            field: vec![
+                FieldDescriptorProto {
+                    name: Some("child".to_owned()),
+                    type_name: Some(".pkg.Node".to_owned()),
+                    ..Default::default()
+                },
+                FieldDescriptorProto {
+                    name: Some("value".to_owned()),
+                    r#type: Some(5),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let context = MessageContext::new(&proto, Some(&package), &RendererConfig::default())?;
+        assert!(context.fields[0].is_recursive());
+        assert!(!context.fields[1].is_recursive());
+        Ok(())
+    }
+
+    #[test]
+    fn group_fields_are_nested_messages() -> Result<()> {
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("Outer".to_owned());
+        proto.nested_type.push(DescriptorProto {
+            name: Some("Result".to_owned()),
+            field: vec![FieldDescriptorProto {
+                r#type: Some(Type::String as i32),
+                type_name: None,
+                ..field("url")
+            }],
+            ..Default::default()
+        });
+        proto.field.push(FieldDescriptorProto {
+            label: Some(Label::Repeated as i32),
+            r#type: Some(Type::Group as i32),
+            type_name: Some(".Outer.Result".to_owned()),
+            ..field("result")
+        });
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert_eq!(context.fields[0].type_kind(), "group");
+        assert!(context.fields[0].is_array());
+        let group = &context.nested_messages[0];
+        assert_eq!(group.name(), "Result");
+        assert_eq!(group.fields()[0].name(), "url");
+        Ok(())
+    }
+
+    #[test]
+    fn stable_id_is_deterministic() -> Result<()> {
+        let config = RendererConfig::default();
+        let package = "pkg".to_owned();
+        let message = DescriptorProto {
+            name: Some("Msg".to_owned()),
+            ..Default::default()
+        };
+        let context = MessageContext::new(&message, Some(&package), &config)?;
+        let again = MessageContext::new(&message, Some(&package), &config)?;
+        assert_eq!(context.stable_id(), again.stable_id());
+        // 32-bit FNV-1a of "pkg.Msg".
+        assert_eq!(context.stable_id(), 1458767530);
+
+        let other = MessageContext::new(&message, None, &config)?;
+        assert_ne!(context.stable_id(), other.stable_id());
+        Ok(())
+    }
+
+    #[test]
+    fn stable_id_of_nested_message_uses_full_name() -> Result<()> {
+        let package = "pkg".to_owned();
+        let message = DescriptorProto {
+            name: Some("Outer".to_owned()),
+            nested_type: vec![DescriptorProto {
+                name: Some("Inner".to_owned()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let context = MessageContext::new(&message, Some(&package), &RendererConfig::default())?;
+        // 32-bit FNV-1a of "pkg.Outer.Inner".
+        assert_eq!(context.nested_messages[0].stable_id(), 2810654022);
+        Ok(())
+    }
+
+    #[test]
+    fn min_serialized_size_of_required_fixed32_fields() -> Result<()> {
+        let fixed32 = |name: &str, number, label: Label| FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(Type::Fixed32 as i32),
+            ..Default::default()
+        };
+        let message = DescriptorProto {
+            name: Some("Msg".to_owned()),
+            field: vec![
+                fixed32("a", 1, Label::Required),
+                fixed32("b", 2, Label::Required),
+                fixed32("c", 3, Label::Optional),
+                fixed32("d", 4, Label::Repeated),
+            ],
+            ..Default::default()
+        };
+        let context = MessageContext::new(&message, None, &RendererConfig::default())?;
+        // Two 1 byte tags with 4 byte values.
+        assert_eq!(context.min_serialized_size(), 10);
+        Ok(())
+    }
+
+    #[test]
+    fn min_serialized_size_of_other_types() -> Result<()> {
+        let required = |name: &str, number, field_type: Type| FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(Label::Required as i32),
+            r#type: Some(field_type as i32),
+            ..Default::default()
+        };
+        let message = DescriptorProto {
+            name: Some("Msg".to_owned()),
+            field: vec![
+                required("varint", 1, Type::Int64),
+                required("string", 2, Type::String),
+                required("double", 16, Type::Double),
+            ],
+            ..Default::default()
+        };
+        let context = MessageContext::new(&message, None, &RendererConfig::default())?;
+        // Field 16 needs a 2 byte tag.
+        assert_eq!(context.min_serialized_size(), (1 + 1) + (1 + 1) + (2 + 8));
+        Ok(())
+    }
+
+    #[test]
+    fn is_map_entry() -> Result<()> {
+        let proto = DescriptorProto {
+            name: Some("SomeEntry".to_owned()),
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert!(context.is_map_entry);
+        Ok(())
+    }
+
+    #[test]
+    fn map_field_entry_numbers() -> Result<()> {
+        let entry_field = |name: &str, number| FieldDescriptorProto {
+            number: Some(number),
+            r#type: Some(Type::String as i32),
+            type_name: None,
+            ..field(name)
+        };
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MapOwner".to_owned());
+        proto.nested_type.push(DescriptorProto {
+            name: Some("ValuesEntry".to_owned()),
+            field: vec![entry_field("key", 1), entry_field("value", 2)],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        proto.field.push(FieldDescriptorProto {
+            type_name: Some(".MapOwner.ValuesEntry".to_owned()),
+            ..field("values")
+        });
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert!(context.fields[0].is_map());
+        assert_eq!(context.fields[0].map_key_number(), Some(1));
+        assert_eq!(context.fields[0].map_value_number(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn map_field_in_nested_message() -> Result<()> {
+        let package = "pkg".to_owned();
+        let entry_field = |name: &str, number| FieldDescriptorProto {
+            number: Some(number),
+            r#type: Some(Type::String as i32),
+            type_name: None,
+            ..field(name)
+        };
+        let inner = DescriptorProto {
+            name: Some("Inner".to_owned()),
+            field: vec![FieldDescriptorProto {
+                label: Some(Label::Repeated as i32),
+                type_name: Some(".pkg.Outer.Inner.ValuesEntry".to_owned()),
+                ..field("values")
+            }],
+            nested_type: vec![DescriptorProto {
+                name: Some("ValuesEntry".to_owned()),
+                field: vec![entry_field("key", 1), entry_field("value", 2)],
+                options: Some(MessageOptions {
+                    map_entry: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let outer = DescriptorProto {
+            name: Some("Outer".to_owned()),
+            nested_type: vec![inner],
+            ..Default::default()
+        };
+        let context = MessageContext::new(&outer, Some(&package), &RendererConfig::default())?;
+        let inner = &context.nested_messages()[0];
+        assert!(inner.fields()[0].is_map());
+        assert!(inner.has_map_field());
+        assert!(!inner.has_repeated_field());
+        assert!(inner.nested_messages().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn has_map_field() -> Result<()> {
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("MapOwner".to_owned());
+        let entry_field = |name: &str, number| FieldDescriptorProto {
+            number: Some(number),
+            r#type: Some(Type::String as i32),
+            type_name: None,
+            ..field(name)
+        };
+        proto.nested_type.push(DescriptorProto {
+            name: Some("ValuesEntry".to_owned()),
+            field: vec![entry_field("key", 1), entry_field("value", 2)],
+            options: Some(MessageOptions {
+                map_entry: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        proto.field.push(FieldDescriptorProto {
+            label: Some(Label::Repeated as i32),
+            type_name: Some(".MapOwner.ValuesEntry".to_owned()),
+            ..field("values")
+        });
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert!(context.has_map_field());
+        assert!(!context.has_repeated_field());
+        assert!(!context.has_oneof());
+        Ok(())
+    }
+
+    #[test]
+    fn has_repeated_field_and_oneof() -> Result<()> {
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("Msg".to_owned());
+        proto.field.push(FieldDescriptorProto {
+            label: Some(Label::Repeated as i32),
+            ..field("list")
+        });
+        proto.field.push(FieldDescriptorProto {
+            oneof_index: Some(0),
+            ..field("choice")
+        });
+        proto.oneof_decl.push(OneofDescriptorProto {
+            name: Some("real".to_owned()),
+            options: None,
+        });
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert!(!context.has_map_field());
+        assert!(context.has_repeated_field());
+        assert!(context.has_oneof());
+        Ok(())
+    }
+
+    mod map_detection {
+        use anyhow::Result;
+        use prost_types::field_descriptor_proto::{Label, Type};
+        use prost_types::{DescriptorProto, FieldDescriptorProto};
+
+        use crate::renderer::context::message::MessageContext;
+        use crate::renderer::{MapDetection, RendererConfig};
+
+        #[test]
+        fn option_ignores_conventional_entry() -> Result<()> {
+            let config = RendererConfig::default();
+            let context = MessageContext::new(&map_owner("ValuesEntry"), None, &config)?;
+            assert!(!context.fields()[0].is_map());
+            assert_eq!(context.nested_messages().len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn heuristic_detects_conventional_entry() -> Result<()> {
+            let context = MessageContext::new(&map_owner("ValuesEntry"), None, &heuristic())?;
+            assert!(context.fields()[0].is_map());
+            assert!(context.nested_messages().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn heuristic_requires_entry_name() -> Result<()> {
+            let context = MessageContext::new(&map_owner("Pair"), None, &heuristic())?;
+            assert!(!context.fields()[0].is_map());
+            Ok(())
+        }
+
+        #[test]
+        fn heuristic_requires_repeated_field() -> Result<()> {
+            let mut owner = map_owner("LogEntry");
+            owner.field[0].label = Some(Label::Optional as i32);
+            let context = MessageContext::new(&owner, None, &heuristic())?;
+            assert!(!context.fields()[0].is_map());
+            assert_eq!(context.nested_messages().len(), 1);
+            Ok(())
+        }
+
+        #[test]
+        fn heuristic_requires_field_numbers() -> Result<()> {
+            let mut owner = map_owner("ValuesEntry");
+            owner.nested_type[0].field[1].number = Some(3);
+            let context = MessageContext::new(&owner, None, &heuristic())?;
+            assert!(!context.fields()[0].is_map());
+            assert_eq!(context.nested_messages().len(), 1);
+            Ok(())
+        }
+
+        fn heuristic() -> RendererConfig {
+            let mut config = RendererConfig::default();
+            config.map_detection = MapDetection::Heuristic;
+            config
+        }
+
+        /// A message with a repeated field of the nested type `entry_name`, which has `key` and
+        /// `value` fields but not the `map_entry` option.
+        fn map_owner(entry_name: &str) -> DescriptorProto {
+            let entry_field = |name: &str, number| FieldDescriptorProto {
+                name: Some(name.to_owned()),
+                number: Some(number),
+                r#type: Some(Type::String as i32),
+                ..Default::default()
+            };
+            DescriptorProto {
+                name: Some("MapOwner".to_owned()),
+                field: vec![FieldDescriptorProto {
+                    name: Some("values".to_owned()),
+                    label: Some(Label::Repeated as i32),
+                    type_name: Some(format!(".MapOwner.{}", entry_name)),
+                    ..Default::default()
+                }],
+                nested_type: vec![DescriptorProto {
+                    name: Some(entry_name.to_owned()),
+                    field: vec![entry_field("key", 1), entry_field("value", 2)],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn first_field() -> Result<()> {
+        let mut proto = DescriptorProto::default();
+        proto.name = Some("Msg".to_owned());
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert!(context.first_field().is_none());
+
+        proto.field.push(field("id"));
+        proto.field.push(field("other"));
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert_eq!(context.first_field().map(|field| field.name()), Some("id"));
+        Ok(())
+    }
+
+    #[test]
+    fn is_not_map_entry() -> Result<()> {
+        let proto = DescriptorProto {
+            name: Some("MessageName".to_owned()),
+            ..Default::default()
+        };
+        let context = MessageContext::new(&proto, None, &RendererConfig::default())?;
+        assert!(!context.is_map_entry);
+        Ok(())
+    }
+
     #[test]
     fn overlay() -> Result<()> {
         let proto = DescriptorProto {
@@ -306,6 +1196,51 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn nested_message_overlay_uses_enclosing_message() -> Result<()> {
+        let proto = DescriptorProto {
+            name: Some("Outer".to_owned()),
+            nested_type: vec![DescriptorProto {
+                name: Some("Inner".to_owned()),
+                field: vec![field("inner_field")],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let package = "pkg".to_owned();
+        let overlay = |key: &str| {
+            HashMap::from([(
+                key.to_owned(),
+                serde_yaml::Value::String("value".to_owned()),
+            )])
+        };
+        let config = RendererConfig {
+            overlays: OverlayConfig::new(
+                HashMap::new(),
+                HashMap::from([
+                    ("pkg.Outer.Inner".to_owned(), overlay("message_key")),
+                    (
+                        "pkg.Outer.Inner.inner_field".to_owned(),
+                        overlay("field_key"),
+                    ),
+                ]),
+            ),
+            ..Default::default()
+        };
+        let context = MessageContext::new(&proto, Some(&package), &config)?;
+        let inner = &context.nested_messages()[0];
+        assert!(inner.overlays().contains_key("message_key"));
+        assert!(inner.fields()[0].overlays().contains_key("field_key"));
+        Ok(())
+    }
+
+    fn oneof_field(name: impl ToString, oneof_index: i32) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            oneof_index: Some(oneof_index),
+            ..field(name)
+        }
+    }
+
     fn field(name: impl ToString) -> FieldDescriptorProto {
         FieldDescriptorProto {
             name: Some(name.to_string()),
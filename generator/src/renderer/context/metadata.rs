@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::Result;
+use prost_types::FileDescriptorProto;
 use serde::{Deserialize, Serialize, Serializer};
 
 use crate::{util, DisplayNormalized};
@@ -20,9 +21,33 @@ pub struct MetadataContext {
     /// Names of files in this directory, with extensions.
     file_names_with_ext: Vec<String>,
 
-    /// Names of directories in this directory.
+    /// Files in this directory, in the same order as `file_names`, along with the description of
+    /// the proto file each was generated from.
+    ///
+    /// ```hbs
+    /// {{#each files}}
+    /// {{file_name}}: {{description}}
+    /// {{/each}}
+    /// ```
+    files: Vec<MetadataFile>,
+
+    /// Names of directories in this directory, sorted so output doesn't depend on the order they
+    /// were found in.
     subdirectories: Vec<String>,
 
+    /// Names of files in this directory, with extensions, that were generated by protoc rather
+    /// than protox. Only filled when a `--proto` output is linked with `--link-proto-output`.
+    native_files: Vec<String>,
+
+    /// Path of the metadata file being rendered, relative to the output directory, e.g.
+    /// `sub/metadata.rs`. Empty outside of rendering.
+    output_path: String,
+
+    /// Longest package prefix shared by every rendered file, by whole package components, e.g.
+    /// `a.b` for files in packages `a.b.c` and `a.b.d`. Empty if any file has no package, or the
+    /// packages have nothing in common.
+    common_package_prefix: String,
+
     /// When one_file_per_package is enabled, this list holds the package->file mapping.
     /// Each package is fully specified.
     ///
@@ -81,6 +106,23 @@ pub struct MetadataContext {
 pub struct PackageFile {
     package: String,
     file_name: String,
+
+    /// The description of the first file in the package that has one. See `MetadataFile`.
+    description: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MetadataFile {
+    /// Name of the file, without extension.
+    file_name: String,
+
+    /// Name of the file, with extension.
+    file_name_with_ext: String,
+
+    /// Leading comment of the proto file's `syntax` declaration, or its `package` declaration if
+    /// the syntax has none. Empty if neither is commented, or the descriptor set was built without
+    /// source info.
+    description: String,
 }
 
 /// A node in a package tree. Typically it will represent _either_ a single file _or_ a component
@@ -97,7 +139,11 @@ impl MetadataContext {
             directory: Default::default(),
             file_names: vec![],
             file_names_with_ext: vec![],
+            files: vec![],
             subdirectories: vec![],
+            native_files: vec![],
+            output_path: String::new(),
+            common_package_prefix: String::new(),
             package_files_full: vec![],
             package_file_tree: Default::default(),
         }
@@ -108,7 +154,11 @@ impl MetadataContext {
             directory: directory.to_path_buf(),
             file_names: vec![],
             file_names_with_ext: vec![],
+            files: vec![],
             subdirectories: vec![],
+            native_files: vec![],
+            output_path: String::new(),
+            common_package_prefix: String::new(),
             package_files_full: vec![],
             package_file_tree: Default::default(),
         };
@@ -124,9 +174,27 @@ impl MetadataContext {
     pub fn file_names_with_ext(&self) -> &[String] {
         &self.file_names_with_ext
     }
+    pub fn files(&self) -> &[MetadataFile] {
+        &self.files
+    }
     pub fn subdirectories(&self) -> &[String] {
         &self.subdirectories
     }
+    pub fn native_files(&self) -> &[String] {
+        &self.native_files
+    }
+    pub fn output_path(&self) -> &str {
+        &self.output_path
+    }
+    pub(crate) fn set_output_path(&mut self, output_path: String) {
+        self.output_path = output_path;
+    }
+    pub fn common_package_prefix(&self) -> &str {
+        &self.common_package_prefix
+    }
+    pub(crate) fn set_common_package_prefix(&mut self, common_package_prefix: String) {
+        self.common_package_prefix = common_package_prefix;
+    }
     pub fn package_files_full(&self) -> &[PackageFile] {
         &self.package_files_full
     }
@@ -139,10 +207,19 @@ impl MetadataContext {
     }
 
     pub fn push_file(&mut self, path: &Path) -> Result<()> {
+        self.push_described_file(path, "")
+    }
+
+    pub fn push_described_file(&mut self, path: &Path, description: &str) -> Result<()> {
         if self.is_direct_child(path) {
             let file_name_with_ext = util::file_name_or_error(path)?;
-            self.file_names_with_ext.push(file_name_with_ext);
             let file_name_no_ext = util::file_name_or_error(&path.with_extension(""))?;
+            self.files.push(MetadataFile {
+                file_name: file_name_no_ext.clone(),
+                file_name_with_ext: file_name_with_ext.clone(),
+                description: description.to_owned(),
+            });
+            self.file_names_with_ext.push(file_name_with_ext);
             self.file_names.push(file_name_no_ext);
         }
         Ok(())
@@ -154,7 +231,9 @@ impl MetadataContext {
         }
         if self.is_direct_child(path) {
             let dir_name = util::file_name_or_error(path)?;
-            self.subdirectories.push(dir_name);
+            if let Err(index) = self.subdirectories.binary_search(&dir_name) {
+                self.subdirectories.insert(index, dir_name);
+            }
         }
         Ok(())
     }
@@ -166,6 +245,27 @@ impl MetadataContext {
         Ok(())
     }
 
+    pub fn append_described_files(&mut self, files: &[(impl AsRef<Path>, String)]) -> Result<()> {
+        for (path, description) in files {
+            self.push_described_file(path.as_ref(), description)?;
+        }
+        Ok(())
+    }
+
+    pub fn push_native_file(&mut self, path: &Path) -> Result<()> {
+        if self.is_direct_child(path) {
+            self.native_files.push(util::file_name_or_error(path)?);
+        }
+        Ok(())
+    }
+
+    pub fn append_native_files(&mut self, paths: &[impl AsRef<Path>]) -> Result<()> {
+        for path in paths {
+            self.push_native_file(path.as_ref())?;
+        }
+        Ok(())
+    }
+
     pub fn append_subdirectories<I, T>(&mut self, paths: I) -> Result<()>
     where
         I: Iterator<Item = T>,
@@ -184,11 +284,22 @@ impl MetadataContext {
             .map(|(package, path)| PackageFile {
                 package: package.to_owned(),
                 file_name: path.as_ref().display_normalized(),
+                description: String::new(),
             })
             .collect::<Vec<PackageFile>>();
         self.package_files_full.sort()
     }
 
+    /// Sets the description of each entry in `package_files_full` from a map of package ->
+    /// description.
+    pub fn set_package_descriptions(&mut self, descriptions: &HashMap<String, String>) {
+        for package_file in &mut self.package_files_full {
+            if let Some(description) = descriptions.get(&package_file.package) {
+                package_file.description = description.clone();
+            }
+        }
+    }
+
     fn is_direct_child(&self, path: &Path) -> bool {
         match path.parent() {
             None => self.directory.as_os_str().is_empty(),
@@ -204,6 +315,71 @@ impl PackageFile {
     pub fn package(&self) -> &str {
         &self.package
     }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl MetadataFile {
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+    pub fn file_name_with_ext(&self) -> &str {
+        &self.file_name_with_ext
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// Longest prefix of whole `.` separated components shared by all `packages`. Empty if there are
+/// no packages.
+pub fn common_package_prefix<'a>(packages: impl IntoIterator<Item = &'a str>) -> String {
+    let mut packages = packages.into_iter();
+    let mut prefix = match packages.next() {
+        None => return String::new(),
+        Some(first) => first.split('.').collect::<Vec<&str>>(),
+    };
+    for package in packages {
+        let shared = prefix
+            .iter()
+            .zip(package.split('.'))
+            .take_while(|(a, b)| *a == b)
+            .count();
+        prefix.truncate(shared);
+    }
+    prefix.join(".")
+}
+
+/// Field numbers in `FileDescriptorProto`, used as source code info paths.
+const SYNTAX_FIELD_NUMBER: i32 = 12;
+const PACKAGE_FIELD_NUMBER: i32 = 2;
+
+/// Leading comment of the file's syntax declaration, falling back to its package declaration.
+/// Each line is trimmed. Empty if there is no such comment.
+pub fn file_description(file: &FileDescriptorProto) -> String {
+    let locations = match &file.source_code_info {
+        None => return String::new(),
+        Some(info) => &info.location,
+    };
+    [SYNTAX_FIELD_NUMBER, PACKAGE_FIELD_NUMBER]
+        .iter()
+        .filter_map(|number| {
+            locations
+                .iter()
+                .find(|location| location.path == [*number])
+                .and_then(|location| location.leading_comments.as_ref())
+        })
+        .map(|comment| {
+            comment
+                .trim()
+                .lines()
+                .map(str::trim)
+                .collect::<Vec<&str>>()
+                .join("\n")
+        })
+        .find(|comment| !comment.is_empty())
+        .unwrap_or_default()
 }
 
 impl PackageTreeNode {
@@ -245,6 +421,50 @@ fn create_package_file_tree(package_files: &HashMap<String, impl AsRef<Path>>) -
 
 #[cfg(test)]
 mod tests {
+    mod common_package_prefix {
+        use crate::renderer::context::common_package_prefix;
+
+        #[test]
+        fn shared_components() {
+            assert_eq!(common_package_prefix(vec!["a.b.c", "a.b.d"]), "a.b");
+        }
+
+        #[test]
+        fn whole_components_only() {
+            assert_eq!(common_package_prefix(vec!["a.bc", "a.bd"]), "a");
+        }
+
+        #[test]
+        fn single_package() {
+            assert_eq!(common_package_prefix(vec!["a.b.c"]), "a.b.c");
+        }
+
+        #[test]
+        fn nothing_shared() {
+            assert_eq!(common_package_prefix(vec!["a.b", "c.d"]), "");
+            assert_eq!(common_package_prefix(vec!["a.b", ""]), "");
+            assert_eq!(common_package_prefix(Vec::new()), "");
+        }
+    }
+
+    mod push_native_file {
+        use std::path::PathBuf;
+
+        use anyhow::Result;
+
+        use crate::renderer::context::MetadataContext;
+
+        #[test]
+        fn only_direct_children_with_ext() -> Result<()> {
+            let root = PathBuf::from("root");
+            let mut context = MetadataContext::with_relative_dir(&root)?;
+            context.append_native_files(&[root.join("Native.cs"), root.join("sub/Other.cs")])?;
+            assert_eq!(context.native_files, vec!["Native.cs".to_owned()]);
+            assert!(context.file_names.is_empty());
+            Ok(())
+        }
+    }
+
     mod push_file {
         use std::path::PathBuf;
 
@@ -284,6 +504,47 @@ mod tests {
         }
     }
 
+    mod file_description {
+        use prost_types::source_code_info::Location;
+        use prost_types::{FileDescriptorProto, SourceCodeInfo};
+
+        use crate::renderer::context::metadata::file_description;
+
+        #[test]
+        fn from_syntax_comment() {
+            let file =
+                file_with_comments(&[(12, " Syntax comment.\n"), (2, " Package comment.\n")]);
+            assert_eq!(file_description(&file), "Syntax comment.");
+        }
+
+        #[test]
+        fn falls_back_to_package_comment() {
+            let file = file_with_comments(&[(2, " First line.\n Second line.\n")]);
+            assert_eq!(file_description(&file), "First line.\nSecond line.");
+        }
+
+        #[test]
+        fn empty_without_source_info() {
+            assert_eq!(file_description(&FileDescriptorProto::default()), "");
+        }
+
+        fn file_with_comments(comments: &[(i32, &str)]) -> FileDescriptorProto {
+            FileDescriptorProto {
+                source_code_info: Some(SourceCodeInfo {
+                    location: comments
+                        .iter()
+                        .map(|(number, comment)| Location {
+                            path: vec![*number],
+                            leading_comments: Some(comment.to_string()),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }),
+                ..Default::default()
+            }
+        }
+    }
+
     mod push_subdirectory {
         use std::path::PathBuf;
 
@@ -308,6 +569,17 @@ mod tests {
             assert!(context.subdirectories.is_empty());
             Ok(())
         }
+
+        #[test]
+        fn sorted_and_deduplicated() -> Result<()> {
+            let root = PathBuf::from("root");
+            let mut context = MetadataContext::with_relative_dir(&root)?;
+            for dir in ["b", "c", "a", "b"] {
+                context.push_subdirectory(&root.join(dir))?;
+            }
+            assert_eq!(context.subdirectories, vec!["a", "b", "c"]);
+            Ok(())
+        }
     }
 
     mod direct_child {
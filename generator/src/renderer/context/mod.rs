@@ -1,17 +1,28 @@
+pub(crate) use extension_bytes::extension_bytes;
+pub(crate) use field::jstype_name;
 pub use field::FieldContext;
 pub use file::FileContext;
 pub use import::ImportContext;
+pub(crate) use map_values::MapValueTypes;
 pub use message::MessageContext;
-pub use metadata::{MetadataContext, PackageFile, PackageTree, PackageTreeNode};
+pub(crate) use metadata::{common_package_prefix, file_description};
+pub use metadata::{MetadataContext, MetadataFile, PackageFile, PackageTree, PackageTreeNode};
+pub use oneof::OneofContext;
+pub(crate) use r#enum::enum_value_key_values;
 pub use r#enum::EnumContext;
 pub use r#enum::EnumValueContext;
 
+mod directive;
 mod r#enum;
+mod extension_bytes;
 mod field;
 mod file;
 mod import;
+mod map_values;
 mod message;
 mod metadata;
+mod oneof;
 mod proto_type;
+mod type_graph;
 
 pub mod overlayed;
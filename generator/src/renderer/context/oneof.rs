@@ -0,0 +1,66 @@
+use anyhow::Result;
+use prost_types::{FieldDescriptorProto, OneofDescriptorProto, OneofOptions};
+use serde::{Deserialize, Serialize};
+
+use crate::renderer::context::FieldContext;
+use crate::util;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OneofContext {
+    /// Name of this oneof, as written in the proto file.
+    name: String,
+
+    /// Fields which are members of this oneof, in declaration order.
+    fields: Vec<FieldContext>,
+
+    /// This oneof was generated by protoc to hold a single proto3 `optional` field, rather than
+    /// declared in the proto file.
+    is_synthetic: bool,
+
+    /// There are no built-in oneof options, so they are left out of template contexts. Custom
+    /// options are available to scripts as `options.unknown_options`.
+    #[serde(skip)]
+    options: Option<OneofOptions>,
+}
+
+impl OneofContext {
+    /// `declared` are all fields declared in the message, and `fields` are pairs of (proto, context)
+    /// for the fields which are rendered; only members of the oneof at `index` are kept. Whether
+    /// the oneof is synthetic is decided by its declared members, so it doesn't change when one is
+    /// skipped.
+    pub fn new(
+        oneof: &OneofDescriptorProto,
+        index: usize,
+        declared: &[FieldDescriptorProto],
+        fields: &[(&FieldDescriptorProto, FieldContext)],
+    ) -> Result<Self> {
+        let name = util::str_or_error(&oneof.name, || "Oneof has no 'name'".to_owned())?;
+        let is_member = |field: &FieldDescriptorProto| field.oneof_index == Some(index as i32);
+        Ok(Self {
+            name: name.to_owned(),
+            is_synthetic: declared
+                .iter()
+                .filter(|field| is_member(field))
+                .any(|field| field.proto3_optional.unwrap_or(false)),
+            fields: fields
+                .iter()
+                .filter(|(field, _)| is_member(field))
+                .map(|(_, context)| context.clone())
+                .collect(),
+            options: oneof.options.clone(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn fields(&self) -> &Vec<FieldContext> {
+        &self.fields
+    }
+    pub fn is_synthetic(&self) -> bool {
+        self.is_synthetic
+    }
+    pub fn options(&self) -> &Option<OneofOptions> {
+        &self.options
+    }
+}
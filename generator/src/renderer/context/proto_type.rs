@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use anyhow::{anyhow, Result};
 use prost::Extendable;
 use prost_types::FieldDescriptorProto;
@@ -69,7 +71,7 @@ fn complex_type_path<'a>(
     change_case: ChangeCase,
 ) -> TypePath<'a> {
     let type_name = complex_type_name(&type_name, config);
-    let mut type_path = proto::TypePath::from_type(type_name);
+    let mut type_path = proto::TypePath::from_type(&type_name);
     if change_case == ChangeCase::Yes {
         type_path.set_name_case(Some(config.case_config.message_name));
     }
@@ -89,14 +91,38 @@ pub fn primitive_type_name(proto_type_id: i32, config: &RendererConfig) -> Resul
     }
 }
 
-fn complex_type_name<'a>(type_name: &'a str, config: &'a RendererConfig) -> &'a str {
+/// Canonical uppercase name of the proto `Type` enum value, e.g. `TYPE_INT32`.
+pub fn proto_type_id_name(proto_type_id: i32) -> Option<&'static str> {
+    let name = match proto_type_id {
+        1 => "TYPE_DOUBLE",
+        2 => "TYPE_FLOAT",
+        3 => "TYPE_INT64",
+        4 => "TYPE_UINT64",
+        5 => "TYPE_INT32",
+        6 => "TYPE_FIXED64",
+        7 => "TYPE_FIXED32",
+        8 => "TYPE_BOOL",
+        9 => "TYPE_STRING",
+        10 => "TYPE_GROUP",
+        11 => "TYPE_MESSAGE",
+        12 => "TYPE_BYTES",
+        13 => "TYPE_UINT32",
+        14 => "TYPE_ENUM",
+        15 => "TYPE_SFIXED32",
+        16 => "TYPE_SFIXED64",
+        17 => "TYPE_SINT32",
+        18 => "TYPE_SINT64",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn complex_type_name<'a>(type_name: &'a str, config: &'a RendererConfig) -> Cow<'a, str> {
     let type_name = proto::normalize_prefix(type_name);
-    let type_name = config
-        .type_config
-        .get(type_name)
-        .map(String::as_str)
-        .unwrap_or(type_name);
-    type_name
+    match config.type_config.get(type_name) {
+        Some(type_name) => Cow::Borrowed(type_name),
+        None => config.package_namespace(type_name),
+    }
 }
 
 fn i32_to_proto_type(val: i32) -> Result<prost_types::field::Kind> {
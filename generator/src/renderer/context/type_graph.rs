@@ -0,0 +1,128 @@
+use std::collections::{HashMap, HashSet};
+
+use prost_types::{DescriptorProto, FileDescriptorProto};
+
+/// References from each message type to the types of its fields, keyed by fully-qualified proto
+/// name with a leading '.', e.g. `.pkg.Message`.
+///
+/// Proto imports can't be circular, so every cycle between message types is contained within a
+/// single file and one file's messages are enough to find recursive fields.
+pub struct TypeGraph {
+    references: HashMap<String, HashSet<String>>,
+}
+
+impl TypeGraph {
+    pub fn from_file(file: &FileDescriptorProto) -> Self {
+        Self::from_messages(file.package.as_ref(), &file.message_type)
+    }
+
+    pub fn from_messages(package: Option<&String>, messages: &[DescriptorProto]) -> Self {
+        let mut graph = Self {
+            references: HashMap::new(),
+        };
+        let scope = package_scope(package.map(String::as_str));
+        for message in messages {
+            graph.insert_message(&scope, message);
+        }
+        graph
+    }
+
+    /// True if `to` can be reached from `from` by following field types, or they are the same.
+    pub fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![from];
+        while let Some(current) = pending.pop() {
+            if current == to {
+                return true;
+            }
+            if !visited.insert(current) {
+                continue;
+            }
+            if let Some(references) = self.references.get(current) {
+                pending.extend(references.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    fn insert_message(&mut self, scope: &str, message: &DescriptorProto) {
+        let name = qualified_name(scope, &message.name);
+        let references = message
+            .field
+            .iter()
+            .filter_map(|field| field.type_name.clone())
+            .collect();
+        for nested in &message.nested_type {
+            self.insert_message(&name, nested);
+        }
+        self.references.insert(name, references);
+    }
+}
+
+/// Fully-qualified proto name of a message declared in `scope`, as used by field type names. The
+/// scope is the package, or the qualified name of the enclosing message for nested messages.
+pub fn message_type_name(scope: Option<&str>, name: &Option<String>) -> String {
+    qualified_name(&package_scope(scope), name)
+}
+
+fn package_scope(package: Option<&str>) -> String {
+    match package {
+        None => String::new(),
+        Some(package) => [".", package].concat(),
+    }
+}
+
+fn qualified_name(scope: &str, name: &Option<String>) -> String {
+    [scope, ".", name.as_deref().unwrap_or_default()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_types::{DescriptorProto, FieldDescriptorProto};
+
+    use crate::renderer::context::type_graph::{message_type_name, TypeGraph};
+
+    #[test]
+    fn message_type_name_with_package() {
+        let name = message_type_name(Some("pkg.sub"), &Some("Name".to_owned()));
+        assert_eq!(name, ".pkg.sub.Name");
+    }
+
+    #[test]
+    fn message_type_name_without_package() {
+        let name = message_type_name(None, &Some("Name".to_owned()));
+        assert_eq!(name, ".Name");
+    }
+
+    #[test]
+    fn reaches_through_nested_types() {
+        let mut outer = message("Outer", &[".pkg.Outer.Inner"]);
+        outer.nested_type.push(message("Inner", &[".pkg.Other"]));
+        let other = message("Other", &[".pkg.Outer"]);
+        let graph = TypeGraph::from_messages(Some(&"pkg".to_owned()), &[outer, other]);
+        assert!(graph.reaches(".pkg.Other", ".pkg.Outer.Inner"));
+        assert!(graph.reaches(".pkg.Outer.Inner", ".pkg.Outer"));
+    }
+
+    #[test]
+    fn does_not_reach_unreferenced_type() {
+        let first = message("First", &[".pkg.Second"]);
+        let second = message("Second", &[]);
+        let graph = TypeGraph::from_messages(Some(&"pkg".to_owned()), &[first, second]);
+        assert!(!graph.reaches(".pkg.Second", ".pkg.First"));
+    }
+
+    fn message(name: &str, field_types: &[&str]) -> DescriptorProto {
+        DescriptorProto {
+            name: Some(name.to_owned()),
+            field: field_types
+                .iter()
+                .map(|type_name| FieldDescriptorProto {
+                    type_name: Some(type_name.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
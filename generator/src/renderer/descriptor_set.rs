@@ -0,0 +1,271 @@
+use crate::in_out_generator::InOutGenerator;
+use crate::render::{Render, RenderOptions};
+use crate::renderer::{
+    collect_output_files, error_output_collision, find_existing_config_path, is_ignored_file,
+    load_config, report_progress, write_sidecars, RendererConfig,
+};
+use crate::{util, Config, ConfigFormat, DisplayNormalized, InOutConfig};
+use anyhow::Result;
+use log::info;
+use prost::Message;
+use prost_types::FileDescriptorSet;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Writes each --descriptor-sets INPUT [OUTPUT].
+pub fn generate(config: &Config, descriptor_set: &FileDescriptorSet) -> Result<bool> {
+    Generator {
+        config,
+        renderer: DescriptorSetRenderer::new(),
+    }
+    .generate_from_descriptor_set(descriptor_set)
+}
+
+/// Instead of rendering text, splits the input descriptor set into binary `FileDescriptorSet`s,
+/// one for each proto file, one for each package when `one_file_per_package` is true, or a single
+/// set when `single_file` is set.
+///
+/// The INPUT folder of --descriptor-sets only needs the config file, and `file_extension` is the
+/// extension of the written files, e.g. `pb`.
+pub struct DescriptorSetRenderer {
+    config: RendererConfig,
+}
+
+impl DescriptorSetRenderer {
+    pub fn new() -> Self {
+        Self {
+            config: Default::default(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_config(config: RendererConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &RendererConfig {
+        &self.config
+    }
+}
+
+impl Render for DescriptorSetRenderer {
+    /// Loads only the config, there are no templates.
+    fn load(
+        &mut self,
+        input_root: &Path,
+        _: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        self.config = load_config(&find_existing_config_path(input_root)?, &[], config_format)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {}
+
+    fn has_files_to_render(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        options: &RenderOptions,
+    ) -> bool {
+        descriptor_set
+            .file
+            .iter()
+            .any(|file| !is_ignored_file(&self.config, file, options))
+    }
+
+    fn check_output_paths(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let mut sources = HashMap::<PathBuf, &str>::new();
+        for (source, path, _) in
+            collect_output_files(&self.config, descriptor_set, output_path, options)?
+        {
+            if let Some(existing) = sources.insert(path.clone(), source) {
+                return Err(error_output_collision(&path, existing, source));
+            }
+        }
+        Ok(())
+    }
+
+    /// Overlays, native files and source file hashes of `options` don't apply, there are no
+    /// contexts or metadata.
+    fn render(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let sets = collect_output_files(&self.config, descriptor_set, output_path, options)?;
+        let total = sets.len();
+        for (i, (_, path, files)) in sets.into_iter().enumerate() {
+            if options.progress {
+                report_progress(i + 1, total);
+            }
+            info!(
+                "Writing descriptor set file: '{}'",
+                path.display_normalized()
+            );
+            let subset = FileDescriptorSet {
+                file: files.into_iter().cloned().collect(),
+            };
+            // Concatenated sets decode as a single set with the files of both.
+            let mut file = if options.append && path.exists() {
+                util::append_file_or_error(&path)?
+            } else {
+                util::create_file_or_error(&path)?
+            };
+            file.write_all(&subset.encode_to_vec())?;
+        }
+        write_sidecars(&self.config, output_path)
+    }
+}
+
+struct Generator<'a> {
+    config: &'a Config,
+    renderer: DescriptorSetRenderer,
+}
+impl<'a> InOutGenerator<DescriptorSetRenderer> for Generator<'a> {
+    fn name(&self) -> &str {
+        "Descriptor sets"
+    }
+
+    fn renderer(&mut self) -> &mut DescriptorSetRenderer {
+        &mut self.renderer
+    }
+
+    fn app_config(&self) -> &Config {
+        &self.config
+    }
+
+    fn in_out_configs(&self) -> Vec<InOutConfig> {
+        self.app_config().descriptor_sets.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use anyhow::Result;
+    use prost::Message;
+    use prost_types::{FileDescriptorProto, FileDescriptorSet};
+    use tempfile::tempdir;
+
+    use crate::render::{Render, RenderOptions};
+    use crate::renderer::{DescriptorSetRenderer, RendererConfig};
+
+    #[test]
+    fn one_set_per_package() -> Result<()> {
+        let output = tempdir()?;
+        let mut config = config();
+        config.one_file_per_package = true;
+        let renderer = DescriptorSetRenderer::with_config(config);
+        renderer.render(&descriptor_set(), output.path(), &RenderOptions::default())?;
+
+        assert_eq!(
+            decoded_file_names(&output.path().join("a.pb"))?,
+            vec!["a/file0.proto", "a/file1.proto"]
+        );
+        assert_eq!(
+            decoded_file_names(&output.path().join("b.pb"))?,
+            vec!["b/file2.proto"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn one_set_per_file() -> Result<()> {
+        let output = tempdir()?;
+        let renderer = DescriptorSetRenderer::with_config(config());
+        renderer.render(&descriptor_set(), output.path(), &RenderOptions::default())?;
+
+        for name in ["a/file0", "a/file1", "b/file2"] {
+            let path = output.path().join(name).with_extension("pb");
+            assert_eq!(decoded_file_names(&path)?, vec![format!("{}.proto", name)]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn one_set_for_single_file() -> Result<()> {
+        let output = tempdir()?;
+        let mut config = config();
+        config.single_file = Some("all.pb".to_owned());
+        let renderer = DescriptorSetRenderer::with_config(config);
+        renderer.render(&descriptor_set(), output.path(), &RenderOptions::default())?;
+
+        assert_eq!(
+            decoded_file_names(&output.path().join("all.pb"))?,
+            vec!["a/file0.proto", "a/file1.proto", "b/file2.proto"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn skips_ignored_files() -> Result<()> {
+        let output = tempdir()?;
+        let mut config = config();
+        config.one_file_per_package = true;
+        config.ignored_files = vec!["a/file0.proto".to_owned()];
+        let renderer = DescriptorSetRenderer::with_config(config);
+        renderer.render(&descriptor_set(), output.path(), &RenderOptions::default())?;
+
+        assert_eq!(
+            decoded_file_names(&output.path().join("a.pb"))?,
+            vec!["a/file1.proto"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn loads_config_without_templates() -> Result<()> {
+        let root = tempdir()?;
+        fs::write(
+            root.path().join("config.yml"),
+            "type_config: {}\nfile_extension: bin\none_file_per_package: true\n",
+        )?;
+        let mut renderer = DescriptorSetRenderer::new();
+        renderer.load(root.path(), &[], None)?;
+        assert_eq!(renderer.config().file_extension, "bin");
+        assert!(renderer.config().one_file_per_package);
+        Ok(())
+    }
+
+    fn config() -> RendererConfig {
+        let mut config = RendererConfig::default();
+        config.file_extension = "pb".to_owned();
+        config
+    }
+
+    fn descriptor_set() -> FileDescriptorSet {
+        FileDescriptorSet {
+            file: vec![
+                file("a/file0.proto", "a"),
+                file("a/file1.proto", "a"),
+                file("b/file2.proto", "b"),
+            ],
+        }
+    }
+
+    fn file(name: &str, package: &str) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_owned()),
+            package: Some(package.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn decoded_file_names(path: &Path) -> Result<Vec<String>> {
+        let decoded = FileDescriptorSet::decode(fs::read(path)?.as_slice())?;
+        Ok(decoded
+            .file
+            .into_iter()
+            .map(|file| file.name.unwrap_or_default())
+            .collect())
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{anyhow, Result};
+
+/// Styles accepted by `escape_string`.
+const STYLES: &[&str] = &["rust", "c", "json"];
+
+/// `s` escaped to be placed between double quotes in a string literal of the language `style`,
+/// one of `rust`, `c` or `json`. Quotes, backslashes and ASCII control characters are escaped,
+/// anything else is kept as is.
+pub fn escape_string(s: &str, style: &str) -> Result<String> {
+    let escape_control: fn(char) -> String = match style {
+        "rust" => |c| format!("\\u{{{:x}}}", c as u32),
+        // Always 3 digits so a following digit isn't read as part of the escape.
+        "c" => |c| format!("\\{:03o}", c as u32),
+        "json" => |c| format!("\\u{:04x}", c as u32),
+        _ => {
+            return Err(anyhow!(
+                "Unknown escape style '{}'. Known styles: {}",
+                style,
+                STYLES.join(", ")
+            ))
+        }
+    };
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_ascii_control() => escaped.push_str(&escape_control(c)),
+            c => escaped.push(c),
+        }
+    }
+    Ok(escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::renderer::escape::escape_string;
+
+    #[test]
+    fn rust() -> Result<()> {
+        assert_eq!(
+            escape_string("say \"hi\"\n\\\u{1b}", "rust")?,
+            r#"say \"hi\"\n\\\u{1b}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn c() -> Result<()> {
+        assert_eq!(escape_string("a\"\0\t1", "c")?, r#"a\"\000\t1"#);
+        Ok(())
+    }
+
+    #[test]
+    fn json() -> Result<()> {
+        assert_eq!(escape_string("\u{8}é\"", "json")?, r#"\u0008é\""#);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_style_errors() {
+        assert!(escape_string("", "cobol").is_err());
+    }
+}
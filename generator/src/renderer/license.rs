@@ -0,0 +1,97 @@
+use anyhow::{anyhow, Result};
+
+/// Short notices for common SPDX license identifiers, written below the
+/// `SPDX-License-Identifier` line when `license_notice` is true.
+const NOTICES: &[(&str, &str)] = &[
+    (
+        "Apache-2.0",
+        "Licensed under the Apache License, Version 2.0: https://www.apache.org/licenses/LICENSE-2.0",
+    ),
+    (
+        "BSD-2-Clause",
+        "Licensed under the BSD 2-Clause License: https://opensource.org/licenses/BSD-2-Clause",
+    ),
+    (
+        "BSD-3-Clause",
+        "Licensed under the BSD 3-Clause License: https://opensource.org/licenses/BSD-3-Clause",
+    ),
+    (
+        "GPL-2.0-only",
+        "Licensed under the GNU General Public License v2.0: https://www.gnu.org/licenses/old-licenses/gpl-2.0.html",
+    ),
+    (
+        "GPL-3.0-only",
+        "Licensed under the GNU General Public License v3.0: https://www.gnu.org/licenses/gpl-3.0.html",
+    ),
+    (
+        "LGPL-3.0-only",
+        "Licensed under the GNU Lesser General Public License v3.0: https://www.gnu.org/licenses/lgpl-3.0.html",
+    ),
+    (
+        "MIT",
+        "Licensed under the MIT License: https://opensource.org/licenses/MIT",
+    ),
+    (
+        "MPL-2.0",
+        "Licensed under the Mozilla Public License 2.0: https://mozilla.org/MPL/2.0/",
+    ),
+    (
+        "Unlicense",
+        "Released into the public domain under the Unlicense: https://unlicense.org",
+    ),
+];
+
+/// Lines written at the top of generated files for the SPDX license identifier `id`, each
+/// prefixed with `comment_prefix`. With `notice`, the bundled notice for `id` follows the
+/// identifier, which is an error if there is none.
+pub fn license_header(id: &str, notice: bool, comment_prefix: &str) -> Result<String> {
+    let mut header = format!("{}SPDX-License-Identifier: {}\n", comment_prefix, id);
+    if notice {
+        let (_, text) = NOTICES
+            .iter()
+            .find(|(known, _)| *known == id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No bundled notice for license '{}'. Known licenses: {}",
+                    id,
+                    NOTICES
+                        .iter()
+                        .map(|(known, _)| *known)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+        header.push_str(&format!("{}{}\n", comment_prefix, text));
+    }
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use crate::renderer::license::license_header;
+
+    #[test]
+    fn identifier_only() -> Result<()> {
+        assert_eq!(
+            license_header("MIT OR Apache-2.0", false, "// ")?,
+            "// SPDX-License-Identifier: MIT OR Apache-2.0\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn with_notice() -> Result<()> {
+        assert_eq!(
+            license_header("MIT", true, "# ")?,
+            "# SPDX-License-Identifier: MIT\n# Licensed under the MIT License: https://opensource.org/licenses/MIT\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_notice_errors() {
+        assert!(license_header("Custom-1.0", true, "// ").is_err());
+    }
+}
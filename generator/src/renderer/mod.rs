@@ -1,21 +1,28 @@
-use std::collections::{HashMap, HashSet};
-use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{fs, io};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::{debug, info};
+use prost::Extendable;
 use prost_types::{FileDescriptorProto, FileDescriptorSet};
+use rayon::prelude::*;
 use serde::de::DeserializeOwned;
 
+pub use descriptor_set::DescriptorSetRenderer;
 pub use overlay_config::OverlayConfig;
-pub use renderer_config::RendererConfig;
+pub use renderer_config::{MapDetection, RegexOverride, RendererConfig, TemplateEngine};
 
-use crate::render::Render;
-use crate::renderer::context::{FileContext, MetadataContext};
-use crate::{util, DisplayNormalized};
+use crate::render::{Render, RenderOptions};
+use crate::renderer::context::{FileContext, MapValueTypes, MetadataContext};
+use crate::{util, ConfigFormat, DisplayNormalized};
 
-mod case;
-mod context;
+pub(crate) mod case;
+pub mod context;
+pub mod descriptor_set;
+mod escape;
+mod license;
 mod overlay_config;
 mod primitive;
 mod proto;
@@ -23,9 +30,15 @@ mod renderer_config;
 pub mod scripted;
 pub mod template;
 
-pub const CONFIG_FILE_NAMES: &[&'static str] = &["config.yml", "config.json", "config.yaml"];
+/// Config files are parsed as YAML, which is a superset of JSON, or as the `--config-format`
+/// override, never by extension. So an extensionless `config` (e.g. a symlink) works too. It is
+/// checked last so that named configs take precedence.
+pub const CONFIG_FILE_NAMES: &[&'static str] =
+    &["config.yml", "config.json", "config.yaml", "config"];
 pub const DEFAULT_CONFIG_FILE_NAME: &'static str = CONFIG_FILE_NAMES[0];
 
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
 const DEFAULT_GENERATED_HEADER: &str = r#"/////////////////////////////////////////////////////
 // *** DO NOT EDIT MANUALLY ***
 // This file is generated by the utility `protox`.
@@ -35,54 +48,113 @@ const DEFAULT_GENERATED_HEADER: &str = r#"//////////////////////////////////////
 
 // Delegate public Render impl to internal Renderer impl.
 impl<R: Renderer> Render for R {
-    fn load(&mut self, input_root: &Path, overlays: &[PathBuf]) -> Result<()> {
-        Renderer::load(self, input_root, overlays)
+    fn load(
+        &mut self,
+        input_root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        Renderer::load(self, input_root, overlays, config_format)
     }
     fn reset(&mut self) {
         Renderer::reset(self)
     }
-    fn render(&self, descriptor_set: &FileDescriptorSet, output_path: &Path) -> Result<()> {
-        if self.config().one_file_per_package {
-            let package_files = self.render_files_collapsed(descriptor_set, output_path)?;
-            self.render_metadata_with_package_files(output_path, package_files)?;
-        } else {
-            self.render_files(descriptor_set, output_path)?;
-            self.render_metadata_for_directories(descriptor_set, output_path)?;
+    fn has_files_to_render(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        options: &RenderOptions,
+    ) -> bool {
+        self.count_rendered_files(descriptor_set, options) > 0
+    }
+    fn check_output_paths(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let mut sources = HashMap::<PathBuf, &str>::new();
+        for (source, path) in self.collect_output_paths(descriptor_set, output_path, options)? {
+            if let Some(existing) = sources.insert(path.clone(), source) {
+                return Err(error_output_collision(&path, existing, source));
+            }
         }
         Ok(())
     }
+    fn render(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if let Some(single_file) = &self.config().single_file {
+            let path =
+                self.render_files_single(descriptor_set, output_path, single_file, options)?;
+            self.render_metadata_for_single_file(descriptor_set, output_path, &path, options)?;
+        } else if self.config().one_file_per_package {
+            let package_files =
+                self.render_files_collapsed(descriptor_set, output_path, options)?;
+            self.render_metadata_with_package_files(
+                descriptor_set,
+                output_path,
+                package_files,
+                options,
+            )?;
+        } else {
+            self.render_files(descriptor_set, output_path, options)?;
+            self.render_metadata_for_directories(descriptor_set, output_path, options)?;
+        }
+        self.render_directories(descriptor_set, output_path, options)?;
+        write_sidecars(self.config(), output_path)
+    }
 }
 
-pub trait Renderer {
-    fn load_config(path: &Path, overlays: &[PathBuf]) -> Result<RendererConfig> {
-        info!("Loading config from: {}", path.display_normalized());
-        let mut config: RendererConfig = deserialize_yaml_file(path).context("RendererConfig")?;
-        Self::load_overlays(&mut config.overlays, overlays)?;
-        config.overlays.initialize();
-        Ok(config)
+/// Renderers are shared between the threads rendering each file, so they must be `Sync`.
+pub trait Renderer: Sync {
+    fn load_config(
+        path: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<RendererConfig> {
+        load_config(path, overlays, config_format)
     }
 
     fn load_overlays(base: &mut OverlayConfig, paths: &[PathBuf]) -> Result<()> {
-        for path in paths {
-            info!("Loading overlay config from: {}", path.display_normalized());
-            let overlay = deserialize_yaml_file(path).context("OverlayConfig")?;
-            base.merge(overlay);
-        }
-        Ok(())
+        load_overlays(base, paths)
     }
 
-    /// Load any necessary files from the `input_root` directory and overlays as specified.
-    fn load(&mut self, input_root: &Path, overlays: &[PathBuf]) -> Result<()>;
+    /// Load any necessary files from the `input_root` directory and overlays as specified. The
+    /// config file is parsed as `config_format` if set, otherwise as YAML.
+    fn load(
+        &mut self,
+        input_root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()>;
 
     /// Reset is called between runs with different input/outputs.
     fn reset(&mut self);
 
     fn config(&self) -> &RendererConfig;
+    /// Replace the loaded config, e.g. when rendering directly via `render_descriptor_set`.
+    fn set_config(&mut self, config: RendererConfig);
     fn has_metadata(&self) -> bool;
     fn render_metadata<W: io::Write>(&self, context: MetadataContext, writer: &mut W)
         -> Result<()>;
     fn render_file<W: io::Write>(&self, context: FileContext, writer: &mut W) -> Result<()>;
 
+    /// Whether `render_directory` should be called for each directory of generated files.
+    fn has_directory_renderer(&self) -> bool {
+        false
+    }
+
+    /// Renders the aggregate file of a directory, e.g. a Python `__init__.py`, from a context
+    /// listing the files generated there. Only supported by the ScriptedRenderer.
+    fn render_directory<W: io::Write>(&self, _: MetadataContext, _: &mut W) -> Result<()> {
+        Err(anyhow!(
+            "render_directory is not supported by this renderer"
+        ))
+    }
+
     fn output_ext(&self) -> &str {
         &self.config().file_extension
     }
@@ -91,43 +163,164 @@ pub trait Renderer {
         &self.config().metadata_file_name
     }
 
-    fn render_files(&self, descriptor_set: &FileDescriptorSet, output_path: &Path) -> Result<()> {
-        for file in &descriptor_set.file {
-            if self.is_ignored_file(file) {
-                log_ignore_file(&file.name, &self.config().file_extension);
-                continue;
-            }
-            let file_name = &file_name(file, self.output_ext())?;
-            info!("Rendering file for descriptor '{}'", file_name);
-            let path = &output_path.join(file_name);
-            let mut writer = self.file_writer(&path)?;
-            log_render_file(&file.name, &self.config().file_extension);
-            let context = FileContext::new(file, &self.config())?;
-            self.render_file(context, &mut writer)?;
+    /// Renders each file to its own output file. With `--since`, unchanged files are skipped.
+    fn render_files(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let files = descriptor_set
+            .file
+            .iter()
+            .filter(|file| {
+                if self.is_ignored_file(file, options) {
+                    log_ignore_file(&file.name, &self.config().file_extension);
+                    return false;
+                }
+                options.is_changed_file(util::str_or_unknown(&file.name))
+            })
+            .collect::<Vec<&FileDescriptorProto>>();
+        let total = files.len();
+        let current = AtomicUsize::new(0);
+        let config = options.apply(self.config());
+        let map_values =
+            MapValueTypes::from_files(&descriptor_set.file, self.config().map_detection);
+        let output_paths = output_paths_by_file(self.config(), descriptor_set, options)?;
+        // Files are rendered in parallel, except that files sharing an output path, e.g. with
+        // --allow-overwrite or --append, are rendered one after another in file order. Results are
+        // collected in file order so the error reported is the same for any number of jobs.
+        let groups = self.group_by_output_path(&files, output_path)?;
+        let mut results = util::thread_pool(options.jobs.max(1))?.install(|| {
+            groups
+                .par_iter()
+                .flat_map_iter(|group| {
+                    group.iter().map(|(i, file)| {
+                        let current = current.fetch_add(1, Ordering::SeqCst) + 1;
+                        if options.progress {
+                            self.report_progress(current, total);
+                        }
+                        let result = self.render_file_to_output(
+                            file,
+                            output_path,
+                            &config,
+                            &map_values,
+                            &output_paths,
+                            options,
+                        );
+                        (*i, result)
+                    })
+                })
+                .collect::<Vec<(usize, Result<()>)>>()
+        });
+        results.sort_by_key(|(i, _)| *i);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// `files` paired with their index, grouped by the path each is rendered to under
+    /// `output_path`. Groups are in order of their first file and files keep their order.
+    fn group_by_output_path<'a>(
+        &self,
+        files: &[&'a FileDescriptorProto],
+        output_path: &Path,
+    ) -> Result<Vec<Vec<(usize, &'a FileDescriptorProto)>>> {
+        let mut groups = Vec::<Vec<(usize, &FileDescriptorProto)>>::new();
+        let mut group_by_path = HashMap::new();
+        for (i, file) in files.iter().enumerate() {
+            let path = self.output_file_path(&output_path.join(file_name(file, self.config())?));
+            let group = *group_by_path.entry(path).or_insert_with(|| {
+                groups.push(vec![]);
+                groups.len() - 1
+            });
+            groups[group].push((i, *file));
         }
-        Ok(())
+        Ok(groups)
+    }
+
+    /// Renders `file` to its own output file under `output_path`.
+    fn render_file_to_output(
+        &self,
+        file: &FileDescriptorProto,
+        output_path: &Path,
+        config: &RendererConfig,
+        map_values: &MapValueTypes,
+        output_paths: &HashMap<String, String>,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        let file_name = &file_name(file, self.config())?;
+        info!("Rendering file for descriptor '{}'", file_name);
+        let path = &output_path.join(file_name);
+        let mut writer = self.file_writer(&path, options)?;
+        log_render_file(&file.name, &self.config().file_extension);
+        let mut context = file_context(file, config, map_values, output_paths, options)?;
+        context.set_output_path(self.relative_output_path(output_path, path)?);
+        if let Some(suffix) = &self.config().enums_output_suffix {
+            self.render_enums_file(&context, output_path, path, suffix, options)?;
+            context.clear_enums();
+        }
+        self.render_file(context, &mut writer)
+    }
+
+    /// Renders the top-level enums of `context` into the `enums_output_suffix` file next to the
+    /// file written to `path`, if it has any.
+    fn render_enums_file(
+        &self,
+        context: &FileContext,
+        output_path: &Path,
+        path: &Path,
+        suffix: &str,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if context.enums().is_empty() {
+            return Ok(());
+        }
+        let enums_path = enums_file_path(&self.output_file_path(path), suffix);
+        info!(
+            "Rendering enums file: '{}'",
+            enums_path.display_normalized()
+        );
+        let mut context = context.clone();
+        context.clear_messages();
+        context.set_output_path(enums_path.strip_prefix(output_path)?.display_normalized());
+        let mut writer = self.file_writer_exact(&enums_path, options)?;
+        self.render_file(context, &mut writer)
     }
 
     fn render_files_collapsed(
         &self,
         descriptor_set: &FileDescriptorSet,
         output_path: &Path,
+        options: &RenderOptions,
     ) -> Result<HashMap<String, PathBuf>> {
         let package_to_files = self.collect_package_to_file_map(descriptor_set);
         let mut package_files = HashMap::new();
+        let total = self.count_rendered_files(descriptor_set, options);
+        let mut current = 0;
+        let config = options.apply(self.config());
+        let map_values =
+            MapValueTypes::from_files(&descriptor_set.file, self.config().map_detection);
+        let output_paths = output_paths_by_file(self.config(), descriptor_set, options)?;
         for (package, files) in package_to_files {
             let files = files
                 .into_iter()
-                .filter(|f| !self.is_ignored_file(f))
+                .filter(|f| !self.is_ignored_file(f, options))
                 .collect::<Vec<&FileDescriptorProto>>();
             if files.is_empty() {
                 continue;
             }
             let path = &self.package_to_file_path(output_path, package);
-            let mut writer = self.file_writer(&path)?;
-            for file in files {
+            let mut writer = self.file_writer(&path, options)?;
+            for (i, file) in files.into_iter().enumerate() {
+                if i > 0 {
+                    self.write_file_separator(&mut writer)?;
+                }
+                current += 1;
+                if options.progress {
+                    self.report_progress(current, total);
+                }
                 log_render_package_file(file, package);
-                let context = FileContext::new(file, &self.config())?;
+                let mut context = file_context(file, &config, &map_values, &output_paths, options)?;
+                context.set_output_path(self.relative_output_path(output_path, path)?);
                 self.render_file(context, &mut writer)?;
             }
             package_files.insert(
@@ -138,66 +331,260 @@ pub trait Renderer {
         Ok(package_files)
     }
 
+    /// Renders every file into `single_file`, returning its path relative to `output_path`.
+    fn render_files_single(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        single_file: &str,
+        options: &RenderOptions,
+    ) -> Result<PathBuf> {
+        let total = self.count_rendered_files(descriptor_set, options);
+        let config = options.apply(self.config());
+        let map_values =
+            MapValueTypes::from_files(&descriptor_set.file, self.config().map_detection);
+        let output_paths = output_paths_by_file(self.config(), descriptor_set, options)?;
+        let path = &output_path.join(single_file);
+        let mut writer = self.file_writer(&path, options)?;
+        let files = descriptor_set
+            .file
+            .iter()
+            .filter(|file| !self.is_ignored_file(file, options));
+        for (i, file) in files.enumerate() {
+            if i > 0 {
+                self.write_file_separator(&mut writer)?;
+            }
+            if options.progress {
+                self.report_progress(i + 1, total);
+            }
+            log_render_file(&file.name, &self.config().file_extension);
+            let mut context = file_context(file, &config, &map_values, &output_paths, options)?;
+            context.set_output_path(self.relative_output_path(output_path, path)?);
+            self.render_file(context, &mut writer)?;
+        }
+        Ok(self
+            .output_file_path(path)
+            .strip_prefix(output_path)?
+            .to_path_buf())
+    }
+
+    fn render_metadata_for_single_file(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        file: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if !self.has_metadata() {
+            return Ok(());
+        }
+        let dir = util::path_parent_or_error(file)?;
+        let mut context = MetadataContext::with_relative_dir(dir)?;
+        context.push_file(file)?;
+        context.append_native_files(&options.native_files)?;
+        self.render_metadata_to_file(descriptor_set, output_path, context, options)
+    }
+
     fn render_metadata_for_directories(
         &self,
         descriptor_set: &FileDescriptorSet,
         output_path: &Path,
+        options: &RenderOptions,
     ) -> Result<()> {
         if !self.has_metadata() {
             return Ok(());
         }
-        let (dirs, files) = collect_dirs_and_files(descriptor_set)?;
+        let (dirs, files) = collect_dirs_and_files(descriptor_set, self.config(), options)?;
         let mut contexts = Vec::new();
         for dir in &dirs {
             let mut context = MetadataContext::with_relative_dir(dir)?;
             context.append_subdirectories(dirs.iter())?;
-            context.append_files(&files)?;
+            context.append_described_files(&files)?;
+            context.append_native_files(&options.native_files)?;
             contexts.push(context);
         }
         for context in contexts {
-            self.render_metadata_to_file(output_path, context)?;
+            self.render_metadata_to_file(descriptor_set, output_path, context, options)?;
+        }
+        Ok(())
+    }
+
+    /// Calls `render_directory` once for each directory that files were generated into, after
+    /// they have all been rendered.
+    fn render_directories(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if !self.has_directory_renderer() {
+            return Ok(());
+        }
+        let mut dirs = BTreeMap::<PathBuf, Vec<PathBuf>>::new();
+        for (_, path) in self.collect_output_paths(descriptor_set, output_path, options)? {
+            let relative = path.strip_prefix(output_path)?.to_path_buf();
+            let dir = util::path_parent_or_error(&relative)?.to_path_buf();
+            dirs.entry(dir).or_default().push(relative);
+        }
+        for (dir, files) in dirs {
+            let mut context = MetadataContext::with_relative_dir(&dir)?;
+            context.append_files(&files)?;
+            let file_path = output_path
+                .join(&dir)
+                .join(&self.config().directory_file_name)
+                .with_extension(&self.config().file_extension);
+            info!(
+                "Rendering directory file: '{}'",
+                file_path.display_normalized()
+            );
+            context.set_output_path(file_path.strip_prefix(output_path)?.display_normalized());
+            let mut writer = self.file_writer_exact(&file_path, options)?;
+            self.render_directory(context, &mut writer)?;
         }
         Ok(())
     }
 
-    fn render_metadata_to_file(&self, output_path: &Path, context: MetadataContext) -> Result<()> {
+    fn render_metadata_to_file(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        output_path: &Path,
+        mut context: MetadataContext,
+        options: &RenderOptions,
+    ) -> Result<()> {
         let file_path = self.metadata_file_path(output_path, &context);
         log_render_metadata(&file_path);
-        let mut writer = self.file_writer(&file_path)?;
+        context.set_output_path(self.relative_output_path(output_path, &file_path)?);
+        context.set_common_package_prefix(context::common_package_prefix(
+            descriptor_set
+                .file
+                .iter()
+                .filter(|file| !self.is_ignored_file(file, options))
+                .map(|file| file.package.as_deref().unwrap_or("")),
+        ));
+        let mut writer = self.file_writer(&file_path, options)?;
         self.render_metadata(context, &mut writer)?;
         Ok(())
     }
 
     fn render_metadata_with_package_files(
         &self,
+        descriptor_set: &FileDescriptorSet,
         output_path: &Path,
         package_files: HashMap<String, PathBuf>,
+        options: &RenderOptions,
     ) -> Result<()> {
         if !self.has_metadata() {
             return Ok(());
         }
         let mut context = MetadataContext::new();
         context.append_package_files(package_files);
-        self.render_metadata_to_file(output_path, context)?;
+        context
+            .set_package_descriptions(&self.collect_package_descriptions(descriptor_set, options));
+        context.append_native_files(&options.native_files)?;
+        self.render_metadata_to_file(descriptor_set, output_path, context, options)?;
         Ok(())
     }
 
-    fn file_writer(&self, path: &Path) -> Result<io::BufWriter<fs::File>> {
-        let path = self.config().case_config.file_name.rename_file_name(path);
-        let mut writer = io::BufWriter::new(util::create_file_or_error(&path)?);
+    /// Map of package -> description of the first file in that package that has one.
+    fn collect_package_descriptions(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        options: &RenderOptions,
+    ) -> HashMap<String, String> {
+        let mut descriptions = HashMap::new();
+        for (package, files) in self.collect_package_to_file_map(descriptor_set) {
+            let description = files
+                .into_iter()
+                .filter(|file| !self.is_ignored_file(file, options))
+                .map(context::file_description)
+                .find(|description| !description.is_empty());
+            if let Some(description) = description {
+                descriptions.insert(package.to_owned(), description);
+            }
+        }
+        descriptions
+    }
+
+    /// Pairs of (source, output file path) for every file that would be rendered, including the
+    /// enums files. See `collect_output_files` for the sources.
+    fn collect_output_paths<'a>(
+        &'a self,
+        descriptor_set: &'a FileDescriptorSet,
+        output_path: &Path,
+        options: &RenderOptions,
+    ) -> Result<Vec<(&'a str, PathBuf)>> {
+        let is_per_file =
+            self.config().single_file.is_none() && !self.config().one_file_per_package;
+        let mut paths = Vec::new();
+        for (source, path, files) in
+            collect_output_files(self.config(), descriptor_set, output_path, options)?
+        {
+            if let Some(suffix) = &self.config().enums_output_suffix {
+                if is_per_file && !files[0].enum_type.is_empty() {
+                    paths.push((source, enums_file_path(&path, suffix)));
+                }
+            }
+            paths.push((source, path));
+        }
+        Ok(paths)
+    }
+
+    /// Final path of a file written to `path`, after applying the configured file name case.
+    fn output_file_path(&self, path: &Path) -> PathBuf {
+        output_file_path(self.config(), path)
+    }
+
+    /// Final path of a file written to `path`, relative to `output_path` and with `/` separators.
+    fn relative_output_path(&self, output_path: &Path, path: &Path) -> Result<String> {
+        let path = self.output_file_path(path);
+        Ok(path.strip_prefix(output_path)?.display_normalized())
+    }
+
+    fn file_writer(&self, path: &Path, options: &RenderOptions) -> Result<io::BufWriter<fs::File>> {
+        self.file_writer_exact(&self.output_file_path(path), options)
+    }
+
+    /// Like `file_writer`, but writes to `path` as is, without applying the file name case.
+    ///
+    /// With `append`, a file which already exists is appended to, without the BOM or header.
+    fn file_writer_exact(
+        &self,
+        path: &Path,
+        options: &RenderOptions,
+    ) -> Result<io::BufWriter<fs::File>> {
+        if options.append && path.exists() {
+            return Ok(io::BufWriter::new(util::append_file_or_error(path)?));
+        }
+        let mut writer = io::BufWriter::new(util::create_file_or_error(path)?);
+        if self.config().emit_bom {
+            io::Write::write_all(&mut writer, UTF8_BOM)?;
+        }
         self.write_generated_header(&mut writer)?;
         Ok(writer)
     }
 
     fn write_generated_header<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        let config = self.config();
+        if let Some(id) = &config.license {
+            let header =
+                license::license_header(id, config.license_notice, &config.license_comment_prefix)?;
+            writer.write_all(header.as_bytes())?;
+        }
         if let Some(configured_header) = &self.config().generated_header {
             if !configured_header.is_empty() {
                 let mut header = configured_header.join("\n");
                 header.push('\n');
-                writer.write(header.as_bytes())?;
+                writer.write_all(header.as_bytes())?;
             }
         } else {
-            writer.write(DEFAULT_GENERATED_HEADER.as_bytes())?;
+            writer.write_all(DEFAULT_GENERATED_HEADER.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_file_separator<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        if let Some(separator) = &self.config().file_separator {
+            writer.write_all(separator.as_bytes())?;
         }
         Ok(())
     }
@@ -216,35 +603,283 @@ pub trait Renderer {
     }
 
     fn package_to_file_path(&self, root: &Path, package: &str) -> PathBuf {
-        root.join(package.replace(proto::PACKAGE_SEPARATOR, "_"))
-            .with_extension(&self.config().file_extension)
+        package_to_file_path(self.config(), root, package)
     }
 
     fn metadata_file_path(&self, output: &Path, context: &MetadataContext) -> PathBuf {
+        let output = match &self.config().metadata_output_dir {
+            None => output.to_path_buf(),
+            Some(dir) => output.join(dir),
+        };
         output
             .join(context.relative_dir())
             .join(self.metadata_file_name())
             .with_extension(&self.config().file_extension)
     }
 
-    fn is_ignored_file(&self, file: &FileDescriptorProto) -> bool {
-        match file.name.as_ref() {
-            None => true,
-            Some(file) => self.config().ignored_files.contains(file),
+    /// Called before each file is rendered when progress reporting is enabled.
+    fn report_progress(&self, current: usize, total: usize) {
+        report_progress(current, total);
+    }
+
+    fn count_rendered_files(
+        &self,
+        descriptor_set: &FileDescriptorSet,
+        options: &RenderOptions,
+    ) -> usize {
+        descriptor_set
+            .file
+            .iter()
+            .filter(|f| !self.is_ignored_file(f, options))
+            .count()
+    }
+
+    fn is_ignored_file(&self, file: &FileDescriptorProto, options: &RenderOptions) -> bool {
+        is_ignored_file(self.config(), file, options)
+    }
+}
+
+pub(crate) fn load_config(
+    path: &Path,
+    overlays: &[PathBuf],
+    config_format: Option<ConfigFormat>,
+) -> Result<RendererConfig> {
+    info!("Loading config from: {}", path.display_normalized());
+    let mut config: RendererConfig =
+        deserialize_config_file(path, config_format).context("RendererConfig")?;
+    expand_config_env_vars(&mut config).context("RendererConfig")?;
+    load_overlays(&mut config.overlays, overlays)?;
+    config.overlays.initialize();
+    if let Some(path) = config.overlays.generated_header_file() {
+        config.generated_header_file = Some(PathBuf::from(util::expand_env_vars(
+            &path.to_string_lossy(),
+        )?));
+    }
+    // Config files are always found at the template root, see `find_existing_config_path`.
+    if let Some(template_root) = path.parent() {
+        load_generated_header_file(&mut config, template_root)?;
+    }
+    Ok(config)
+}
+
+/// Triples of (source, output file path, files written to it) for every file written with
+/// `config`. The source is the proto file name, the package name when `one_file_per_package` is
+/// true, or `single_file`.
+pub(crate) fn collect_output_files<'a>(
+    config: &'a RendererConfig,
+    descriptor_set: &'a FileDescriptorSet,
+    output_path: &Path,
+    options: &RenderOptions,
+) -> Result<Vec<(&'a str, PathBuf, Vec<&'a FileDescriptorProto>)>> {
+    let files = descriptor_set
+        .file
+        .iter()
+        .filter(|file| !is_ignored_file(config, file, options));
+    let mut output_files = Vec::new();
+    if let Some(single_file) = &config.single_file {
+        let path = output_file_path(config, &output_path.join(single_file));
+        output_files.push((single_file.as_str(), path, files.collect()));
+    } else if config.one_file_per_package {
+        let mut packages = BTreeMap::<&str, Vec<&FileDescriptorProto>>::new();
+        for file in files {
+            let package = package(file, &config.default_package_file_name);
+            packages.entry(package).or_default().push(file);
+        }
+        for (package, files) in packages {
+            let path =
+                output_file_path(config, &package_to_file_path(config, output_path, package));
+            output_files.push((package, path, files));
+        }
+    } else {
+        for file in files {
+            let path = output_path.join(file_relative_path(file, config)?);
+            output_files.push((util::str_or_unknown(&file.name), path, vec![file]));
+        }
+    }
+    Ok(output_files)
+}
+
+/// Path of the output file each proto file of `descriptor_set` is rendered into with `config`,
+/// relative to the output directory and keyed by the proto file name.
+fn output_paths_by_file(
+    config: &RendererConfig,
+    descriptor_set: &FileDescriptorSet,
+    options: &RenderOptions,
+) -> Result<HashMap<String, String>> {
+    let mut paths = HashMap::new();
+    for (_, path, files) in collect_output_files(config, descriptor_set, Path::new(""), options)? {
+        let path = path.display_normalized();
+        for file in files {
+            paths.insert(util::str_or_unknown(&file.name).to_owned(), path.clone());
+        }
+    }
+    Ok(paths)
+}
+
+/// Final path of a file written to `path`, after applying the configured file name case.
+fn output_file_path(config: &RendererConfig, path: &Path) -> PathBuf {
+    config.case_config.file_name.rename_file_name(path)
+}
+
+/// True if `file` is skipped by `config` in the run with `options`.
+pub(crate) fn is_ignored_file(
+    config: &RendererConfig,
+    file: &FileDescriptorProto,
+    options: &RenderOptions,
+) -> bool {
+    match file.name.as_ref() {
+        None => true,
+        Some(file) => options.is_ignored_file(config, file),
+    }
+}
+
+pub(crate) fn report_progress(current: usize, total: usize) {
+    eprintln!("rendering file {} of {}", current, total);
+}
+
+/// Context for rendering `file`, with its source file hash from `options` and the paths its
+/// imports are rendered to from `output_paths`.
+fn file_context(
+    file: &FileDescriptorProto,
+    config: &RendererConfig,
+    map_values: &MapValueTypes,
+    output_paths: &HashMap<String, String>,
+    options: &RenderOptions,
+) -> Result<FileContext> {
+    let mut context = FileContext::with_map_value_types(file, config, map_values)?;
+    context.set_import_output_paths(output_paths, config);
+    let name = util::str_or_unknown(&file.name);
+    context.set_source_file_hash(
+        options
+            .source_file_hashes
+            .get(name)
+            .cloned()
+            .unwrap_or_default(),
+    );
+    Ok(context)
+}
+
+fn load_overlays(base: &mut OverlayConfig, paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        info!("Loading overlay config from: {}", path.display_normalized());
+        let overlay = deserialize_yaml_file(path).context("OverlayConfig")?;
+        base.merge(overlay);
+    }
+    Ok(())
+}
+
+/// Path of the file rendered for `package` when `one_file_per_package` is true.
+fn package_to_file_path(config: &RendererConfig, root: &Path, package: &str) -> PathBuf {
+    let template = match &config.package_file_path_template {
+        None => {
+            return root
+                .join(package.replace(proto::PACKAGE_SEPARATOR, "_"))
+                .with_extension(&config.file_extension)
+        }
+        Some(template) => template,
+    };
+    let package_path = package.replace(proto::PACKAGE_SEPARATOR, "/");
+    let relative = template
+        .replace("{package}", package)
+        .replace("{package_path}", &package_path)
+        .replace("{ext}", &config.file_extension);
+    // Split so that empty segments, e.g. from an empty package, don't make the path absolute.
+    root.join(
+        relative
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<PathBuf>(),
+    )
+}
+
+/// Writes the `emit_sidecar` files into `output_path`, replacing any existing file. Errors before
+/// writing anything if a name is absolute or contains `..`, which would escape `output_path`.
+fn write_sidecars(config: &RendererConfig, output_path: &Path) -> Result<()> {
+    for name in config.emit_sidecar.keys() {
+        let is_relative = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_relative {
+            bail!(
+                "emit_sidecar file name '{}' must be relative to the output directory and must not contain '..'",
+                name
+            );
         }
     }
+    for (name, contents) in &config.emit_sidecar {
+        let path = output_path.join(name);
+        info!("Writing sidecar file: '{}'", path.display_normalized());
+        io::Write::write_all(&mut util::create_file_or_error(&path)?, contents.as_bytes())?;
+    }
+    Ok(())
 }
 
 fn deserialize_yaml_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    deserialize_config_file(path, None)
+}
+
+/// Parses `path` as `config_format`, or as YAML if None. The file extension is never considered.
+fn deserialize_config_file<T: DeserializeOwned>(
+    path: &Path,
+    config_format: Option<ConfigFormat>,
+) -> Result<T> {
     let file = fs::File::open(path).context("Failed to read file.")?;
     let buf_reader = io::BufReader::new(file);
-    serde_yaml::from_reader(buf_reader).with_context(|| error_deserialize_config("yaml", &path))
+    match config_format.unwrap_or(ConfigFormat::Yaml) {
+        ConfigFormat::Yaml => serde_yaml::from_reader(buf_reader)
+            .with_context(|| error_deserialize_config("yaml", &path)),
+        ConfigFormat::Json => serde_json::from_reader(buf_reader)
+            .with_context(|| error_deserialize_config("json", &path)),
+    }
+}
+
+/// Expands environment variables in the path-bearing fields of `config`, see
+/// `util::expand_env_vars`.
+fn expand_config_env_vars(config: &mut RendererConfig) -> Result<()> {
+    for value in [
+        &mut config.single_file,
+        &mut config.no_package_dir,
+        &mut config.strip_proto_path_prefix,
+        &mut config.output_include_base,
+        &mut config.package_file_path_template,
+    ] {
+        if let Some(value) = value {
+            *value = util::expand_env_vars(value)?;
+        }
+    }
+    for path in [
+        &mut config.metadata_output_dir,
+        &mut config.generated_header_file,
+    ] {
+        if let Some(path) = path {
+            *path = PathBuf::from(util::expand_env_vars(&path.to_string_lossy())?);
+        }
+    }
+    Ok(())
+}
+
+/// Replaces `generated_header` with the lines of `generated_header_file`, if one is configured.
+/// Relative paths are resolved from `template_root`.
+fn load_generated_header_file(config: &mut RendererConfig, template_root: &Path) -> Result<()> {
+    let path = match &config.generated_header_file {
+        None => return Ok(()),
+        Some(path) => template_root.join(path),
+    };
+    let contents = fs::read_to_string(&path).with_context(|| {
+        format!(
+            "Failed to read generated_header_file: {}",
+            path.display_normalized()
+        )
+    })?;
+    // The header is written with a newline after each line, so the trailing newline is implied.
+    config.generated_header = Some(contents.lines().map(str::to_owned).collect());
+    Ok(())
 }
 
 pub fn find_existing_config_path(input_root: &Path) -> Result<PathBuf> {
     for config_file_name in CONFIG_FILE_NAMES {
         let path = input_root.join(config_file_name);
-        if path.exists() {
+        if path.is_file() {
             return Ok(path);
         }
     }
@@ -254,15 +889,29 @@ pub fn find_existing_config_path(input_root: &Path) -> Result<PathBuf> {
     ))
 }
 
+/// Directories and (path, description) pairs of the files rendered with `config`, skipping ignored
+/// files like `render_files` does.
 fn collect_dirs_and_files(
     descriptor_set: &FileDescriptorSet,
-) -> Result<(HashSet<PathBuf>, Vec<PathBuf>)> {
+    config: &RendererConfig,
+    options: &RenderOptions,
+) -> Result<(HashSet<PathBuf>, Vec<(PathBuf, String)>)> {
     let mut dirs = HashSet::new();
     let mut files = Vec::new();
-    for file in &descriptor_set.file {
-        let relative_path = file_relative_path(file)?;
+    for file in descriptor_set
+        .file
+        .iter()
+        .filter(|file| !is_ignored_file(config, file, options))
+    {
+        let relative_path = file_relative_path(file, config)?;
         insert_all_parents(&mut dirs, &relative_path)?;
-        files.push(relative_path);
+        if let Some(suffix) = &config.enums_output_suffix {
+            if !file.enum_type.is_empty() {
+                let enums_path = enums_file_path(&relative_path, suffix);
+                files.push((enums_path, context::file_description(file)));
+            }
+        }
+        files.push((relative_path, context::file_description(file)));
     }
     Ok((dirs, files))
 }
@@ -276,20 +925,71 @@ fn insert_all_parents(dirs: &mut HashSet<PathBuf>, path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn file_name(file: &FileDescriptorProto, new_ext: &str) -> Result<String> {
-    Ok(util::replace_proto_ext(
-        util::str_or_error(&file.name, || {
-            "Descriptor set file is missing a file name. The descriptor set was probably generated incorrectly.".to_owned()
-        })?,
-        new_ext,
+/// Output file name of `file` relative to the output directory, without `strip_proto_path_prefix`,
+/// named by the `output_file_name` option if it has one, and moved under `no_package_dir` when the
+/// file has no package.
+fn file_name(file: &FileDescriptorProto, config: &RendererConfig) -> Result<String> {
+    let mut file_name = util::replace_proto_ext(
+        strip_proto_path_prefix(
+            util::str_or_error(&file.name, || {
+                "Descriptor set file is missing a file name. The descriptor set was probably generated incorrectly.".to_owned()
+            })?,
+            config,
+        ),
+        &config.file_extension,
+    );
+    if let Some(name) = output_file_name_option(file) {
+        let name = match config.file_extension.as_str() {
+            "" => name.to_owned(),
+            ext => [name, ".", ext].concat(),
+        };
+        file_name = Path::new(&file_name)
+            .with_file_name(name)
+            .display_normalized();
+    }
+    match (&file.package, &config.no_package_dir) {
+        (None, Some(dir)) => Ok([dir, "/", &file_name].concat()),
+        _ => Ok(file_name),
+    }
+}
+
+fn output_file_name_option(file: &FileDescriptorProto) -> Option<&str> {
+    file.options
+        .as_ref()?
+        .extension_data(proto_options::OUTPUT_FILE_NAME)
+        .map(String::as_str)
+        .ok()
+}
+
+/// `path` with `suffix` inserted before its extension, e.g. `foo.enums.ext`.
+fn enums_file_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        None => format!("{}.{}", stem, suffix),
+        Some(ext) => format!("{}.{}.{}", stem, suffix, ext.to_string_lossy()),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Path of the file rendered for `file` relative to the output directory, after applying the file
+/// name case and the `output_file_name` option. Metadata lists files by this path so it matches
+/// the rendered files.
+fn file_relative_path(file: &FileDescriptorProto, config: &RendererConfig) -> Result<PathBuf> {
+    Ok(output_file_path(
+        config,
+        Path::new(&file_name(file, config)?),
     ))
 }
 
-fn file_relative_path(file: &FileDescriptorProto) -> Result<PathBuf> {
-    let path = PathBuf::from(file.name.as_ref().ok_or(anyhow!(
-        "No file name in descriptor to create relative path from."
-    ))?);
-    Ok(path)
+/// `name` without the `strip_proto_path_prefix` directory. Names outside of it are unchanged.
+fn strip_proto_path_prefix<'a>(name: &'a str, config: &RendererConfig) -> &'a str {
+    let prefix = match &config.strip_proto_path_prefix {
+        None => return name,
+        Some(prefix) => prefix.trim_end_matches('/'),
+    };
+    name.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .unwrap_or(name)
 }
 
 fn package<'a>(file: &'a FileDescriptorProto, default: &'a String) -> &'a str {
@@ -324,6 +1024,15 @@ fn log_render_metadata(file_path: &Path) {
     );
 }
 
+fn error_output_collision(path: &Path, first: &str, second: &str) -> anyhow::Error {
+    anyhow!(
+        "Both '{}' and '{}' would be rendered to the same output file '{}'. Use --allow-overwrite to ignore.",
+        first,
+        second,
+        path.display_normalized(),
+    )
+}
+
 fn error_deserialize_config(format: &str, path: &Path) -> String {
     format!(
         "Failed to deserialize RendererConfig as {}, path: {}",
@@ -336,21 +1045,25 @@ fn error_deserialize_config(format: &str, path: &Path) -> String {
 mod tests {
     use std::io;
     use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
 
-    use anyhow::Result;
+    use anyhow::{anyhow, Result};
     use prost_types::{
         DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
     };
 
     use crate::renderer::context::{FileContext, MetadataContext};
     use crate::renderer::{Renderer, RendererConfig};
+    use crate::ConfigFormat;
 
     mod load_config {
         use crate::renderer::tests::FakeRenderer;
-        use crate::renderer::{Renderer, RendererConfig};
+        use crate::renderer::{find_existing_config_path, Renderer, RendererConfig};
+        use crate::ConfigFormat;
         use anyhow::Result;
         use std::fs::File;
         use std::io::Write;
+        use std::path::PathBuf;
         use tempfile::tempdir;
 
         #[test]
@@ -364,151 +1077,976 @@ mod tests {
         }
 
         #[test]
-        fn yml() -> Result<()> {
-            run_test("config.yml", &serde_yaml::to_string(&config())?)
+        fn yml() -> Result<()> {
+            run_test("config.yml", &serde_yaml::to_string(&config())?)
+        }
+
+        #[test]
+        fn extensionless_as_yaml() -> Result<()> {
+            run_test("config", &serde_yaml::to_string(&config())?)
+        }
+
+        #[test]
+        fn extensionless_as_json() -> Result<()> {
+            run_test("config", &serde_json::to_string(&config())?)
+        }
+
+        #[test]
+        fn json_format_override() -> Result<()> {
+            run_test_as(
+                "config.yml",
+                &serde_json::to_string(&config())?,
+                Some(ConfigFormat::Json),
+            )
+        }
+
+        #[test]
+        fn json_format_rejects_yaml() -> Result<()> {
+            let result = run_test_as(
+                "config",
+                &serde_yaml::to_string(&config())?,
+                Some(ConfigFormat::Json),
+            );
+            assert!(result.is_err());
+            Ok(())
+        }
+
+        #[test]
+        fn finds_extensionless_config() -> Result<()> {
+            let test_dir = tempdir()?;
+            File::create(test_dir.path().join("config"))?;
+            let path = find_existing_config_path(test_dir.path())?;
+            assert_eq!(path, test_dir.path().join("config"));
+
+            File::create(test_dir.path().join("config.yaml"))?;
+            let path = find_existing_config_path(test_dir.path())?;
+            assert_eq!(path, test_dir.path().join("config.yaml"));
+            Ok(())
+        }
+
+        fn config() -> RendererConfig {
+            RendererConfig {
+                file_extension: "rawr".to_owned(),
+                ..Default::default()
+            }
+        }
+
+        fn run_test(file_name: &str, content: &str) -> Result<()> {
+            run_test_as(file_name, content, None)
+        }
+
+        fn run_test_as(
+            file_name: &str,
+            content: &str,
+            config_format: Option<ConfigFormat>,
+        ) -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_file_path = test_dir.path().join(file_name);
+            File::create(&config_file_path)?.write_all(content.as_bytes())?;
+
+            let config = FakeRenderer::load_config(&config_file_path, &[], config_format)?;
+            assert_eq!(config.file_extension, "rawr");
+
+            Ok(())
+        }
+
+        #[test]
+        fn expands_env_vars_in_paths() -> Result<()> {
+            std::env::set_var("PROTOX_TEST_CONFIG_DIR", "generated");
+            let test_dir = tempdir()?;
+            let config_file_path = test_dir.path().join("config.yml");
+            File::create(&config_file_path)?.write_all(
+                "file_extension: rawr\ntype_config: {}\nmetadata_output_dir: ${PROTOX_TEST_CONFIG_DIR}/meta\nsingle_file: ${PROTOX_TEST_CONFIG_UNSET:-all}.rawr\n"
+                    .as_bytes(),
+            )?;
+
+            let config = FakeRenderer::load_config(&config_file_path, &[], None)?;
+            assert_eq!(
+                config.metadata_output_dir,
+                Some(PathBuf::from("generated/meta"))
+            );
+            assert_eq!(config.single_file.as_deref(), Some("all.rawr"));
+            Ok(())
+        }
+
+        #[test]
+        fn keeps_unknown_keys() -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_file_path = test_dir.path().join("config.json");
+            File::create(&config_file_path)?.write_all(
+                r#"{"file_extension": "rawr", "type_config": {}, "my_setting": "value"}"#
+                    .as_bytes(),
+            )?;
+
+            let config = FakeRenderer::load_config(&config_file_path, &[], None)?;
+            assert_eq!(
+                config.extra.get("my_setting"),
+                Some(&serde_yaml::Value::String("value".to_owned()))
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn invalid_regex_override_errors() -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_file_path = test_dir.path().join("config.yml");
+            File::create(&config_file_path)?.write_all(
+                "file_extension: rawr\ntype_config: {}\nfield_name_regex_override: [[\"(\", \"\"]]\n"
+                    .as_bytes(),
+            )?;
+
+            let error = FakeRenderer::load_config(&config_file_path, &[], None).unwrap_err();
+            assert!(format!("{:#}", error).contains("Invalid field_name_regex_override '('"));
+            Ok(())
+        }
+    }
+
+    mod load_overlays {
+        use crate::renderer::tests::FakeRenderer;
+        use crate::renderer::{OverlayConfig, Renderer, RendererConfig};
+        use anyhow::Result;
+        use serde::Serialize;
+        use std::collections::HashMap;
+        use std::fs::File;
+        use std::io::Write;
+        use std::path::Path;
+        use tempfile::tempdir;
+
+        #[test]
+        fn loads_overlays_from_various_paths() -> Result<()> {
+            let test_dir = tempdir()?;
+            let mut base_config = RendererConfig::default();
+            let config_path = test_dir.path().join("config.yaml");
+            write_file(&config_path, &base_config)?;
+
+            let overlay_dir = tempdir()?;
+            let mut overlay_paths = Vec::new();
+            for i in 0..3 {
+                let overlay = overlay(0);
+                let path = overlay_dir.path().join(format!("{}.yml", i));
+                write_file(&path, &overlay)?;
+                overlay_paths.push(path);
+                base_config.overlays.merge(overlay);
+            }
+
+            let loaded_config = FakeRenderer::load_config(&config_path, &overlay_paths, None)?;
+            base_config.overlays.initialize();
+            assert_eq!(loaded_config.overlays, base_config.overlays);
+
+            Ok(())
+        }
+
+        #[test]
+        fn initializes_config() -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_file_path = test_dir.path().join("config.yaml");
+            write_file(&config_file_path, RendererConfig::default())?;
+            let config = FakeRenderer::load_config(&config_file_path, &[], None)?;
+            assert!(config.overlays.is_initialized());
+            Ok(())
+        }
+
+        fn write_file<T: Serialize>(path: &Path, content: T) -> Result<()> {
+            Ok(File::create(path)?.write_all(serde_json::to_string(&content)?.as_bytes())?)
+        }
+
+        fn overlay(i: u64) -> OverlayConfig {
+            OverlayConfig::uninit_by_target(HashMap::from([(
+                format!("target{}", i),
+                HashMap::from([(
+                    format!("key{}", i),
+                    serde_yaml::Value::String(format!("value{}", i)),
+                )]),
+            )]))
+        }
+    }
+
+    mod render {
+        use anyhow::Result;
+        use prost::Extendable;
+        use prost_types::source_code_info::Location;
+        use prost_types::{
+            EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet, FileOptions,
+            SourceCodeInfo,
+        };
+        use std::collections::{HashMap, HashSet};
+        use std::fs;
+        use tempfile::tempdir;
+
+        use crate::render::{Render, RenderOptions};
+        use crate::renderer::case::Case;
+        use crate::renderer::tests::{
+            fake_file, fake_file_empty, fake_file_with_package, fake_message, FakeRenderer,
+        };
+        use crate::renderer::{RendererConfig, UTF8_BOM};
+
+        #[test]
+        fn render_files() -> Result<()> {
+            let mut renderer = FakeRenderer::default();
+            renderer.has_metadata = true;
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            assert!(test_dir.path().join("file1").exists());
+            assert!(test_dir.path().join("test/file2").exists());
+            assert!(test_dir.path().join("test/file3").exists());
+            assert!(test_dir.path().join("test/sub/file4").exists());
+            assert!(test_dir.path().join("other/sub/inner/file5").exists());
+
+            assert!(test_dir.path().join("metadata").exists());
+            assert!(test_dir.path().join("test/metadata").exists());
+            assert!(test_dir.path().join("test/sub/metadata").exists());
+            assert!(test_dir.path().join("other/sub/inner/metadata").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn render_files_with_metadata_output_dir() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.metadata_output_dir = Some("index".into());
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.has_metadata = true;
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            assert!(test_dir.path().join("file1").exists());
+            assert!(test_dir.path().join("test/file2").exists());
+            assert!(test_dir.path().join("other/sub/inner/file5").exists());
+
+            assert!(test_dir.path().join("index/metadata").exists());
+            assert!(test_dir.path().join("index/test/metadata").exists());
+            assert!(test_dir.path().join("index/test/sub/metadata").exists());
+            assert!(test_dir
+                .path()
+                .join("index/other/sub/inner/metadata")
+                .exists());
+            assert!(!test_dir.path().join("metadata").exists());
+            assert!(!test_dir.path().join("test/metadata").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn render_files_collapsed() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.one_file_per_package = true;
+            config.default_package_file_name = "pkg-root".to_owned();
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.has_metadata = true;
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            assert!(test_dir.path().join("pkg-root").exists());
+            assert!(test_dir.path().join("test").exists());
+            assert!(test_dir.path().join("test-sub").exists());
+            assert!(test_dir.path().join("other-sub-inner").exists());
+
+            assert!(test_dir.path().join("metadata").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn render_files_collapsed_with_path_template() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.one_file_per_package = true;
+            config.file_extension = "ext".to_owned();
+            config.package_file_path_template = Some("{package_path}/index.{ext}".to_owned());
+            config.default_package_file_name = "pkg-root".to_owned();
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.has_metadata = true;
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            assert!(test_dir.path().join("pkg-root/index.ext").is_file());
+            assert!(test_dir.path().join("test/index.ext").is_file());
+            assert!(test_dir.path().join("test/sub/index.ext").is_file());
+            assert!(test_dir.path().join("other/sub/inner/index.ext").is_file());
+            assert!(test_dir.path().join("metadata.ext").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn append_accumulates_across_renders() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.generated_header = Some(vec!["// header".to_owned()]);
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("file0")],
+            };
+            let options = RenderOptions {
+                append: true,
+                ..Default::default()
+            };
+            renderer.render(&set, test_dir.path(), &options)?;
+            renderer.render(&set, test_dir.path(), &options)?;
+
+            let contents = fs::read_to_string(test_dir.path().join("file0"))?;
+            assert_eq!(contents, "// header\nfile0file0");
+            Ok(())
+        }
+
+        #[test]
+        fn overwrites_without_append() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.generated_header = Some(vec!["// header".to_owned()]);
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("file0")],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("file0"))?;
+            assert_eq!(contents, "// header\nfile0");
+            Ok(())
+        }
+
+        #[test]
+        fn render_files_collapsed_with_separator() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.one_file_per_package = true;
+            config.generated_header = Some(vec![]);
+            config.file_separator = Some("\n---\n".to_owned());
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            let test_dir = tempdir()?;
+
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_with_package("file0", "test"),
+                    fake_file_with_package("file1", "test"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("test"))?;
+            assert_eq!(contents, "file0\n---\nfile1");
+            Ok(())
+        }
+
+        #[test]
+        fn metadata_file_descriptions() -> Result<()> {
+            let mut renderer = described_renderer(false);
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    described_file("file0", "test", "Described."),
+                    fake_file_empty("file1"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("metadata"))?;
+            assert_eq!(contents, "file0: Described.\nfile1: \n");
+            Ok(())
+        }
+
+        #[test]
+        fn metadata_package_descriptions() -> Result<()> {
+            let mut renderer = described_renderer(true);
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_with_package("file0", "test"),
+                    described_file("file1", "test", "Described."),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("metadata"))?;
+            assert_eq!(contents, "test: Described.\n");
+            Ok(())
+        }
+
+        #[test]
+        fn since_renders_only_changed_files() -> Result<()> {
+            let mut renderer = described_renderer(false);
+            renderer.render_descriptions = false;
+            renderer.render_source_file = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("file0"), fake_file_empty("file1")],
+            };
+            let options = RenderOptions {
+                changed_files: Some(HashSet::from(["file1".to_owned()])),
+                ..Default::default()
+            };
+            renderer.render(&set, test_dir.path(), &options)?;
+
+            assert!(!test_dir.path().join("file0").exists());
+            assert_eq!(fs::read_to_string(test_dir.path().join("file1"))?, "file1");
+            assert!(test_dir.path().join("metadata").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn since_renders_package_files_from_every_file() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.one_file_per_package = true;
+            config.generated_header = Some(vec![]);
+            config.file_separator = None;
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_with_package("file0", "test"),
+                    fake_file_with_package("file1", "test"),
+                ],
+            };
+            let options = RenderOptions {
+                changed_files: Some(HashSet::from(["file1".to_owned()])),
+                ..Default::default()
+            };
+            renderer.render(&set, test_dir.path(), &options)?;
+
+            let contents = fs::read_to_string(test_dir.path().join("test"))?;
+            assert_eq!(contents, "file0file1");
+            Ok(())
+        }
+
+        #[test]
+        fn metadata_common_package_prefix() -> Result<()> {
+            let mut renderer = described_renderer(true);
+            renderer.render_descriptions = false;
+            renderer.render_common_package_prefix = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_with_package("file0", "a.b.c"),
+                    fake_file_with_package("file1", "a.b.d"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("metadata"))?;
+            assert_eq!(contents, "common_package_prefix: a.b\n");
+            Ok(())
+        }
+
+        #[test]
+        fn metadata_native_files() -> Result<()> {
+            let mut renderer = described_renderer(false);
+            renderer.render_descriptions = false;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("file0"), fake_file_empty("sub/file1")],
+            };
+            let options = RenderOptions {
+                native_files: vec!["Native.cs".into(), "sub/Other.cs".into()],
+                ..Default::default()
+            };
+            renderer.render(&set, test_dir.path(), &options)?;
+
+            let contents = fs::read_to_string(test_dir.path().join("metadata"))?;
+            assert_eq!(contents, "native: Native.cs\n");
+            let contents = fs::read_to_string(test_dir.path().join("sub").join("metadata"))?;
+            assert_eq!(contents, "native: Other.cs\n");
+            Ok(())
+        }
+
+        fn described_renderer(one_file_per_package: bool) -> FakeRenderer {
+            let mut config = RendererConfig::default();
+            config.generated_header = Some(vec![]);
+            config.one_file_per_package = one_file_per_package;
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.has_metadata = true;
+            renderer.render_descriptions = true;
+            renderer
+        }
+
+        fn described_file(name: &str, package: &str, description: &str) -> FileDescriptorProto {
+            let mut file = fake_file_with_package(name, package);
+            file.source_code_info = Some(SourceCodeInfo {
+                location: vec![Location {
+                    path: vec![12],
+                    leading_comments: Some(format!(" {}\n", description)),
+                    ..Default::default()
+                }],
+            });
+            file
+        }
+
+        #[test]
+        fn emits_bom_when_enabled() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.emit_bom = true;
+            let renderer = FakeRenderer::with_config(config);
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            let bytes = fs::read(test_dir.path().join("file1"))?;
+            assert_eq!(&bytes[..3], UTF8_BOM);
+            Ok(())
+        }
+
+        #[test]
+        fn no_bom_by_default() -> Result<()> {
+            let renderer = FakeRenderer::default();
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            let bytes = fs::read(test_dir.path().join("file1"))?;
+            assert_ne!(&bytes[..3], UTF8_BOM);
+            Ok(())
+        }
+
+        #[test]
+        fn reports_progress_for_non_ignored_files() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.ignored_files = vec!["file1".to_owned()];
+            let renderer = FakeRenderer::with_config(config);
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_empty("file0"),
+                    fake_file_empty("file1"),
+                    fake_file_empty("file2"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &progress())?;
+            assert_eq!(*renderer.progress.lock().unwrap(), vec![(1, 2), (2, 2)]);
+            Ok(())
+        }
+
+        #[test]
+        fn reports_progress_collapsed() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.one_file_per_package = true;
+            config.ignored_files = vec!["file1".to_owned()];
+            let renderer = FakeRenderer::with_config(config);
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_with_package("file0", "a"),
+                    fake_file_with_package("file1", "a"),
+                    fake_file_with_package("file2", "b"),
+                    fake_file_with_package("file3", "b"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &progress())?;
+            assert_eq!(
+                *renderer.progress.lock().unwrap(),
+                vec![(1, 3), (2, 3), (3, 3)]
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn no_progress_by_default() -> Result<()> {
+            let renderer = FakeRenderer::default();
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+            assert!(renderer.progress.lock().unwrap().is_empty());
+            Ok(())
+        }
+
+        #[test]
+        fn renders_source_file_hash() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.generated_header = Some(vec![]);
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file_hash = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("file0"), fake_file_empty("file1")],
+            };
+            let options = RenderOptions {
+                source_file_hashes: HashMap::from([("file0".to_owned(), "abc123".to_owned())]),
+                ..Default::default()
+            };
+            renderer.render(&set, test_dir.path(), &options)?;
+
+            assert_eq!(fs::read_to_string(test_dir.path().join("file0"))?, "abc123");
+            assert_eq!(fs::read_to_string(test_dir.path().join("file1"))?, "");
+            Ok(())
+        }
+
+        #[test]
+        fn one_job_renders_same_files_as_many() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.generated_header = Some(vec![]);
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            let set = FileDescriptorSet {
+                file: (0..8)
+                    .map(|i| fake_file_empty(format!("file{}", i)))
+                    .collect(),
+            };
+            let sequential = tempdir()?;
+            let parallel = tempdir()?;
+            renderer.render(&set, sequential.path(), &jobs(1))?;
+            renderer.render(&set, parallel.path(), &jobs(4))?;
+
+            for i in 0..8 {
+                let name = format!("file{}", i);
+                assert_eq!(fs::read_to_string(sequential.path().join(&name))?, name);
+                assert_eq!(fs::read_to_string(parallel.path().join(&name))?, name);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn reports_first_error_in_file_order() -> Result<()> {
+            let mut renderer = FakeRenderer::default();
+            renderer.fail_source_files = vec!["file2".to_owned(), "file6".to_owned()];
+            let set = FileDescriptorSet {
+                file: (0..8)
+                    .map(|i| fake_file_empty(format!("file{}", i)))
+                    .collect(),
+            };
+            for job_count in [1, 4] {
+                let test_dir = tempdir()?;
+                let error = renderer
+                    .render(&set, test_dir.path(), &jobs(job_count))
+                    .unwrap_err();
+                assert_eq!(error.to_string(), "failed to render file2");
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn files_sharing_output_path_render_in_order() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.case_config.file_name = Case::UpperSnake;
+            config.generated_header = Some(vec![]);
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            let mut files = (0..8)
+                .map(|i| fake_file_empty(format!("file{}", i)))
+                .collect::<Vec<_>>();
+            files.push(fake_file_empty("fileName"));
+            files.push(fake_file_empty("file_name"));
+            let set = FileDescriptorSet { file: files };
+            let test_dir = tempdir()?;
+            // --allow-overwrite skips check_output_paths, so the later file overwrites the earlier.
+            renderer.render(&set, test_dir.path(), &jobs(4))?;
+
+            assert_eq!(
+                fs::read_to_string(test_dir.path().join("FILE_NAME"))?,
+                "file_name"
+            );
+            Ok(())
+        }
+
+        fn jobs(jobs: usize) -> RenderOptions {
+            RenderOptions {
+                jobs,
+                ..Default::default()
+            }
+        }
+
+        fn progress() -> RenderOptions {
+            RenderOptions {
+                progress: true,
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn errors_on_colliding_output_paths() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.case_config.file_name = Case::UpperSnake;
+            let renderer = FakeRenderer::with_config(config);
+            let test_dir = tempdir()?;
+
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("fileName"), fake_file_empty("file_name")],
+            };
+            let result =
+                renderer.check_output_paths(&set, test_dir.path(), &RenderOptions::default());
+            assert!(result.is_err());
+            Ok(())
+        }
+
+        #[test]
+        fn no_error_on_distinct_output_paths() -> Result<()> {
+            let renderer = FakeRenderer::default();
+            let test_dir = tempdir()?;
+            renderer.check_output_paths(
+                &test_file_set(),
+                test_dir.path(),
+                &RenderOptions::default(),
+            )?;
+            Ok(())
+        }
+
+        #[test]
+        fn renders_file_with_configured_case() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.case_config.file_name = Case::UpperSnake;
+            let renderer = FakeRenderer::with_config(config);
+            let test_dir = tempdir()?;
+
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("fileName")],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            assert!(test_dir.path().join("FILE_NAME").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn render_files_single() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.single_file = Some("out/bundle".to_owned());
+            config.generated_header = Some(vec![]);
+            config.file_separator = Some("|".to_owned());
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_source_file = true;
+            renderer.has_metadata = true;
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("out/bundle"))?;
+            assert_eq!(
+                contents,
+                "file1|test/file2|test/file3|test/sub/file4|other/sub/inner/file5"
+            );
+            assert!(!test_dir.path().join("file1").exists());
+            assert!(!test_dir.path().join("test").exists());
+            assert!(test_dir.path().join("out/metadata").exists());
+            assert!(!test_dir.path().join("metadata").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn renders_enums_into_suffixed_file() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.file_extension = "ext".to_owned();
+            config.enums_output_suffix = Some("enums".to_owned());
+            config.generated_header = Some(vec![]);
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_type_names = true;
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file(
+                        "test/foo.proto",
+                        vec![EnumDescriptorProto {
+                            name: Some("Kind".to_owned()),
+                            ..Default::default()
+                        }],
+                        vec![fake_message("Thing", vec![])],
+                    ),
+                    fake_file(
+                        "test/bar.proto",
+                        vec![],
+                        vec![fake_message("Other", vec![])],
+                    ),
+                ],
+            };
+            renderer.check_output_paths(&set, test_dir.path(), &RenderOptions::default())?;
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let dir = test_dir.path().join("test");
+            assert_eq!(fs::read_to_string(dir.join("foo.ext"))?, "message Thing;");
+            assert_eq!(fs::read_to_string(dir.join("foo.enums.ext"))?, "enum Kind;");
+            assert_eq!(fs::read_to_string(dir.join("bar.ext"))?, "message Other;");
+            assert!(!dir.join("bar.enums.ext").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn metadata_lists_enums_file() -> Result<()> {
+            let mut renderer = described_renderer(false);
+            renderer.config.file_extension = "ext".to_owned();
+            renderer.config.enums_output_suffix = Some("enums".to_owned());
+            let test_dir = tempdir()?;
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file(
+                        "foo.proto",
+                        vec![EnumDescriptorProto {
+                            name: Some("Kind".to_owned()),
+                            ..Default::default()
+                        }],
+                        vec![],
+                    ),
+                    fake_file_empty("bar.proto"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
+
+            let contents = fs::read_to_string(test_dir.path().join("metadata.ext"))?;
+            assert_eq!(contents, "foo.enums: \nfoo: \nbar: \n");
+            Ok(())
+        }
+
+        #[test]
+        fn does_not_render_well_known_imports_by_default() -> Result<()> {
+            let renderer = FakeRenderer::default();
+            let test_dir = tempdir()?;
+            renderer.render(
+                &imports_file_set(),
+                test_dir.path(),
+                &RenderOptions::default(),
+            )?;
+
+            assert!(!test_dir.path().join("google/protobuf/timestamp").exists());
+            assert!(test_dir.path().join("user/import").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn renders_well_known_imports_when_enabled() -> Result<()> {
+            let renderer = FakeRenderer::default();
+            let test_dir = tempdir()?;
+            let options = RenderOptions {
+                render_imports: true,
+                ..Default::default()
+            };
+            renderer.render(&imports_file_set(), test_dir.path(), &options)?;
+
+            assert!(test_dir.path().join("google/protobuf/timestamp").exists());
+            assert!(test_dir.path().join("user/import").exists());
+            Ok(())
         }
 
-        fn config() -> RendererConfig {
-            RendererConfig {
-                file_extension: "rawr".to_owned(),
-                ..Default::default()
+        fn imports_file_set() -> FileDescriptorSet {
+            FileDescriptorSet {
+                file: vec![
+                    fake_file_with_package("google/protobuf/timestamp.proto", "google.protobuf"),
+                    fake_file_with_package("user/import.proto", "user"),
+                ],
             }
         }
 
-        fn run_test(file_name: &str, content: &str) -> Result<()> {
+        #[test]
+        fn renders_package_less_file_into_no_package_dir() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.no_package_dir = Some("no_package".to_owned());
+            let renderer = FakeRenderer::with_config(config);
             let test_dir = tempdir()?;
-            let config_file_path = test_dir.path().join(file_name);
-            File::create(&config_file_path)?.write_all(content.as_bytes())?;
 
-            let config = FakeRenderer::load_config(&config_file_path, &[])?;
-            assert_eq!(config.file_extension, "rawr");
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_empty("root"),
+                    fake_file_with_package("test/file", "test"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
+            assert!(test_dir.path().join("no_package/root").exists());
+            assert!(!test_dir.path().join("root").exists());
+            assert!(test_dir.path().join("test/file").exists());
             Ok(())
         }
-    }
-
-    mod load_overlays {
-        use crate::renderer::tests::FakeRenderer;
-        use crate::renderer::{OverlayConfig, Renderer, RendererConfig};
-        use anyhow::Result;
-        use serde::Serialize;
-        use std::collections::HashMap;
-        use std::fs::File;
-        use std::io::Write;
-        use std::path::Path;
-        use tempfile::tempdir;
 
         #[test]
-        fn loads_overlays_from_various_paths() -> Result<()> {
+        fn metadata_lists_files_in_no_package_dir() -> Result<()> {
+            let mut renderer = described_renderer(false);
+            renderer.config.no_package_dir = Some("no_package".to_owned());
             let test_dir = tempdir()?;
-            let mut base_config = RendererConfig::default();
-            let config_path = test_dir.path().join("config.yaml");
-            write_file(&config_path, &base_config)?;
-
-            let overlay_dir = tempdir()?;
-            let mut overlay_paths = Vec::new();
-            for i in 0..3 {
-                let overlay = overlay(0);
-                let path = overlay_dir.path().join(format!("{}.yml", i));
-                write_file(&path, &overlay)?;
-                overlay_paths.push(path);
-                base_config.overlays.merge(overlay);
-            }
 
-            let loaded_config = FakeRenderer::load_config(&config_path, &overlay_paths)?;
-            base_config.overlays.initialize();
-            assert_eq!(loaded_config.overlays, base_config.overlays);
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_empty("root"),
+                    fake_file_with_package("test/file", "test"),
+                ],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
+            let contents = fs::read_to_string(test_dir.path().join("no_package/metadata"))?;
+            assert_eq!(contents, "root: \n");
+            assert_eq!(fs::read_to_string(test_dir.path().join("metadata"))?, "");
             Ok(())
         }
 
         #[test]
-        fn initializes_config() -> Result<()> {
+        fn renders_to_output_file_name_option() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.file_extension = "ext".to_owned();
+            let renderer = FakeRenderer::with_config(config);
             let test_dir = tempdir()?;
-            let config_file_path = test_dir.path().join("config.yaml");
-            write_file(&config_file_path, RendererConfig::default())?;
-            let config = FakeRenderer::load_config(&config_file_path, &[])?;
-            assert!(config.overlays.is_initialized());
-            Ok(())
-        }
 
-        fn write_file<T: Serialize>(path: &Path, content: T) -> Result<()> {
-            Ok(File::create(path)?.write_all(serde_json::to_string(&content)?.as_bytes())?)
-        }
+            let mut options = FileOptions::default();
+            options.set_extension_data(&proto_options::OUTPUT_FILE_NAME, "renamed".to_owned())?;
+            let mut file = fake_file_empty("sub/original.proto");
+            file.options = Some(options);
+            let set = FileDescriptorSet {
+                file: vec![file, fake_file_empty("sub/other.proto")],
+            };
+            renderer.check_output_paths(&set, test_dir.path(), &RenderOptions::default())?;
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
-        fn overlay(i: u64) -> OverlayConfig {
-            OverlayConfig::uninit_by_target(HashMap::from([(
-                format!("target{}", i),
-                HashMap::from([(
-                    format!("key{}", i),
-                    serde_yaml::Value::String(format!("value{}", i)),
-                )]),
-            )]))
+            assert!(test_dir.path().join("sub/renamed.ext").exists());
+            assert!(!test_dir.path().join("sub/original.ext").exists());
+            assert!(test_dir.path().join("sub/other.ext").exists());
+            Ok(())
         }
-    }
-
-    mod render {
-        use anyhow::Result;
-        use prost_types::FileDescriptorSet;
-        use tempfile::tempdir;
-
-        use crate::render::Render;
-        use crate::renderer::case::Case;
-        use crate::renderer::tests::{fake_file_empty, fake_file_with_package, FakeRenderer};
-        use crate::renderer::RendererConfig;
 
         #[test]
-        fn render_files() -> Result<()> {
-            let mut renderer = FakeRenderer::default();
-            renderer.has_metadata = true;
+        fn import_output_path_is_rendered_path() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.generated_header = Some(vec![]);
+            config.file_extension = "txt".to_owned();
+            config.case_config.file_name = Case::LowerKebab;
+            let mut renderer = FakeRenderer::with_config(config);
+            renderer.render_import_paths = true;
             let test_dir = tempdir()?;
-            renderer.render(&test_file_set(), test_dir.path())?;
 
-            assert!(test_dir.path().join("file1").exists());
-            assert!(test_dir.path().join("test/file2").exists());
-            assert!(test_dir.path().join("test/file3").exists());
-            assert!(test_dir.path().join("test/sub/file4").exists());
-            assert!(test_dir.path().join("other/sub/inner/file5").exists());
+            let mut main = fake_file_empty("main.proto");
+            main.dependency = vec!["sub/SomeFile.proto".to_owned()];
+            let set = FileDescriptorSet {
+                file: vec![fake_file_empty("sub/SomeFile.proto"), main],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
-            assert!(test_dir.path().join("metadata").exists());
-            assert!(test_dir.path().join("test/metadata").exists());
-            assert!(test_dir.path().join("test/sub/metadata").exists());
-            assert!(test_dir.path().join("other/sub/inner/metadata").exists());
+            assert!(test_dir.path().join("sub/some-file.txt").exists());
+            assert_eq!(
+                fs::read_to_string(test_dir.path().join("main.txt"))?,
+                "import sub/some-file.txt;"
+            );
             Ok(())
         }
 
         #[test]
-        fn render_files_collapsed() -> Result<()> {
-            let mut config = RendererConfig::default();
-            config.one_file_per_package = true;
-            config.default_package_file_name = "pkg-root".to_owned();
-            let mut renderer = FakeRenderer::with_config(config);
-            renderer.has_metadata = true;
+        fn metadata_lists_output_file_name_option() -> Result<()> {
+            let mut renderer = described_renderer(false);
+            renderer.config.file_extension = "ext".to_owned();
             let test_dir = tempdir()?;
-            renderer.render(&test_file_set(), test_dir.path())?;
 
-            assert!(test_dir.path().join("pkg-root").exists());
-            assert!(test_dir.path().join("test").exists());
-            assert!(test_dir.path().join("test-sub").exists());
-            assert!(test_dir.path().join("other-sub-inner").exists());
+            let mut options = FileOptions::default();
+            options.set_extension_data(&proto_options::OUTPUT_FILE_NAME, "renamed".to_owned())?;
+            let mut file = fake_file_empty("sub/original.proto");
+            file.options = Some(options);
+            let set = FileDescriptorSet {
+                file: vec![file, fake_file_empty("sub/other.proto")],
+            };
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
-            assert!(test_dir.path().join("metadata").exists());
+            let contents = fs::read_to_string(test_dir.path().join("sub/metadata.ext"))?;
+            assert_eq!(contents, "renamed: \nother: \n");
             Ok(())
         }
 
         #[test]
-        fn renders_file_with_configured_case() -> Result<()> {
+        fn strips_proto_path_prefix_from_output_paths() -> Result<()> {
             let mut config = RendererConfig::default();
-            config.case_config.file_name = Case::UpperSnake;
+            config.strip_proto_path_prefix = Some("proto/src/".to_owned());
             let renderer = FakeRenderer::with_config(config);
             let test_dir = tempdir()?;
 
             let set = FileDescriptorSet {
-                file: vec![fake_file_empty("fileName")],
+                file: vec![
+                    fake_file_empty("proto/src/foo"),
+                    fake_file_empty("proto/src/sub/bar"),
+                    fake_file_empty("proto/srcbaz"),
+                    fake_file_empty("other/qux"),
+                ],
             };
-            renderer.render(&set, test_dir.path())?;
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
-            assert!(test_dir.path().join("FILE_NAME").exists());
+            assert!(test_dir.path().join("foo").exists());
+            assert!(test_dir.path().join("sub/bar").exists());
+            assert!(test_dir.path().join("proto/srcbaz").exists());
+            assert!(test_dir.path().join("other/qux").exists());
+            assert!(!test_dir.path().join("proto/src").exists());
             Ok(())
         }
 
@@ -524,7 +2062,7 @@ mod tests {
             let set = FileDescriptorSet {
                 file: vec![fake_file_empty("fileName")],
             };
-            renderer.render(&set, test_dir.path())?;
+            renderer.render(&set, test_dir.path(), &RenderOptions::default())?;
 
             assert!(test_dir.path().join("PKG_ROOT").exists());
             Ok(())
@@ -538,7 +2076,7 @@ mod tests {
             };
             let renderer = FakeRenderer::with_config(config);
             let test_dir = tempdir()?;
-            renderer.render(&test_file_set(), test_dir.path())?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
 
             assert!(!test_dir.path().join("file1").exists());
             assert!(test_dir.path().join("test/file2").exists());
@@ -558,7 +2096,7 @@ mod tests {
             };
             let renderer = FakeRenderer::with_config(config);
             let test_dir = tempdir()?;
-            renderer.render(&test_file_set(), test_dir.path())?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
 
             assert!(
                 !test_dir.path().join("pkg-root").exists(),
@@ -573,6 +2111,43 @@ mod tests {
             Ok(())
         }
 
+        #[test]
+        fn writes_sidecar_files() -> Result<()> {
+            let mut config = RendererConfig::default();
+            config.emit_sidecar.insert(
+                ".editorconfig".to_owned(),
+                "[*]\nindent_size = 4\n".to_owned(),
+            );
+            let renderer = FakeRenderer::with_config(config);
+            let test_dir = tempdir()?;
+            renderer.render(&test_file_set(), test_dir.path(), &RenderOptions::default())?;
+
+            assert_eq!(
+                fs::read_to_string(test_dir.path().join(".editorconfig"))?,
+                "[*]\nindent_size = 4\n"
+            );
+            assert!(!test_dir.path().join("test/.editorconfig").exists());
+            Ok(())
+        }
+
+        #[test]
+        fn sidecar_outside_output_errors() -> Result<()> {
+            let test_dir = tempdir()?;
+            let output_path = test_dir.path().join("out");
+            for name in ["../escaped", "sub/../../escaped", "/escaped"] {
+                let mut config = RendererConfig::default();
+                config
+                    .emit_sidecar
+                    .insert(name.to_owned(), "contents".to_owned());
+                let renderer = FakeRenderer::with_config(config);
+                assert!(renderer
+                    .render(&test_file_set(), &output_path, &RenderOptions::default())
+                    .is_err());
+            }
+            assert!(!test_dir.path().join("escaped").exists());
+            Ok(())
+        }
+
         fn test_file_set() -> FileDescriptorSet {
             FileDescriptorSet {
                 file: vec![
@@ -600,8 +2175,10 @@ mod tests {
         use anyhow::Result;
         use prost_types::FileDescriptorSet;
 
-        use crate::renderer::collect_dirs_and_files;
-        use crate::renderer::tests::fake_file_empty;
+        use crate::render::RenderOptions;
+        use crate::renderer::case::Case;
+        use crate::renderer::tests::{fake_file_empty, fake_file_with_package};
+        use crate::renderer::{collect_dirs_and_files, RendererConfig};
 
         #[test]
         fn files() -> Result<()> {
@@ -613,7 +2190,15 @@ mod tests {
                     fake_file_empty("other/sub/inner/file4"),
                 ],
             };
-            let (_, files) = collect_dirs_and_files(&set)?;
+            let (_, files) = collect_dirs_and_files(
+                &set,
+                &RendererConfig::default(),
+                &RenderOptions::default(),
+            )?;
+            let files = files
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<PathBuf>>();
             assert!(files.contains(&PathBuf::from("file1")));
             assert!(files.contains(&PathBuf::from("test/file2")));
             assert!(files.contains(&PathBuf::from("test/sub/file3")));
@@ -630,7 +2215,11 @@ mod tests {
                     fake_file_empty("test/sub/file3"),
                 ],
             };
-            let (dirs, _) = collect_dirs_and_files(&set)?;
+            let (dirs, _) = collect_dirs_and_files(
+                &set,
+                &RendererConfig::default(),
+                &RenderOptions::default(),
+            )?;
             assert!(dirs.contains(&PathBuf::new()));
             assert!(dirs.contains(&PathBuf::from("test")));
             assert!(dirs.contains(&PathBuf::from("test/sub")));
@@ -642,7 +2231,11 @@ mod tests {
             let set = FileDescriptorSet {
                 file: vec![fake_file_empty("test/sub/inner/file4")],
             };
-            let (dirs, _) = collect_dirs_and_files(&set)?;
+            let (dirs, _) = collect_dirs_and_files(
+                &set,
+                &RendererConfig::default(),
+                &RenderOptions::default(),
+            )?;
             assert!(dirs.contains(&PathBuf::new()));
             assert!(dirs.contains(&PathBuf::from("test")));
             assert!(dirs.contains(&PathBuf::from("test/sub")));
@@ -659,10 +2252,79 @@ mod tests {
                     fake_file_empty("test/file3"),
                 ],
             };
-            let (dirs, _) = collect_dirs_and_files(&set)?;
+            let (dirs, _) = collect_dirs_and_files(
+                &set,
+                &RendererConfig::default(),
+                &RenderOptions::default(),
+            )?;
+            assert_eq!(dirs.len(), 2);
+            assert!(dirs.contains(&PathBuf::new()));
+            assert!(dirs.contains(&PathBuf::from("test")));
+            Ok(())
+        }
+
+        #[test]
+        fn strips_proto_path_prefix() -> Result<()> {
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_empty("proto/src/file1"),
+                    fake_file_empty("proto/src/test/file2"),
+                ],
+            };
+            let mut config = RendererConfig::default();
+            config.strip_proto_path_prefix = Some("proto/src".to_owned());
+            let (dirs, files) = collect_dirs_and_files(&set, &config, &RenderOptions::default())?;
             assert_eq!(dirs.len(), 2);
             assert!(dirs.contains(&PathBuf::new()));
             assert!(dirs.contains(&PathBuf::from("test")));
+            assert_eq!(files[0].0, PathBuf::from("file1"));
+            assert_eq!(files[1].0, PathBuf::from("test/file2"));
+            Ok(())
+        }
+
+        #[test]
+        fn uses_rendered_file_paths() -> Result<()> {
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_empty("SomeFile.proto"),
+                    fake_file_with_package("test/OtherFile.proto", "test"),
+                ],
+            };
+            let mut config = RendererConfig::default();
+            config.file_extension = "ext".to_owned();
+            config.case_config.file_name = Case::LowerSnake;
+            config.no_package_dir = Some("no_package".to_owned());
+            let (dirs, files) = collect_dirs_and_files(&set, &config, &RenderOptions::default())?;
+            assert!(dirs.contains(&PathBuf::from("no_package")));
+            assert_eq!(files[0].0, PathBuf::from("no_package/some_file.ext"));
+            assert_eq!(files[1].0, PathBuf::from("test/other_file.ext"));
+            Ok(())
+        }
+
+        #[test]
+        fn skips_ignored_files() -> Result<()> {
+            let set = FileDescriptorSet {
+                file: vec![
+                    fake_file_empty("file1"),
+                    fake_file_empty("google/protobuf/any"),
+                ],
+            };
+            let (dirs, files) = collect_dirs_and_files(
+                &set,
+                &RendererConfig::default(),
+                &RenderOptions::default(),
+            )?;
+            assert_eq!(dirs.len(), 1);
+            assert!(dirs.contains(&PathBuf::new()));
+            assert_eq!(files.len(), 1);
+            assert_eq!(files[0].0, PathBuf::from("file1"));
+
+            let options = RenderOptions {
+                render_imports: true,
+                ..Default::default()
+            };
+            let (_, files) = collect_dirs_and_files(&set, &RendererConfig::default(), &options)?;
+            assert_eq!(files.len(), 2);
             Ok(())
         }
     }
@@ -742,6 +2404,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn package_to_file_path_from_template() {
+        let mut config = RendererConfig::default();
+        config.file_extension = "test".to_owned();
+        config.package_file_path_template = Some("{package_path}/index.{ext}".to_owned());
+        let renderer = FakeRenderer::with_config(config);
+        assert_eq!(
+            renderer.package_to_file_path(&PathBuf::from("root"), "some.package"),
+            PathBuf::from("root/some/package/index.test"),
+        );
+        let mut config = renderer.config.clone();
+        config.package_file_path_template = Some("{package}/mod.{ext}".to_owned());
+        let renderer = FakeRenderer::with_config(config);
+        assert_eq!(
+            renderer.package_to_file_path(&PathBuf::from("root"), "some.package"),
+            PathBuf::from("root/some.package/mod.test"),
+        );
+    }
+
     mod generated_header {
         use std::fs;
         use std::io::Read;
@@ -751,9 +2432,9 @@ mod tests {
         use prost_types::FileDescriptorSet;
         use tempfile::tempdir;
 
-        use crate::render::Render;
+        use crate::render::{Render, RenderOptions};
         use crate::renderer::tests::{fake_file_with_package, FakeRenderer};
-        use crate::renderer::{RendererConfig, DEFAULT_GENERATED_HEADER};
+        use crate::renderer::{Renderer, RendererConfig, DEFAULT_GENERATED_HEADER};
 
         #[test]
         fn default_in_file() -> Result<()> {
@@ -776,6 +2457,7 @@ mod tests {
             let test_dir = tempdir()?;
             let mut config = RendererConfig::default();
             config.one_file_per_package = true;
+            config.file_separator = None;
             render(test_dir.path(), config, false)?;
             assert_file_has_header(&test_dir.path().join("root"), DEFAULT_GENERATED_HEADER)?;
             assert_file_has_header(&test_dir.path().join("root-sub"), DEFAULT_GENERATED_HEADER)?;
@@ -808,6 +2490,7 @@ mod tests {
             let mut config = RendererConfig::default();
             config.generated_header = Some(CONFIGURED_HEADER_LINES.map(&str::to_owned).to_vec());
             config.one_file_per_package = true;
+            config.file_separator = None;
             render(test_dir.path(), config, false)?;
             assert_file_has_header(&test_dir.path().join("root"), CONFIGURED_HEADER)?;
             assert_file_has_header(&test_dir.path().join("root-sub"), CONFIGURED_HEADER)?;
@@ -824,6 +2507,78 @@ mod tests {
             Ok(())
         }
 
+        #[test]
+        fn configured_from_file() -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_dir = tempdir()?;
+            let header = "// Copyright\n//\n// Licensed under the terms below.\n";
+            fs::write(config_dir.path().join("header.txt"), header)?;
+            let config_path = config_dir.path().join("config.yml");
+            fs::write(
+                &config_path,
+                [
+                    "file_extension: ''",
+                    "type_config: {}",
+                    "generated_header: [inline]",
+                    "generated_header_file: header.txt",
+                ]
+                .join("\n"),
+            )?;
+            let config = FakeRenderer::load_config(&config_path, &[], None)?;
+            render(test_dir.path(), config, false)?;
+            assert_file_has_header(&test_dir.path().join("root"), header)?;
+            Ok(())
+        }
+
+        #[test]
+        fn configured_from_crlf_file() -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_dir = tempdir()?;
+            fs::write(config_dir.path().join("header.txt"), "// one\r\n// two\r\n")?;
+            let config_path = config_dir.path().join("config.yml");
+            fs::write(
+                &config_path,
+                "file_extension: ''\ntype_config: {}\ngenerated_header_file: header.txt\n",
+            )?;
+            let config = FakeRenderer::load_config(&config_path, &[], None)?;
+            render(test_dir.path(), config, false)?;
+            assert_file_has_header(&test_dir.path().join("root"), "// one\n// two\n")?;
+            Ok(())
+        }
+
+        #[test]
+        fn configured_from_overlay_file() -> Result<()> {
+            let test_dir = tempdir()?;
+            let config_dir = tempdir()?;
+            let overlay_dir = tempdir()?;
+            fs::write(config_dir.path().join("header.txt"), "// config\n")?;
+            fs::write(config_dir.path().join("overlay.txt"), "// overlay\n")?;
+            let config_path = config_dir.path().join("config.yml");
+            fs::write(
+                &config_path,
+                "file_extension: ''\ntype_config: {}\ngenerated_header_file: header.txt\n",
+            )?;
+            let overlay_path = overlay_dir.path().join("overlay.yml");
+            fs::write(&overlay_path, "generated_header_file: overlay.txt\n")?;
+
+            let config = FakeRenderer::load_config(&config_path, &[overlay_path], None)?;
+            render(test_dir.path(), config, false)?;
+            assert_file_has_header(&test_dir.path().join("root"), "// overlay\n")?;
+            Ok(())
+        }
+
+        #[test]
+        fn configured_from_missing_file_errors() -> Result<()> {
+            let config_dir = tempdir()?;
+            let config_path = config_dir.path().join("config.yml");
+            fs::write(
+                &config_path,
+                "file_extension: txt\ntype_config: {}\ngenerated_header_file: missing.txt\n",
+            )?;
+            assert!(FakeRenderer::load_config(&config_path, &[], None).is_err());
+            Ok(())
+        }
+
         #[test]
         fn configured_empty_prints_empty() -> Result<()> {
             let test_dir = tempdir()?;
@@ -834,6 +2589,35 @@ mod tests {
             Ok(())
         }
 
+        #[test]
+        fn license_before_header() -> Result<()> {
+            let test_dir = tempdir()?;
+            let mut config = RendererConfig::default();
+            config.license = Some("MIT".to_owned());
+            config.generated_header = Some(CONFIGURED_HEADER_LINES.map(&str::to_owned).to_vec());
+            render(test_dir.path(), config, true)?;
+            let header = ["// SPDX-License-Identifier: MIT\n", CONFIGURED_HEADER].concat();
+            assert_file_has_header(&test_dir.path().join("root"), &header)?;
+            assert_file_has_header(&test_dir.path().join("metadata"), &header)?;
+            Ok(())
+        }
+
+        #[test]
+        fn license_without_header() -> Result<()> {
+            let test_dir = tempdir()?;
+            let mut config = RendererConfig::default();
+            config.license = Some("MIT".to_owned());
+            config.license_notice = true;
+            config.license_comment_prefix = "# ".to_owned();
+            config.generated_header = Some(Vec::new());
+            render(test_dir.path(), config, false)?;
+            assert_file_has_header(
+                &test_dir.path().join("root"),
+                "# SPDX-License-Identifier: MIT\n# Licensed under the MIT License: https://opensource.org/licenses/MIT\n",
+            )?;
+            Ok(())
+        }
+
         const CONFIGURED_HEADER: &str = "configured\nheader\n";
         const CONFIGURED_HEADER_LINES: [&str; 2] = ["configured", "header"];
 
@@ -847,7 +2631,7 @@ mod tests {
             };
             let mut renderer = FakeRenderer::with_config(config);
             renderer.has_metadata = use_metadata;
-            renderer.render(&descriptor_set, path)?;
+            renderer.render(&descriptor_set, path, &RenderOptions::default())?;
             Ok(())
         }
 
@@ -865,6 +2649,14 @@ mod tests {
     struct FakeRenderer {
         pub config: RendererConfig,
         pub has_metadata: bool,
+        pub render_source_file: bool,
+        pub render_source_file_hash: bool,
+        pub render_import_paths: bool,
+        pub render_descriptions: bool,
+        pub render_type_names: bool,
+        pub render_common_package_prefix: bool,
+        pub progress: Mutex<Vec<(usize, usize)>>,
+        pub fail_source_files: Vec<String>,
     }
 
     impl FakeRenderer {
@@ -877,7 +2669,12 @@ mod tests {
     }
 
     impl Renderer for FakeRenderer {
-        fn load(&mut self, _input_root: &Path, _overlays: &[PathBuf]) -> Result<()> {
+        fn load(
+            &mut self,
+            _input_root: &Path,
+            _overlays: &[PathBuf],
+            _config_format: Option<ConfigFormat>,
+        ) -> Result<()> {
             Ok(())
         }
 
@@ -887,21 +2684,73 @@ mod tests {
             &self.config
         }
 
+        fn set_config(&mut self, config: RendererConfig) {
+            self.config = config;
+        }
+
         fn has_metadata(&self) -> bool {
             self.has_metadata
         }
 
         fn render_metadata<W: io::Write>(
             &self,
-            _context: MetadataContext,
-            _writer: &mut W,
+            context: MetadataContext,
+            writer: &mut W,
         ) -> Result<()> {
+            if self.render_descriptions {
+                for file in context.files() {
+                    writeln!(writer, "{}: {}", file.file_name(), file.description())?;
+                }
+                for file in context.package_files_full() {
+                    writeln!(writer, "{}: {}", file.package(), file.description())?;
+                }
+            }
+            if self.render_common_package_prefix {
+                writeln!(
+                    writer,
+                    "common_package_prefix: {}",
+                    context.common_package_prefix()
+                )?;
+            }
+            for file in context.native_files() {
+                writeln!(writer, "native: {}", file)?;
+            }
             Ok(())
         }
 
-        fn render_file<W: io::Write>(&self, _context: FileContext, _writer: &mut W) -> Result<()> {
+        fn render_file<W: io::Write>(&self, context: FileContext, writer: &mut W) -> Result<()> {
+            if self
+                .fail_source_files
+                .iter()
+                .any(|file| file == context.source_file())
+            {
+                return Err(anyhow!("failed to render {}", context.source_file()));
+            }
+            if self.render_source_file {
+                writer.write(context.source_file().as_bytes())?;
+            }
+            if self.render_source_file_hash {
+                writer.write(context.source_file_hash().as_bytes())?;
+            }
+            if self.render_import_paths {
+                for import in context.imports() {
+                    write!(writer, "import {};", import.output_file_path())?;
+                }
+            }
+            if self.render_type_names {
+                for proto in context.enums() {
+                    write!(writer, "enum {};", proto.name())?;
+                }
+                for message in context.messages() {
+                    write!(writer, "message {};", message.name())?;
+                }
+            }
             Ok(())
         }
+
+        fn report_progress(&self, current: usize, total: usize) {
+            self.progress.lock().unwrap().push((current, total));
+        }
     }
 
     fn fake_file_with_package(
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
 pub type Target = String;
 pub type Key = String;
@@ -20,8 +21,18 @@ pub struct OverlayConfig {
     #[serde(default)]
     by_target: HashMap<Target, HashMap<Key, serde_yaml::Value>>,
 
+    /// Replaces the config's `generated_header_file`, e.g. to use another license header for one
+    /// build. Relative paths are resolved from the template root, like the config's.
+    #[serde(default)]
+    generated_header_file: Option<PathBuf>,
+
     #[serde(skip)]
     is_initialized: bool,
+
+    // When non-empty, these targets are used for every context instead of the target derived from
+    // the context itself. Set from the command line, so never part of the config file.
+    #[serde(skip)]
+    active_targets: Vec<Target>,
 }
 
 impl OverlayConfig {
@@ -33,7 +44,9 @@ impl OverlayConfig {
         let mut config = Self {
             by_key,
             by_target,
+            generated_header_file: None,
             is_initialized: false,
+            active_targets: vec![],
         };
         config.initialize();
         config
@@ -44,7 +57,9 @@ impl OverlayConfig {
         Self {
             by_key,
             by_target: Default::default(),
+            generated_header_file: None,
             is_initialized: false,
+            active_targets: vec![],
         }
     }
 
@@ -53,7 +68,9 @@ impl OverlayConfig {
         Self {
             by_key: Default::default(),
             by_target,
+            generated_header_file: None,
             is_initialized: false,
+            active_targets: vec![],
         }
     }
 
@@ -73,6 +90,9 @@ impl OverlayConfig {
         for (k, v) in other.by_target {
             self.merge_by_target(k, v);
         }
+        if other.generated_header_file.is_some() {
+            self.generated_header_file = other.generated_header_file;
+        }
     }
 
     fn merge_by_key(&mut self, key: Key, override_vt: ValueTargets) {
@@ -96,14 +116,28 @@ impl OverlayConfig {
         }
     }
 
+    /// The `generated_header_file` of the last overlay which set one.
+    pub fn generated_header_file(&self) -> Option<&PathBuf> {
+        self.generated_header_file.as_ref()
+    }
+
     pub fn by_target(&self, target: &str) -> Option<&HashMap<Key, serde_yaml::Value>> {
         self.by_target.get(target)
     }
 
+    /// Override the target derived by each context with these targets. Values from later targets
+    /// take precedence over earlier ones.
+    pub fn set_active_targets(&mut self, targets: Vec<Target>) {
+        self.active_targets = targets;
+    }
+
     pub fn by_target_opt_clone(
         &self,
         target: &Option<String>,
     ) -> HashMap<String, serde_yaml::Value> {
+        if !self.active_targets.is_empty() {
+            return self.merged_active_targets();
+        }
         if let Some(name) = target {
             self.by_target(name)
                 .map(Clone::clone)
@@ -113,6 +147,18 @@ impl OverlayConfig {
         }
     }
 
+    fn merged_active_targets(&self) -> HashMap<String, serde_yaml::Value> {
+        let mut merged = HashMap::new();
+        for target in &self.active_targets {
+            if let Some(kv) = self.by_target(target) {
+                for (key, value) in kv {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        merged
+    }
+
     pub fn initialize(&mut self) {
         if self.is_initialized {
             return;
@@ -186,6 +232,41 @@ mod tests {
         assert!(config.is_initialized);
     }
 
+    mod active_targets {
+        use crate::renderer::overlay_config::tests::{by_target_entry, yaml_string};
+        use crate::renderer::overlay_config::OverlayConfig;
+        use std::collections::HashMap;
+
+        #[test]
+        fn overrides_derived_target() {
+            let mut config = OverlayConfig::new(
+                HashMap::new(),
+                by_target!(
+                    by_target_entry("derived", &[("key0", "derived")]),
+                    by_target_entry("active", &[("key0", "active")])
+                ),
+            );
+            config.set_active_targets(vec!["active".to_owned()]);
+            let overlays = config.by_target_opt_clone(&Some("derived".to_owned()));
+            assert_eq!(overlays.get("key0"), Some(&yaml_string("active")));
+        }
+
+        #[test]
+        fn multiple_targets_merge() {
+            let mut config = OverlayConfig::new(
+                HashMap::new(),
+                by_target!(
+                    by_target_entry("first", &[("key0", "first0"), ("key1", "first1")]),
+                    by_target_entry("second", &[("key1", "second1")])
+                ),
+            );
+            config.set_active_targets(vec!["first".to_owned(), "second".to_owned()]);
+            let overlays = config.by_target_opt_clone(&None);
+            assert_eq!(overlays.get("key0"), Some(&yaml_string("first0")));
+            assert_eq!(overlays.get("key1"), Some(&yaml_string("second1")));
+        }
+    }
+
     mod get_by_target {
         use crate::renderer::overlay_config::tests::{by_key_entry, by_target_entry, yaml_string};
         use crate::renderer::overlay_config::OverlayConfig;
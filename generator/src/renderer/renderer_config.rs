@@ -1,9 +1,15 @@
+use crate::lint::LintConfig;
 use crate::renderer::case::Case;
 use crate::renderer::overlay_config::OverlayConfig;
-use crate::renderer::template::METADATA_TEMPLATE_NAME;
+use crate::renderer::template::{FILE_TEMPLATE_NAME, METADATA_TEMPLATE_NAME};
 use crate::renderer::{primitive, proto};
-use serde::{Deserialize, Serialize};
+use prost_types::field_descriptor_proto::Label;
+use prost_types::DescriptorProto;
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RendererConfig {
@@ -40,6 +46,46 @@ pub struct RendererConfig {
     #[serde(default = "default_metadata_file_name")]
     pub metadata_file_name: String,
 
+    /// If set, metadata files are written under this directory instead of next to the generated
+    /// files, keeping the same relative directory structure, e.g. `index/sub/dir/metadata.ext`.
+    /// Relative paths are resolved from the output directory. File names in the metadata context
+    /// are still relative to the directory they describe.
+    pub metadata_output_dir: Option<PathBuf>,
+
+    /// Name of the aggregate file written into each directory of generated files by a script's
+    /// `render_directory` function, e.g. `__init__` for Python. Unlike generated file names,
+    /// `case_config.file_name` is not applied. Only used by the ScriptedRenderer.
+    /// default: "directory"
+    #[serde(default = "default_directory_file_name")]
+    pub directory_file_name: String,
+
+    /// Template language used by the TemplateRenderer, `handlebars` or `tera`. Templates for each
+    /// engine use that engine's file extension, i.e. `file.hbs` or `file.tera`.
+    /// default: `handlebars`
+    #[serde(default)]
+    pub template_engine: TemplateEngine,
+
+    /// Name of the template used to render each proto file, without the `.hbs` extension.
+    /// Allows keeping several variants in one directory, e.g. `file.header` for `file.header.hbs`.
+    /// Only used by the TemplateRenderer.
+    /// default: "file"
+    #[serde(default = "default_file_template_name")]
+    pub file_template_name: String,
+
+    /// Name of the template used to render directory metadata, without the `.hbs` extension.
+    /// Only used by the TemplateRenderer.
+    /// default: "metadata"
+    #[serde(default = "default_metadata_template_name")]
+    pub metadata_template_name: String,
+
+    /// How many directory levels below the template root are searched for templates. Templates
+    /// in subdirectories are named by their path relative to the root, e.g. `sub/foo.hbs` is the
+    /// partial `sub/foo`.
+    /// Only used by the TemplateRenderer and TeraRenderer.
+    /// default: 1
+    #[serde(default = "default_template_scan_depth")]
+    pub template_scan_depth: usize,
+
     /// Separator used in qualified type names.
     /// e.g. root.sub.TypeName
     ///          ^   ^
@@ -47,6 +93,25 @@ pub struct RendererConfig {
     #[serde(default = "default_package_separator")]
     pub package_separator: String,
 
+    /// Proto packages rendered as a different namespace, in the file's `package` and in the
+    /// fully-qualified and relative types of fields. A package also maps the packages nested in
+    /// it, and the longest matching package wins. Output file paths are not affected.
+    /// ```txt
+    /// e.g.
+    /// {
+    ///     "example": "com.example"
+    /// }
+    /// ```
+    /// renders `example.sub.TypeName` as `com.example.sub.TypeName`.
+    #[serde(default)]
+    pub package_namespace_map: HashMap<String, String>,
+
+    /// If set, relative types are computed relative to this package instead of the package of
+    /// the file they're in, e.g. when generated code lives in a namespace other than its proto
+    /// package. Given as it is rendered, so after `package_namespace_map`.
+    /// e.g. with "root", the type `root.sub.TypeName` is `sub.TypeName` in any file.
+    pub relative_root_package: Option<String>,
+
     /// If true, each protobuf package is written out as a single file with all messages inside.
     /// By default (false), the output structure will mirror the proto file structure.
     ///
@@ -54,11 +119,56 @@ pub struct RendererConfig {
     #[serde(default)]
     pub one_file_per_package: bool,
 
+    /// If set, every proto file is written into this single file, relative to the output
+    /// directory, e.g. "sdk.ts". Takes precedence over `one_file_per_package`.
+    ///
+    /// If a metadata template is specified, only one will be generated, as a sibling to this file.
+    pub single_file: Option<String>,
+
+    /// When `one_file_per_package` is true or `single_file` is set, this is written between each
+    /// proto file rendered into the same output file. It is not written before the first file or after the last.
+    ///
+    /// Explicitly setting this to null disables the separator entirely.
+    /// default: `\n`
+    #[serde(default = "default_file_separator")]
+    pub file_separator: Option<String>,
+
     /// When `one_file_per_package` is true, use this file name when a package is not specified in the proto file.
     /// default: `unknown`
     #[serde(default = "default_package_file_name")]
     pub default_package_file_name: String,
 
+    /// When `one_file_per_package` is true, the path of each package file relative to the output
+    /// directory, in place of the default `package_name.ext` layout. Intermediate directories are
+    /// created as needed. Supported placeholders:
+    /// - `{package}`: the package name, e.g. `root.sub`
+    /// - `{package_path}`: the package name as a path, e.g. `root/sub`
+    /// - `{ext}`: `file_extension`
+    /// e.g. "{package_path}/mod.{ext}"
+    pub package_file_path_template: Option<String>,
+
+    /// When `one_file_per_package` is false, files which do not declare a package are rendered
+    /// into this directory under the output directory, rather than next to the output root.
+    /// e.g. "no_package"
+    pub no_package_dir: Option<String>,
+
+    /// Directory removed from the start of each proto file's path before its output path is
+    /// derived, so e.g. with "proto/src" the file `proto/src/foo.proto` is rendered to `foo.ext`.
+    /// Files outside of this directory keep their full path.
+    pub strip_proto_path_prefix: Option<String>,
+
+    /// Directory prepended to the `include_path` of each import, e.g. the path of a `--proto`
+    /// output relative to the include root of the generated code, so with "gen/cpp" the import
+    /// `foo.proto` has the include path `gen/cpp/foo.ext`.
+    pub output_include_base: Option<String>,
+
+    /// When set, the top-level enums of each proto file are rendered into a separate file with
+    /// this suffix before the extension, and the main file is rendered without them, e.g. with
+    /// "enums" `foo.proto` renders messages to `foo.ext` and enums to `foo.enums.ext`. The file
+    /// template is used for both, with `enums` or `messages` empty. Files without enums get no
+    /// enums file. Only applies when `one_file_per_package` is false and `single_file` is unset.
+    pub enums_output_suffix: Option<String>,
+
     /// Override field names declared by the proto, for example when a proto uses a keyword as a
     /// field name in your target language.
     /// e.g. { "enum": "new_name" }
@@ -66,16 +176,50 @@ pub struct RendererConfig {
     #[serde(default)]
     pub field_name_override: HashMap<String, String>,
 
+    /// Rename field names with a list of (pattern, replacement) regex rules. Rules are applied in
+    /// order after case conversion, but before `field_name_override`. Replacements can reference
+    /// capture groups, e.g. `$1`.
+    /// e.g. [["^m_(.*)$", "$1"], ["(.*)_id$", "${1}_key"]]
+    #[serde(default)]
+    pub field_name_regex_override: Vec<RegexOverride>,
+
+    /// Keywords of the target language. Field, message and enum names that match one of these
+    /// after case conversion and overrides have `keyword_escape` appended.
+    /// e.g. ["type", "match", "ref"]
+    #[serde(default)]
+    pub reserved_keywords: Vec<String>,
+
+    /// Suffix appended to names that collide with `reserved_keywords`.
+    /// default: `_`
+    #[serde(default = "default_keyword_escape")]
+    pub keyword_escape: String,
+
     /// A list of input files that will not be rendered.
     /// e.g. "some/useless/file.proto"
     #[serde(default)]
     pub ignored_files: Vec<String>,
 
+    /// Files whose names start with any of these prefixes are treated as imports, e.g. well-known
+    /// types pulled into the descriptor set by `--include_imports`. They are available for type
+    /// resolution but are only rendered when `render_imports` is true.
+    /// default: `["google/protobuf/"]`
+    #[serde(default = "default_import_prefixes")]
+    pub import_prefixes: Vec<String>,
+
+    /// Render files matching `import_prefixes` like any other input file.
+    #[serde(default)]
+    pub render_imports: bool,
+
     /// A list of proto imports that will not be printed to the final file imports.
     /// e.g. "some/useless/file.proto"
     #[serde(default)]
     pub ignored_imports: Vec<String>,
 
+    /// Sort the `imports` of each file by their `output_file_path` and remove duplicates, instead
+    /// of keeping the order of the proto file's imports.
+    #[serde(default)]
+    pub sort_imports: bool,
+
     /// If set, relative types in parent scopes will be specified with this prefix _instead_ of using the
     /// fully qualified type.
     ///
@@ -88,32 +232,121 @@ pub struct RendererConfig {
     /// ```
     pub field_relative_parent_prefix: Option<String>,
 
+    /// If set, the container type of repeated fields, where `{type}` is replaced with the element's
+    /// relative type and made available to templates as `array_type`. Not applied to maps.
+    /// e.g. "Vec<{type}>", "{type}[]" or "List[{type}]"
+    pub array_type: Option<String>,
+
+    /// How nested messages are recognized as the entry types of map fields. See `MapDetection`.
+    /// default: `option`
+    #[serde(default)]
+    pub map_detection: MapDetection,
+
     /// If set, this will be used in place of the default generated warning header in generated files.
     /// Newlines will be placed after each string in the list.
     ///
     /// Explicitly setting this to an empty array will disable the header entirely.
     pub generated_header: Option<Vec<String>>,
 
+    /// If set, the contents of this file are used as the generated header, taking precedence over
+    /// `generated_header`. Relative paths are resolved from the template root, the directory
+    /// containing the config. Overlays can replace it with their own `generated_header_file`.
+    pub generated_header_file: Option<PathBuf>,
+
+    /// SPDX license identifier of the generated code, e.g. "MIT". When set, a
+    /// `SPDX-License-Identifier` comment is written at the top of generated files, before the
+    /// generated header, even if the generated header is disabled.
+    pub license: Option<String>,
+
+    /// If true, a short notice for `license` from a bundled list of common licenses is written
+    /// after the `SPDX-License-Identifier` line. Unknown licenses are an error.
+    #[serde(default)]
+    pub license_notice: bool,
+
+    /// Written before each `license` line to make it a comment in the target language.
+    /// default: "// "
+    #[serde(default = "default_license_comment_prefix")]
+    pub license_comment_prefix: String,
+
+    /// If true, the UTF-8 byte order mark is written at the start of every generated file, before
+    /// the generated header. Some Windows tooling expects it.
+    #[serde(default)]
+    pub emit_bom: bool,
+
+    /// Extra files written once into the output directory after rendering, mapping file names to
+    /// their literal contents, e.g. an `.editorconfig` or `.gitattributes` for the generated code.
+    /// Names are relative to the output directory and can't contain `..`.
+    /// e.g. { ".gitattributes": "* linguist-generated=true\n" }
+    #[serde(default)]
+    pub emit_sidecar: HashMap<String, String>,
+
+    /// Naming rules checked when protox is run with `--lint`. See `LintConfig`.
+    #[serde(default)]
+    pub lint: LintConfig,
+
+    /// If true, rendered template output is cleaned up by stripping trailing whitespace from each
+    /// line and collapsing runs of blank lines into a single blank line.
+    /// Only used by the TemplateRenderer.
+    #[serde(default)]
+    pub trim_whitespace: bool,
+
     /// Options for the ScriptedRenderer Output methods related to scope.
     #[serde(default)]
     pub scripted: ScriptedConfig,
 
     #[serde(default)]
     pub overlays: OverlayConfig,
+
+    /// Any other keys in the config file are kept here for templates and scripts to read, e.g.
+    /// `{{config_extra.my_setting}}` or `context.config_extra("my_setting")`.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_yaml::Value>,
 }
 
 fn default_metadata_file_name() -> String {
     METADATA_TEMPLATE_NAME.to_owned()
 }
 
+fn default_license_comment_prefix() -> String {
+    "// ".to_owned()
+}
+
+fn default_directory_file_name() -> String {
+    "directory".to_owned()
+}
+
+fn default_file_template_name() -> String {
+    FILE_TEMPLATE_NAME.to_owned()
+}
+
+fn default_metadata_template_name() -> String {
+    METADATA_TEMPLATE_NAME.to_owned()
+}
+
+fn default_template_scan_depth() -> usize {
+    1
+}
+
 fn default_package_separator() -> String {
     proto::PACKAGE_SEPARATOR.to_string()
 }
 
+fn default_file_separator() -> Option<String> {
+    Some("\n".to_owned())
+}
+
 fn default_package_file_name() -> String {
     "unknown".to_owned()
 }
 
+fn default_import_prefixes() -> Vec<String> {
+    vec!["google/protobuf/".to_owned()]
+}
+
+fn default_keyword_escape() -> String {
+    "_".to_owned()
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct CaseConfig {
     pub file_name: Case,
@@ -125,6 +358,109 @@ pub struct CaseConfig {
     pub field_name: Case,
 }
 
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateEngine {
+    Handlebars,
+    Tera,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MapDetection {
+    /// Only messages with the `map_entry` option set, which protoc sets for every map field.
+    Option,
+    /// Also messages named `*Entry` with exactly a `key` field numbered 1 and a `value` field
+    /// numbered 2, which a repeated field of the message they're nested in references, for
+    /// hand-written descriptors which follow the map entry convention without setting the option.
+    Heuristic,
+}
+
+impl MapDetection {
+    /// True if `message` is a map entry. `owner` is the message it is nested in, if any. Map
+    /// entries are always nested in the message with the map field, so without an owner only the
+    /// option is checked.
+    pub fn is_map_entry(&self, owner: Option<&DescriptorProto>, message: &DescriptorProto) -> bool {
+        let has_option = message
+            .options
+            .as_ref()
+            .and_then(|options| options.map_entry)
+            .unwrap_or(false);
+        match (self, owner) {
+            (Self::Heuristic, Some(owner)) => {
+                has_option || is_conventional_map_entry(owner, message)
+            }
+            _ => has_option,
+        }
+    }
+}
+
+fn is_conventional_map_entry(owner: &DescriptorProto, message: &DescriptorProto) -> bool {
+    let (owner_name, name) = match (&owner.name, &message.name) {
+        (Some(owner_name), Some(name)) if name.ends_with("Entry") => (owner_name, name),
+        _ => return false,
+    };
+    let fields = message
+        .field
+        .iter()
+        .map(|field| (field.name.as_deref(), field.number))
+        .collect::<Vec<_>>();
+    if fields != [(Some("key"), Some(1)), (Some("value"), Some(2))] {
+        return false;
+    }
+    let type_name_suffix = format!(".{}.{}", owner_name, name);
+    owner.field.iter().any(|field| {
+        field.label == Some(Label::Repeated as i32)
+            && field
+                .type_name
+                .as_ref()
+                .map(|type_name| type_name.ends_with(&type_name_suffix))
+                .unwrap_or(false)
+    })
+}
+
+/// A (pattern, replacement) rule of `field_name_regex_override`. The pattern is compiled when the
+/// config is loaded, so invalid patterns are reported as config errors.
+#[derive(Clone, Debug)]
+pub struct RegexOverride {
+    regex: Regex,
+    replacement: String,
+}
+
+impl RegexOverride {
+    pub fn new(pattern: &str, replacement: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            replacement: replacement.to_owned(),
+        })
+    }
+
+    /// `name` with every match of the pattern replaced.
+    pub fn apply(&self, name: &str) -> String {
+        self.regex
+            .replace_all(name, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+impl Serialize for RegexOverride {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.regex.as_str(), &self.replacement).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for RegexOverride {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (pattern, replacement) = <(String, String)>::deserialize(deserializer)?;
+        Self::new(&pattern, &replacement).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid field_name_regex_override '{}': {}",
+                pattern, e
+            ))
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub enum IndentChar {
     Space,
@@ -153,6 +489,67 @@ pub struct ScopeConfig {
     pub open_on_new_line: bool,
 }
 
+impl RendererConfig {
+    /// True if the proto file `name` is in `ignored_files`, or is an import that isn't rendered.
+    /// Imports are rendered if either the config or `render_imports` says so.
+    pub fn is_ignored_file(&self, name: &str, render_imports: bool) -> bool {
+        if self.ignored_files.iter().any(|file| file == name) {
+            return true;
+        }
+        !(self.render_imports || render_imports)
+            && self
+                .import_prefixes
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+    }
+
+    /// The proto package or fully-qualified type `name`, without a leading `.`, with its package
+    /// replaced by the namespace it maps to in `package_namespace_map`.
+    pub fn package_namespace<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        let mapped = self
+            .package_namespace_map
+            .iter()
+            .filter(|(package, _)| {
+                name.strip_prefix(package.as_str())
+                    .map(|rest| rest.is_empty() || rest.starts_with(proto::PACKAGE_SEPARATOR))
+                    .unwrap_or(false)
+            })
+            .max_by_key(|(package, _)| package.len());
+        match mapped {
+            None => Cow::Borrowed(name),
+            Some((package, namespace)) => {
+                let rest = &name[package.len()..];
+                if namespace.is_empty() {
+                    Cow::Owned(rest.trim_start_matches(proto::PACKAGE_SEPARATOR).to_owned())
+                } else {
+                    Cow::Owned([namespace, rest].concat())
+                }
+            }
+        }
+    }
+
+    /// Appends `keyword_escape` to `name` if it is one of the `reserved_keywords`.
+    pub fn escape_keyword(&self, name: String) -> String {
+        if self.reserved_keywords.contains(&name) {
+            name + &self.keyword_escape
+        } else {
+            name
+        }
+    }
+}
+
+impl Default for TemplateEngine {
+    fn default() -> Self {
+        Self::Handlebars
+    }
+}
+
+impl Default for MapDetection {
+    fn default() -> Self {
+        Self::Option
+    }
+}
+
 impl Default for IndentChar {
     fn default() -> Self {
         Self::Space
@@ -179,16 +576,48 @@ impl Default for RendererConfig {
             type_config: default_type_config(),
             case_config: Default::default(),
             metadata_file_name: default_metadata_file_name(),
+            metadata_output_dir: None,
+            directory_file_name: default_directory_file_name(),
+            template_engine: Default::default(),
+            file_template_name: default_file_template_name(),
+            metadata_template_name: default_metadata_template_name(),
+            template_scan_depth: default_template_scan_depth(),
             package_separator: default_package_separator(),
+            package_namespace_map: HashMap::new(),
+            relative_root_package: None,
             one_file_per_package: false,
+            single_file: None,
+            file_separator: default_file_separator(),
             default_package_file_name: default_package_file_name(),
+            package_file_path_template: None,
+            no_package_dir: None,
+            strip_proto_path_prefix: None,
+            output_include_base: None,
+            enums_output_suffix: None,
             field_name_override: Default::default(),
+            field_name_regex_override: vec![],
+            reserved_keywords: vec![],
+            keyword_escape: default_keyword_escape(),
             ignored_files: vec![],
+            import_prefixes: default_import_prefixes(),
+            render_imports: false,
             ignored_imports: vec![],
+            sort_imports: false,
             field_relative_parent_prefix: None,
+            array_type: None,
+            map_detection: Default::default(),
             generated_header: None,
+            generated_header_file: None,
+            license: None,
+            license_notice: false,
+            license_comment_prefix: default_license_comment_prefix(),
+            emit_bom: false,
+            emit_sidecar: Default::default(),
+            lint: Default::default(),
+            trim_whitespace: false,
             scripted: Default::default(),
             overlays: Default::default(),
+            extra: Default::default(),
         }
     }
 }
@@ -12,12 +12,46 @@ pub fn register(engine: &mut Engine) {
 
 fn register_context(engine: &mut Engine) {
     engine.register_global_module(exported_module!(api).into());
+    engine.register_static_module(PROTO_MODULE_NAME, exported_module!(proto).into());
 }
 
+/// Namespace of the `FieldDescriptorProto` enum constants, e.g. `proto::TYPE_MESSAGE`. Global
+/// modules can't hold variables, so the constants live in their own module.
+const PROTO_MODULE_NAME: &str = "proto";
+
 fn get_str_or_new(opt: Option<&String>) -> String {
     opt.map(&String::clone).unwrap_or(String::new())
 }
 
+/// Raw bytes of the unregistered extensions set on `options`.
+fn extension_bytes_array<M: prost::Message>(
+    options: &M,
+) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+    let bytes =
+        crate::renderer::context::extension_bytes(options).map_err(|e| format!("{:#}", e))?;
+    Ok(bytes
+        .into_iter()
+        .map(|byte| Dynamic::from(byte as rhai::INT))
+        .collect())
+}
+
+fn format_radix(n: rhai::INT, radix: u32) -> String {
+    let mut value = n.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (value % radix as u64) as u32;
+        digits.push(std::char::from_digit(digit, radix).unwrap_or('?'));
+        value /= radix as u64;
+        if value == 0 {
+            break;
+        }
+    }
+    if n < 0 {
+        digits.push('-');
+    }
+    digits.into_iter().rev().collect()
+}
+
 fn hash_to_btree<K: Ord, V>(map: HashMap<K, V>) -> BTreeMap<K, V> {
     let mut btree = BTreeMap::<K, V>::new();
     for (k, v) in map {
@@ -28,7 +62,7 @@ fn hash_to_btree<K: Ord, V>(map: HashMap<K, V>) -> BTreeMap<K, V> {
 
 #[export_module]
 mod api {
-    use super::get_str_or_new;
+    use super::{extension_bytes_array, get_str_or_new};
     use crate::renderer::context;
     use crate::renderer::context::overlayed::Overlayed;
     use crate::renderer::scripted::api::hash_to_btree;
@@ -51,6 +85,35 @@ mod api {
         return result;
     }
 
+    /// Lowercase hexadecimal digits of `n`, without a prefix, e.g. `255` => `ff`.
+    pub fn to_hex(n: rhai::INT) -> String {
+        super::format_radix(n, 16)
+    }
+
+    /// Digits of `n` in `radix`, between 2 and 36, with a leading '-' when negative.
+    #[rhai_fn(return_raw)]
+    pub fn format_int(n: rhai::INT, radix: rhai::INT) -> Result<String, Box<rhai::EvalAltResult>> {
+        if !(2..=36).contains(&radix) {
+            return Err(format!("format_int radix must be between 2 and 36, got {}", radix).into());
+        }
+        Ok(super::format_radix(n, radix as u32))
+    }
+
+    /// Pads the start of `value` with `ch` until it is `width` characters long.
+    pub fn pad_left(value: &str, width: rhai::INT, ch: char) -> String {
+        let len = value.chars().count();
+        let padding = (width.max(0) as usize).saturating_sub(len);
+        let mut result = std::iter::repeat(ch).take(padding).collect::<String>();
+        result.push_str(value);
+        result
+    }
+
+    /// `s` escaped for a string literal in `style`, one of "rust", "c" or "json".
+    #[rhai_fn(return_raw)]
+    pub fn escape_string(s: &str, style: &str) -> Result<String, Box<rhai::EvalAltResult>> {
+        crate::renderer::escape::escape_string(s, style).map_err(|e| e.to_string().into())
+    }
+
     pub type YamlValue = serde_yaml::Value;
 
     ////////////////////////////////////////////////////
@@ -62,8 +125,10 @@ mod api {
     pub type EnumValueContext = context::EnumValueContext;
     pub type MessageContext = context::MessageContext;
     pub type FieldContext = context::FieldContext;
+    pub type OneofContext = context::OneofContext;
 
     pub type MetadataContext = context::MetadataContext;
+    pub type MetadataFile = context::MetadataFile;
     pub type PackageFile = context::PackageFile;
     pub type PackageTreeNode = context::PackageTreeNode;
 
@@ -72,6 +137,7 @@ mod api {
     pub type EnumValueOptions = prost_types::EnumValueOptions;
     pub type MessageOptions = prost_types::MessageOptions;
     pub type FieldOptions = prost_types::FieldOptions;
+    pub type OneofOptions = prost_types::OneofOptions;
 
     ////////////////////////////////////////////////////
     // FileContext
@@ -79,10 +145,33 @@ mod api {
     pub fn file_source_file(context: &mut FileContext) -> String {
         context.source_file().to_owned()
     }
+
+    #[rhai_fn(get = "output_path", pure)]
+    pub fn file_output_path(context: &mut FileContext) -> String {
+        context.output_path().to_owned()
+    }
+    #[rhai_fn(get = "file_extension", pure)]
+    pub fn file_file_extension(context: &mut FileContext) -> String {
+        context.file_extension().to_owned()
+    }
     #[rhai_fn(get = "package_", pure)]
     pub fn file_package(context: &mut FileContext) -> String {
         context.package().to_owned()
     }
+    #[rhai_fn(get = "source_file_hash", pure)]
+    pub fn file_source_file_hash(context: &mut FileContext) -> String {
+        context.source_file_hash().to_owned()
+    }
+
+    #[rhai_fn(get = "edition", pure)]
+    pub fn file_edition(context: &mut FileContext) -> String {
+        get_str_or_new(context.edition())
+    }
+
+    #[rhai_fn(get = "package_components", pure)]
+    pub fn file_package_components(context: &mut FileContext) -> rhai::Dynamic {
+        context.package_components().clone().into()
+    }
     #[rhai_fn(get = "imports", pure)]
     pub fn file_imports(context: &mut FileContext) -> rhai::Dynamic {
         context.imports().clone().into()
@@ -95,11 +184,24 @@ mod api {
     pub fn file_messages(context: &mut FileContext) -> rhai::Dynamic {
         context.messages().clone().into()
     }
+    #[rhai_fn(get = "enum_count", pure)]
+    pub fn file_enum_count(context: &mut FileContext) -> rhai::INT {
+        context.enum_count() as rhai::INT
+    }
+    #[rhai_fn(get = "message_count", pure)]
+    pub fn file_message_count(context: &mut FileContext) -> rhai::INT {
+        context.message_count() as rhai::INT
+    }
     #[rhai_fn(get = "options", pure)]
     pub fn file_options(context: &mut FileContext) -> FileOptions {
         context.options().clone().unwrap_or(FileOptions::default())
     }
 
+    #[rhai_fn(name = "config_extra")]
+    pub fn file_config_extra(context: &mut FileContext, key: String) -> YamlValue {
+        context.config_extra(&key)
+    }
+
     #[rhai_fn(name = "overlay")]
     pub fn file_overlay(context: &mut FileContext, key: String) -> serde_yaml::Value {
         context.overlay(&key)
@@ -122,6 +224,31 @@ mod api {
         context.file_name_with_ext().to_owned()
     }
 
+    #[rhai_fn(get = "is_public", pure)]
+    pub fn import_is_public(context: &mut ImportContext) -> bool {
+        context.is_public()
+    }
+
+    #[rhai_fn(get = "is_weak", pure)]
+    pub fn import_is_weak(context: &mut ImportContext) -> bool {
+        context.is_weak()
+    }
+
+    #[rhai_fn(get = "file_extension", pure)]
+    pub fn import_file_extension(context: &mut ImportContext) -> String {
+        context.file_extension().to_owned()
+    }
+
+    #[rhai_fn(get = "include_path", pure)]
+    pub fn import_include_path(context: &mut ImportContext) -> String {
+        context.include_path().to_owned()
+    }
+
+    #[rhai_fn(get = "output_file_path", pure)]
+    pub fn import_output_file_path(context: &mut ImportContext) -> String {
+        context.output_file_path().to_owned()
+    }
+
     ////////////////////////////////////////////////////
     // EnumContext
     #[rhai_fn(get = "name", pure)]
@@ -134,6 +261,16 @@ mod api {
         context.values().clone().into()
     }
 
+    #[rhai_fn(get = "has_zero_value", pure)]
+    pub fn enum_has_zero_value(context: &mut EnumContext) -> bool {
+        context.has_zero_value()
+    }
+
+    #[rhai_fn(get = "zero_value_name", pure)]
+    pub fn enum_zero_value_name(context: &mut EnumContext) -> String {
+        get_str_or_new(context.zero_value_name())
+    }
+
     #[rhai_fn(get = "options", pure)]
     pub fn enum_options(context: &mut EnumContext) -> EnumOptions {
         context.options().clone().unwrap_or(EnumOptions::default())
@@ -156,6 +293,16 @@ mod api {
         context.number().into()
     }
 
+    #[rhai_fn(get = "aliases", pure)]
+    pub fn enum_value_aliases(context: &mut EnumValueContext) -> rhai::Dynamic {
+        context.aliases().clone().into()
+    }
+
+    #[rhai_fn(get = "is_canonical", pure)]
+    pub fn enum_value_is_canonical(context: &mut EnumValueContext) -> bool {
+        context.is_canonical()
+    }
+
     #[rhai_fn(get = "options", pure)]
     pub fn enum_value_options(context: &mut EnumValueContext) -> EnumValueOptions {
         context
@@ -181,6 +328,75 @@ mod api {
         context.fields().clone().into()
     }
 
+    /// The first field, or `()` if the message has none.
+    #[rhai_fn(get = "first_field", pure)]
+    pub fn message_first_field(context: &mut MessageContext) -> rhai::Dynamic {
+        match context.first_field() {
+            None => rhai::Dynamic::UNIT,
+            Some(field) => rhai::Dynamic::from(field.clone()),
+        }
+    }
+
+    #[rhai_fn(get = "regular_fields", pure)]
+    pub fn message_regular_fields(context: &mut MessageContext) -> rhai::Dynamic {
+        context.regular_fields().clone().into()
+    }
+
+    #[rhai_fn(get = "oneofs", pure)]
+    pub fn message_oneofs(context: &mut MessageContext) -> rhai::Dynamic {
+        context.oneofs().clone().into()
+    }
+
+    #[rhai_fn(get = "all_oneofs", pure)]
+    pub fn message_all_oneofs(context: &mut MessageContext) -> rhai::Dynamic {
+        context.all_oneofs().clone().into()
+    }
+
+    #[rhai_fn(get = "is_map_entry", pure)]
+    pub fn message_is_map_entry(context: &mut MessageContext) -> bool {
+        context.is_map_entry()
+    }
+
+    #[rhai_fn(get = "nested_enums", pure)]
+    pub fn message_nested_enums(context: &mut MessageContext) -> rhai::Dynamic {
+        context.nested_enums().clone().into()
+    }
+
+    #[rhai_fn(get = "has_map_field", pure)]
+    pub fn message_has_map_field(context: &mut MessageContext) -> bool {
+        context.has_map_field()
+    }
+
+    #[rhai_fn(get = "has_repeated_field", pure)]
+    pub fn message_has_repeated_field(context: &mut MessageContext) -> bool {
+        context.has_repeated_field()
+    }
+
+    #[rhai_fn(get = "has_oneof", pure)]
+    pub fn message_has_oneof(context: &mut MessageContext) -> bool {
+        context.has_oneof()
+    }
+
+    #[rhai_fn(get = "used_as_map_value", pure)]
+    pub fn message_used_as_map_value(context: &mut MessageContext) -> bool {
+        context.used_as_map_value()
+    }
+
+    #[rhai_fn(get = "stable_id", pure)]
+    pub fn message_stable_id(context: &mut MessageContext) -> rhai::INT {
+        context.stable_id() as rhai::INT
+    }
+
+    #[rhai_fn(get = "min_serialized_size", pure)]
+    pub fn message_min_serialized_size(context: &mut MessageContext) -> rhai::INT {
+        context.min_serialized_size() as rhai::INT
+    }
+
+    #[rhai_fn(get = "nested_messages", pure)]
+    pub fn message_nested_messages(context: &mut MessageContext) -> rhai::Dynamic {
+        context.nested_messages().clone().into()
+    }
+
     #[rhai_fn(get = "options", pure)]
     pub fn message_options(context: &mut MessageContext) -> MessageOptions {
         context
@@ -194,6 +410,28 @@ mod api {
         context.overlay(&key)
     }
 
+    ////////////////////////////////////////////////////
+    // OneofContext
+    #[rhai_fn(get = "name", pure)]
+    pub fn oneof_name(context: &mut OneofContext) -> String {
+        context.name().to_owned()
+    }
+
+    #[rhai_fn(get = "fields", pure)]
+    pub fn oneof_fields(context: &mut OneofContext) -> rhai::Dynamic {
+        context.fields().clone().into()
+    }
+
+    #[rhai_fn(get = "is_synthetic", pure)]
+    pub fn oneof_is_synthetic(context: &mut OneofContext) -> bool {
+        context.is_synthetic()
+    }
+
+    #[rhai_fn(get = "options", pure)]
+    pub fn oneof_options(context: &mut OneofContext) -> OneofOptions {
+        context.options().clone().unwrap_or(OneofOptions::default())
+    }
+
     ////////////////////////////////////////////////////
     // FieldContext
     #[rhai_fn(get = "name", pure)]
@@ -201,6 +439,11 @@ mod api {
         context.name().to_owned()
     }
 
+    #[rhai_fn(get = "message_name", pure)]
+    pub fn field_message_name(context: &mut FieldContext) -> String {
+        get_str_or_new(context.message_name())
+    }
+
     #[rhai_fn(get = "fully_qualified_type", pure)]
     pub fn field_fully_qualified_type(context: &mut FieldContext) -> String {
         get_str_or_new(context.fully_qualified_type())
@@ -225,6 +468,25 @@ mod api {
     pub fn field_is_oneof(context: &mut FieldContext) -> bool {
         context.is_oneof()
     }
+    #[rhai_fn(get = "oneof_name", pure)]
+    pub fn field_oneof_name(context: &mut FieldContext) -> String {
+        get_str_or_new(context.oneof_name())
+    }
+
+    #[rhai_fn(get = "is_optional", pure)]
+    pub fn field_is_optional(context: &mut FieldContext) -> bool {
+        context.is_optional()
+    }
+
+    #[rhai_fn(get = "is_required", pure)]
+    pub fn field_is_required(context: &mut FieldContext) -> bool {
+        context.is_required()
+    }
+
+    #[rhai_fn(get = "is_repeated", pure)]
+    pub fn field_is_repeated(context: &mut FieldContext) -> bool {
+        context.is_repeated()
+    }
 
     #[rhai_fn(get = "fully_qualified_key_type", pure)]
     pub fn field_fully_qualified_key_type(context: &mut FieldContext) -> String {
@@ -246,6 +508,70 @@ mod api {
         get_str_or_new(context.relative_value_type())
     }
 
+    #[rhai_fn(get = "map_key_number", pure)]
+    pub fn field_map_key_number(context: &mut FieldContext) -> rhai::INT {
+        context.map_key_number().unwrap_or(0) as rhai::INT
+    }
+
+    #[rhai_fn(get = "map_value_number", pure)]
+    pub fn field_map_value_number(context: &mut FieldContext) -> rhai::INT {
+        context.map_value_number().unwrap_or(0) as rhai::INT
+    }
+
+    #[rhai_fn(get = "proto_type_number", pure)]
+    pub fn field_proto_type_number(context: &mut FieldContext) -> rhai::INT {
+        context.proto_type_number().unwrap_or(0) as rhai::INT
+    }
+
+    #[rhai_fn(get = "proto_type_name", pure)]
+    pub fn field_proto_type_name(context: &mut FieldContext) -> String {
+        get_str_or_new(context.proto_type_name())
+    }
+
+    #[rhai_fn(get = "array_type", pure)]
+    pub fn field_array_type(context: &mut FieldContext) -> String {
+        get_str_or_new(context.array_type())
+    }
+
+    #[rhai_fn(get = "type_kind", pure)]
+    pub fn field_type_kind(context: &mut FieldContext) -> String {
+        context.type_kind().to_owned()
+    }
+
+    #[rhai_fn(get = "extendee", pure)]
+    pub fn field_extendee(context: &mut FieldContext) -> String {
+        get_str_or_new(context.extendee())
+    }
+
+    #[rhai_fn(get = "jstype_name", pure)]
+    pub fn field_jstype_name(context: &mut FieldContext) -> String {
+        context.jstype_name().to_owned()
+    }
+
+    #[rhai_fn(get = "is_recursive", pure)]
+    pub fn field_is_recursive(context: &mut FieldContext) -> bool {
+        context.is_recursive()
+    }
+
+    #[rhai_fn(get = "is_packed", pure)]
+    pub fn field_is_packed(context: &mut FieldContext) -> bool {
+        context.is_packed()
+    }
+
+    #[rhai_fn(get = "wire_tag", pure)]
+    pub fn field_wire_tag(context: &mut FieldContext) -> rhai::INT {
+        context.wire_tag() as rhai::INT
+    }
+
+    #[rhai_fn(get = "default_value_bytes", pure)]
+    pub fn field_default_value_bytes(context: &mut FieldContext) -> rhai::Array {
+        context
+            .default_value_bytes()
+            .iter()
+            .map(|byte| rhai::Dynamic::from(*byte as rhai::INT))
+            .collect()
+    }
+
     #[rhai_fn(get = "options", pure)]
     pub fn field_options(context: &mut FieldContext) -> FieldOptions {
         context
@@ -267,6 +593,11 @@ mod api {
         context.directory().display_normalized()
     }
 
+    #[rhai_fn(get = "output_path", pure)]
+    pub fn metadata_output_path(context: &mut MetadataContext) -> String {
+        context.output_path().to_owned()
+    }
+
     #[rhai_fn(get = "file_names", pure)]
     pub fn metadata_file_names(context: &mut MetadataContext) -> rhai::Dynamic {
         context.file_names().clone().into()
@@ -277,11 +608,26 @@ mod api {
         context.file_names_with_ext().to_vec().into()
     }
 
+    #[rhai_fn(get = "files", pure)]
+    pub fn metadata_files(context: &mut MetadataContext) -> rhai::Dynamic {
+        context.files().to_vec().into()
+    }
+
     #[rhai_fn(get = "subdirectories", pure)]
     pub fn metadata_subdirectories(context: &mut MetadataContext) -> rhai::Dynamic {
         context.subdirectories().to_vec().into()
     }
 
+    #[rhai_fn(get = "native_files", pure)]
+    pub fn metadata_native_files(context: &mut MetadataContext) -> rhai::Dynamic {
+        context.native_files().to_vec().into()
+    }
+
+    #[rhai_fn(get = "common_package_prefix", pure)]
+    pub fn metadata_common_package_prefix(context: &mut MetadataContext) -> String {
+        context.common_package_prefix().to_owned()
+    }
+
     #[rhai_fn(get = "package_files_full", pure)]
     pub fn metadata_package_files_full(context: &mut MetadataContext) -> rhai::Dynamic {
         context.package_files_full().to_vec().into()
@@ -305,6 +651,29 @@ mod api {
         context.package().to_owned()
     }
 
+    #[rhai_fn(get = "description", pure)]
+    pub fn package_file_description(context: &mut PackageFile) -> String {
+        context.description().to_owned()
+    }
+
+    ////////////////////////////////////////////////////
+    // MetadataFile
+
+    #[rhai_fn(get = "file_name", pure)]
+    pub fn metadata_file_file_name(context: &mut MetadataFile) -> String {
+        context.file_name().to_owned()
+    }
+
+    #[rhai_fn(get = "file_name_with_ext", pure)]
+    pub fn metadata_file_file_name_with_ext(context: &mut MetadataFile) -> String {
+        context.file_name_with_ext().to_owned()
+    }
+
+    #[rhai_fn(get = "description", pure)]
+    pub fn metadata_file_description(context: &mut MetadataFile) -> String {
+        context.description().to_owned()
+    }
+
     ////////////////////////////////////////////////////
     // PackageTreeNode
 
@@ -401,6 +770,13 @@ mod api {
         get_str_or_new(opt.objc_class_prefix.as_ref())
     }
 
+    #[rhai_fn(get = "unknown_options", pure, return_raw)]
+    pub fn file_opt_unknown_options(
+        opt: &mut FileOptions,
+    ) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+        extension_bytes_array(opt)
+    }
+
     ////////////////////////////////////////////////////
     // EnumOptions
     #[rhai_fn(get = "allow_alias", pure)]
@@ -412,6 +788,13 @@ mod api {
         opt.deprecated.unwrap_or(false)
     }
 
+    #[rhai_fn(get = "unknown_options", pure, return_raw)]
+    pub fn enum_opt_unknown_options(
+        opt: &mut EnumOptions,
+    ) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+        extension_bytes_array(opt)
+    }
+
     ////////////////////////////////////////////////////
     // EnumValueOptions
     #[rhai_fn(get = "deprecated", pure)]
@@ -419,6 +802,21 @@ mod api {
         opt.deprecated.unwrap_or(false)
     }
 
+    /// Custom key-value data, e.g. `value.options["some_key"]`. Empty string if not present.
+    #[rhai_fn(index_get, pure)]
+    pub fn enum_value_opt_get_kv(opt: &mut EnumValueOptions, key: String) -> String {
+        context::enum_value_key_values(opt)
+            .remove(&key)
+            .unwrap_or_default()
+    }
+
+    #[rhai_fn(get = "unknown_options", pure, return_raw)]
+    pub fn enum_value_opt_unknown_options(
+        opt: &mut EnumValueOptions,
+    ) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+        extension_bytes_array(opt)
+    }
+
     ////////////////////////////////////////////////////
     // MessageOptions
     #[rhai_fn(get = "message_set_wire_format", pure)]
@@ -438,6 +836,22 @@ mod api {
         opt.map_entry.unwrap_or(false)
     }
 
+    #[rhai_fn(get = "unknown_options", pure, return_raw)]
+    pub fn message_opt_unknown_options(
+        opt: &mut MessageOptions,
+    ) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+        extension_bytes_array(opt)
+    }
+
+    ////////////////////////////////////////////////////
+    // OneofOptions
+    #[rhai_fn(get = "unknown_options", pure, return_raw)]
+    pub fn oneof_opt_unknown_options(
+        opt: &mut OneofOptions,
+    ) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+        extension_bytes_array(opt)
+    }
+
     ////////////////////////////////////////////////////
     // FieldOptions
     #[rhai_fn(get = "ctype", pure)]
@@ -448,6 +862,10 @@ mod api {
     pub fn field_opt_jstype(opt: &mut FieldOptions) -> rhai::INT {
         opt.jstype.unwrap_or(0) as rhai::INT
     }
+    #[rhai_fn(get = "jstype_name", pure)]
+    pub fn field_opt_jstype_name(opt: &mut FieldOptions) -> String {
+        context::jstype_name(Some(opt)).to_owned()
+    }
     #[rhai_fn(get = "packed", pure)]
     pub fn field_opt_packed(opt: &mut FieldOptions) -> bool {
         opt.packed.unwrap_or(false)
@@ -464,6 +882,12 @@ mod api {
     pub fn field_opt_weak(opt: &mut FieldOptions) -> bool {
         opt.weak.unwrap_or(false)
     }
+    #[rhai_fn(get = "unknown_options", pure, return_raw)]
+    pub fn field_opt_unknown_options(
+        opt: &mut FieldOptions,
+    ) -> Result<rhai::Array, Box<rhai::EvalAltResult>> {
+        extension_bytes_array(opt)
+    }
 
     ////////////////////////////////////////////////////
     // Value
@@ -575,6 +999,36 @@ mod api {
     }
 }
 
+/// Values of the protobuf `FieldDescriptorProto.Label` and `FieldDescriptorProto.Type` enums, for
+/// comparing against e.g. `field.proto_type_number` without magic numbers.
+#[export_module]
+mod proto {
+    use prost_types::field_descriptor_proto::{Label, Type};
+
+    pub const LABEL_OPTIONAL: rhai::INT = Label::Optional as rhai::INT;
+    pub const LABEL_REQUIRED: rhai::INT = Label::Required as rhai::INT;
+    pub const LABEL_REPEATED: rhai::INT = Label::Repeated as rhai::INT;
+
+    pub const TYPE_DOUBLE: rhai::INT = Type::Double as rhai::INT;
+    pub const TYPE_FLOAT: rhai::INT = Type::Float as rhai::INT;
+    pub const TYPE_INT64: rhai::INT = Type::Int64 as rhai::INT;
+    pub const TYPE_UINT64: rhai::INT = Type::Uint64 as rhai::INT;
+    pub const TYPE_INT32: rhai::INT = Type::Int32 as rhai::INT;
+    pub const TYPE_FIXED64: rhai::INT = Type::Fixed64 as rhai::INT;
+    pub const TYPE_FIXED32: rhai::INT = Type::Fixed32 as rhai::INT;
+    pub const TYPE_BOOL: rhai::INT = Type::Bool as rhai::INT;
+    pub const TYPE_STRING: rhai::INT = Type::String as rhai::INT;
+    pub const TYPE_GROUP: rhai::INT = Type::Group as rhai::INT;
+    pub const TYPE_MESSAGE: rhai::INT = Type::Message as rhai::INT;
+    pub const TYPE_BYTES: rhai::INT = Type::Bytes as rhai::INT;
+    pub const TYPE_UINT32: rhai::INT = Type::Uint32 as rhai::INT;
+    pub const TYPE_ENUM: rhai::INT = Type::Enum as rhai::INT;
+    pub const TYPE_SFIXED32: rhai::INT = Type::Sfixed32 as rhai::INT;
+    pub const TYPE_SFIXED64: rhai::INT = Type::Sfixed64 as rhai::INT;
+    pub const TYPE_SINT32: rhai::INT = Type::Sint32 as rhai::INT;
+    pub const TYPE_SINT64: rhai::INT = Type::Sint64 as rhai::INT;
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -25,6 +25,46 @@ mod utilities {
             &expected,
         )
     }
+
+    #[test]
+    fn to_hex() -> Result<()> {
+        let context = FileContext::new(&default_file_proto(), &RendererConfig::default())?;
+        test_file_script(
+            context,
+            r#"output.append(to_hex(255) + " " + to_hex(0) + " " + to_hex(-26));"#,
+            "ff 0 -1a",
+        )
+    }
+
+    #[test]
+    fn format_int() -> Result<()> {
+        let context = FileContext::new(&default_file_proto(), &RendererConfig::default())?;
+        test_file_script(
+            context,
+            r#"output.append(format_int(5, 2) + " " + format_int(35, 36));"#,
+            "101 z",
+        )
+    }
+
+    #[test]
+    fn pad_left() -> Result<()> {
+        let context = FileContext::new(&default_file_proto(), &RendererConfig::default())?;
+        test_file_script(
+            context,
+            r#"output.append(pad_left(to_hex(10), 4, '0') + " " + pad_left("long", 2, ' '));"#,
+            "000a long",
+        )
+    }
+
+    #[test]
+    fn escape_string() -> Result<()> {
+        let context = FileContext::new(&default_file_proto(), &RendererConfig::default())?;
+        test_file_script(
+            context,
+            r#"output.append(escape_string("say \"hi\"\n", "rust"));"#,
+            r#"say \"hi\"\n"#,
+        )
+    }
 }
 
 mod file_context {
@@ -50,13 +90,36 @@ mod file_context {
         test_file_script(context, "output.append(context.package_);", &expected)
     }
 
+    #[test]
+    fn config_extra() -> Result<()> {
+        let config: RendererConfig =
+            serde_yaml::from_str("file_extension: txt\ntype_config: {}\nmy_setting: value")?;
+        let context = FileContext::new(&default_file_proto(), &config)?;
+        test_file_script(
+            context,
+            r#"output.append(context.config_extra("my_setting").as_str());"#,
+            "value",
+        )
+    }
+
+    #[test]
+    fn file_extension() -> Result<()> {
+        let config: RendererConfig = serde_yaml::from_str("file_extension: txt\ntype_config: {}")?;
+        let context = FileContext::new(&default_file_proto(), &config)?;
+        test_file_script(context, "output.append(context.file_extension);", "txt")
+    }
+
     // Others accessors are tested in their own sections.
 }
 
 mod import_context {
     use anyhow::Result;
 
-    use crate::renderer::scripted::integration_tests::{file_with_imports, test_file_script};
+    use crate::renderer::context::FileContext;
+    use crate::renderer::scripted::integration_tests::{
+        default_file_proto, file_with_imports, test_file_script,
+    };
+    use crate::renderer::RendererConfig;
 
     #[test]
     fn file_path() -> Result<()> {
@@ -73,6 +136,45 @@ mod import_context {
         run_test("file_name_with_ext", "file.txt")
     }
 
+    #[test]
+    fn is_public() -> Result<()> {
+        run_test("is_public.to_string()", "false")
+    }
+
+    #[test]
+    fn is_weak() -> Result<()> {
+        run_test("is_weak.to_string()", "false")
+    }
+
+    #[test]
+    fn output_file_path_uses_file_extension() -> Result<()> {
+        let mut proto = default_file_proto();
+        proto.dependency.push("relative/path/file.proto".to_owned());
+        let mut config = RendererConfig::default();
+        config.file_extension = "rs".to_owned();
+        let context = FileContext::new(&proto, &config)?;
+        test_file_script(
+            context,
+            "output.append(context.imports[0].output_file_path + \",\" + context.imports[0].file_extension);",
+            "relative/path/file.rs,rs",
+        )
+    }
+
+    #[test]
+    fn include_path_uses_output_include_base() -> Result<()> {
+        let mut proto = default_file_proto();
+        proto.dependency.push("foo.proto".to_owned());
+        let mut config = RendererConfig::default();
+        config.file_extension = "ext".to_owned();
+        config.output_include_base = Some("base".to_owned());
+        let context = FileContext::new(&proto, &config)?;
+        test_file_script(
+            context,
+            "output.append(context.imports[0].include_path);",
+            "base/foo.ext",
+        )
+    }
+
     fn run_test(method: &str, expected_output: &str) -> Result<()> {
         let context = file_with_imports(&["relative/path/file.txt"])?;
         test_file_script(
@@ -85,6 +187,7 @@ mod import_context {
 
 mod enum_context {
     use anyhow::Result;
+    use prost_types::EnumValueDescriptorProto;
 
     use crate::renderer::scripted::integration_tests::{
         enum_proto, file_with_enums, test_file_script,
@@ -95,6 +198,27 @@ mod enum_context {
         run_test("name", enum_proto().name())
     }
 
+    #[test]
+    fn has_zero_value() -> Result<()> {
+        run_test("has_zero_value.to_string()", "false")
+    }
+
+    #[test]
+    fn zero_value_name() -> Result<()> {
+        let mut proto = enum_proto();
+        proto.value.push(EnumValueDescriptorProto {
+            name: Some("Zero".to_owned()),
+            number: Some(0),
+            ..Default::default()
+        });
+        let context = file_with_enums(vec![proto])?;
+        test_file_script(
+            context,
+            "output.append(context.enums[0].zero_value_name);",
+            "Zero",
+        )
+    }
+
     // Others accessors are tested in their own sections.
 
     fn run_test(method: &str, expected_output: &str) -> Result<()> {
@@ -124,6 +248,25 @@ mod enum_value_context {
         run_test("number", &enum_proto().value[0].number().to_string())
     }
 
+    #[test]
+    fn aliases() -> Result<()> {
+        let mut proto = enum_proto();
+        let mut alias = proto.value[0].clone();
+        alias.name = Some("AliasName".to_owned());
+        proto.value.push(alias);
+        let context = file_with_enums(vec![proto])?;
+        test_file_script(
+            context,
+            r#"
+            for value in context.enums[0].values {
+                output.append(value.name + ":" + value.is_canonical.to_string() + ":");
+                output.append(value.aliases[0] + " ");
+            }
+            "#,
+            "EnumValueName:true:AliasName AliasName:false:EnumValueName ",
+        )
+    }
+
     fn run_test(method: &str, expected_output: &str) -> Result<()> {
         let context = file_with_enums(vec![enum_proto()])?;
         test_file_script(
@@ -143,9 +286,12 @@ mod enum_value_context {
 
 mod message_context {
     use anyhow::Result;
+    use prost_types::field_descriptor_proto::Label;
+    use prost_types::OneofDescriptorProto;
 
     use crate::renderer::scripted::integration_tests::{
-        default_message_proto, file_with_messages, test_file_script,
+        default_field_proto, default_message_proto, enum_proto, file_with_messages,
+        test_file_script,
     };
 
     #[test]
@@ -153,6 +299,113 @@ mod message_context {
         run_test("name", "SomeMessage")
     }
 
+    #[test]
+    fn min_serialized_size() -> Result<()> {
+        run_test("min_serialized_size.to_string()", "0")
+    }
+
+    #[test]
+    fn collection_fields() -> Result<()> {
+        let mut message = default_message_proto("SomeMessage");
+        let mut list = default_field_proto("list", "float");
+        list.label = Some(Label::Repeated as i32);
+        message.field.push(list);
+        let context = file_with_messages(vec![message])?;
+        test_file_script(
+            context,
+            r#"
+            let message = context.messages[0];
+            output.append(`${message.has_map_field}:${message.has_repeated_field}:${message.has_oneof}`);
+            "#,
+            "false:true:false",
+        )
+    }
+
+    #[test]
+    fn stable_id() -> Result<()> {
+        // FNV-1a of "SomeMessage".
+        run_test("stable_id.to_string()", "1797250184")
+    }
+
+    #[test]
+    fn nested_enums() -> Result<()> {
+        let mut message = default_message_proto("SomeMessage");
+        message.enum_type.push(enum_proto());
+        let context = file_with_messages(vec![message])?;
+        test_file_script(
+            context,
+            "output.append(context.messages[0].nested_enums[0].name);",
+            enum_proto().name(),
+        )
+    }
+
+    #[test]
+    fn first_field() -> Result<()> {
+        let mut message = default_message_proto("SomeMessage");
+        message.field.push(default_field_proto("id", "float"));
+        message.field.push(default_field_proto("other", "float"));
+        let empty = default_message_proto("Empty");
+        let context = file_with_messages(vec![message, empty])?;
+        test_file_script(
+            context,
+            r#"
+            output.append(context.messages[0].first_field.name);
+            output.append(":" + type_of(context.messages[1].first_field));
+            "#,
+            "id:()",
+        )
+    }
+
+    #[test]
+    fn regular_fields() -> Result<()> {
+        let mut message = default_message_proto("SomeMessage");
+        message.field.push(default_field_proto("regular", "float"));
+        let mut oneof_member = default_field_proto("choice", "float");
+        oneof_member.oneof_index = Some(0);
+        message.field.push(oneof_member);
+        let context = file_with_messages(vec![message])?;
+        test_file_script(
+            context,
+            r#"
+            let fields = context.messages[0].regular_fields;
+            output.append(fields.len().to_string() + ":" + fields[0].name);
+            "#,
+            "1:regular",
+        )
+    }
+
+    #[test]
+    fn oneofs() -> Result<()> {
+        let mut message = default_message_proto("SomeMessage");
+        let mut choice = default_field_proto("choice", "float");
+        choice.oneof_index = Some(0);
+        message.field.push(choice);
+        let mut optional = default_field_proto("optional", "float");
+        optional.oneof_index = Some(1);
+        optional.proto3_optional = Some(true);
+        message.field.push(optional);
+        message.oneof_decl.push(OneofDescriptorProto {
+            name: Some("real".to_owned()),
+            options: None,
+        });
+        message.oneof_decl.push(OneofDescriptorProto {
+            name: Some("_optional".to_owned()),
+            options: None,
+        });
+        let context = file_with_messages(vec![message])?;
+        test_file_script(
+            context,
+            r#"
+            let message = context.messages[0];
+            let real = message.oneofs[0];
+            output.append(message.oneofs.len().to_string() + ":" + real.name + ":" + real.is_synthetic.to_string() + ":" + real.fields[0].name);
+            let synthetic = message.all_oneofs[1];
+            output.append("," + message.all_oneofs.len().to_string() + ":" + synthetic.name + ":" + synthetic.is_synthetic.to_string());
+            "#,
+            "1:real:false:choice,2:_optional:true",
+        )
+    }
+
     // Others accessors are tested in their own sections.
 
     fn run_test(method: &str, expected_output: &str) -> Result<()> {
@@ -169,7 +422,7 @@ mod message_context {
 mod field_context {
     use anyhow::Result;
     use prost_types::field_descriptor_proto::{Label, Type};
-    use prost_types::{DescriptorProto, FieldDescriptorProto, MessageOptions};
+    use prost_types::{DescriptorProto, FieldDescriptorProto, FieldOptions, MessageOptions};
 
     use crate::renderer::scripted::integration_tests::{
         default_message_proto, file_with_messages, test_file_script,
@@ -180,6 +433,10 @@ mod field_context {
         run_test(field(), "name", "some_field")
     }
     #[test]
+    fn message_name() -> Result<()> {
+        run_test(field(), "message_name", "SomeName")
+    }
+    #[test]
     fn fully_qualified_type() -> Result<()> {
         run_test(field(), "fully_qualified_type", "package.SomeType")
     }
@@ -197,6 +454,73 @@ mod field_context {
         run_test(array_field(), "is_array", "true")
     }
 
+    #[test]
+    fn extendee() -> Result<()> {
+        run_test(extension_field(), "extendee", "package.Extended")
+    }
+
+    #[test]
+    fn proto_type_number() -> Result<()> {
+        run_test(scalar_field(), "proto_type_number", "5")
+    }
+
+    #[test]
+    fn proto_type_number_constants() -> Result<()> {
+        let context = file_with_messages(vec![message(vec![scalar_field()])])?;
+        test_file_script(
+            context,
+            r#"
+            let field = context.messages[0].fields[0];
+            output.append((field.proto_type_number == proto::TYPE_INT32).to_string());
+            output.append((field.proto_type_number == proto::TYPE_MESSAGE).to_string());
+            output.append(proto::LABEL_REPEATED.to_string());
+            "#,
+            "truefalse3",
+        )
+    }
+
+    #[test]
+    fn proto_type_name() -> Result<()> {
+        run_test(scalar_field(), "proto_type_name", "TYPE_INT32")
+    }
+
+    #[test]
+    fn default_value_bytes() -> Result<()> {
+        let field = FieldDescriptorProto {
+            name: Some("some_field".to_owned()),
+            r#type: Some(Type::Bytes as i32),
+            default_value: Some(r"\x01\x02".to_owned()),
+            ..Default::default()
+        };
+        run_test(field, "default_value_bytes", "[1, 2]")
+    }
+
+    #[test]
+    fn is_packed() -> Result<()> {
+        let field = FieldDescriptorProto {
+            name: Some("some_field".to_owned()),
+            r#type: Some(Type::Int32 as i32),
+            label: Some(Label::Repeated as i32),
+            options: Some(FieldOptions {
+                packed: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        run_test(field, "is_packed", "true")
+    }
+
+    #[test]
+    fn wire_tag() -> Result<()> {
+        let field = FieldDescriptorProto {
+            name: Some("some_field".to_owned()),
+            number: Some(2),
+            r#type: Some(Type::String as i32),
+            ..Default::default()
+        };
+        run_test(field, "wire_tag", "18")
+    }
+
     #[test]
     fn is_map() -> Result<()> {
         run_map_test("is_map", "true")
@@ -217,6 +541,14 @@ mod field_context {
     fn relative_value_type() -> Result<()> {
         run_map_test("relative_value_type", "int32")
     }
+    #[test]
+    fn map_key_number() -> Result<()> {
+        run_map_test("map_key_number", "1")
+    }
+    #[test]
+    fn map_value_number() -> Result<()> {
+        run_map_test("map_value_number", "2")
+    }
 
     fn field() -> FieldDescriptorProto {
         FieldDescriptorProto {
@@ -236,6 +568,23 @@ mod field_context {
         }
     }
 
+    fn scalar_field() -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some("some_field".to_owned()),
+            r#type: Some(Type::Int32 as i32),
+            ..Default::default()
+        }
+    }
+
+    fn extension_field() -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some("some_field".to_owned()),
+            type_name: Some(".package.SomeType".to_owned()),
+            extendee: Some(".package.Extended".to_owned()),
+            ..Default::default()
+        }
+    }
+
     fn map_field() -> FieldDescriptorProto {
         FieldDescriptorProto {
             name: Some("some_field".to_owned()),
@@ -294,11 +643,13 @@ mod field_context {
         });
         map_entry.field.push(FieldDescriptorProto {
             name: Some("key".to_owned()),
+            number: Some(1),
             r#type: Some(Type::String as i32),
             ..Default::default()
         });
         map_entry.field.push(FieldDescriptorProto {
             name: Some("value".to_owned()),
+            number: Some(2),
             r#type: Some(Type::Int32 as i32),
             ..Default::default()
         });
@@ -371,6 +722,22 @@ mod metadata_context {
         )
     }
 
+    #[test]
+    fn files() -> Result<()> {
+        let mut context = MetadataContext::new();
+        context.push_described_file(&PathBuf::from("file0.ext"), "First.")?;
+        context.push_file(&PathBuf::from("file1.ext"))?;
+        test_metadata_script(
+            context,
+            r#"
+            for file in context.files {
+                output.append(file.file_name + "|" + file.file_name_with_ext + "|" + file.description + ";");
+            }
+            "#,
+            "file0|file0.ext|First.;file1|file1.ext|;",
+        )
+    }
+
     #[test]
     fn subdirectories() -> Result<()> {
         let mut context = MetadataContext::new();
@@ -449,6 +816,82 @@ mod metadata_context {
     }
 }
 
+mod render_directory {
+    use std::fs;
+
+    use anyhow::Result;
+    use prost_types::{FileDescriptorProto, FileDescriptorSet};
+    use tempfile::tempdir;
+
+    use crate::render::Render;
+    use crate::renderer::case::Case;
+    use crate::renderer::scripted::renderer::ScriptedRenderer;
+    use crate::renderer::{Renderer, RendererConfig};
+
+    #[test]
+    fn emits_init_listing_directory_modules() -> Result<()> {
+        let output = tempdir()?;
+        let mut config = RendererConfig::default();
+        config.file_extension = "py".to_owned();
+        config.directory_file_name = "__init__".to_owned();
+        config.case_config.file_name = Case::LowerSnake;
+        config.generated_header = Some(vec![]);
+        let mut renderer = ScriptedRenderer::new();
+        renderer.set_config(config);
+        renderer.load_test_script(
+            r#"
+            fn render_file(context, output) {
+                output
+            }
+            fn render_directory(context, output) {
+                for name in context.file_names {
+                    output.append(`from . import ${name}\n`);
+                }
+                output
+            }
+            "#,
+        )?;
+        let descriptor_set = FileDescriptorSet {
+            file: vec![
+                file("pkg/first.proto"),
+                file("pkg/second_file.proto"),
+                file("other/third.proto"),
+            ],
+        };
+        Render::render(&renderer, &descriptor_set, output.path())?;
+
+        assert_eq!(
+            fs::read_to_string(output.path().join("pkg/__init__.py"))?,
+            "from . import first\nfrom . import second_file\n"
+        );
+        assert_eq!(
+            fs::read_to_string(output.path().join("other/__init__.py"))?,
+            "from . import third\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn not_rendered_without_function() -> Result<()> {
+        let output = tempdir()?;
+        let mut renderer = ScriptedRenderer::new();
+        renderer.load_test_script("fn render_file(context, output) { output }")?;
+        let descriptor_set = FileDescriptorSet {
+            file: vec![file("pkg/first.proto")],
+        };
+        Render::render(&renderer, &descriptor_set, output.path())?;
+        assert!(!output.path().join("pkg/directory").exists());
+        Ok(())
+    }
+
+    fn file(name: &str) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        }
+    }
+}
+
 macro_rules! opt_test {
     ($opt_type: ident, $name: ident, $value: expr) => {
         #[test]
@@ -531,6 +974,7 @@ mod enum_options {
 
 mod enum_value_options {
     use anyhow::Result;
+    use prost::Extendable;
     use prost_types::{EnumValueDescriptorProto, EnumValueOptions};
 
     use crate::renderer::context::FileContext;
@@ -540,6 +984,33 @@ mod enum_value_options {
 
     opt_test!(EnumValueOptions, deprecated, true);
 
+    #[test]
+    fn key_value() -> Result<()> {
+        let mut options = EnumValueOptions::default();
+        options.set_extension_data(
+            &proto_options::ENUM_VALUE_KEY_VALUE,
+            vec![proto_options::KeyValue {
+                key: "display".to_owned(),
+                value: "Value A".to_owned(),
+            }],
+        )?;
+        run_key_value_test(options, "Value A")
+    }
+
+    #[test]
+    fn missing_key_value_is_empty() -> Result<()> {
+        run_key_value_test(EnumValueOptions::default(), "")
+    }
+
+    fn run_key_value_test(options: EnumValueOptions, expected_output: &str) -> Result<()> {
+        let context = file_context(options)?;
+        test_file_script(
+            context,
+            r#"output.append(context.enums[0].values[0].options["display"]);"#,
+            expected_output,
+        )
+    }
+
     fn run_test(options: EnumValueOptions, method: &str, expected_output: &str) -> Result<()> {
         let context = file_context(options)?;
         test_file_script(
@@ -598,6 +1069,8 @@ mod message_options {
 
 mod field_options {
     use anyhow::Result;
+    use prost::Extendable;
+    use prost_types::field_options::JsType;
     use prost_types::FieldOptions;
 
     use crate::renderer::context::FileContext;
@@ -607,10 +1080,41 @@ mod field_options {
 
     opt_test!(FieldOptions, ctype, 1);
     opt_test!(FieldOptions, jstype, 2);
+
+    #[test]
+    fn jstype_name() -> Result<()> {
+        run_test(FieldOptions::default(), "jstype_name", "normal")?;
+        for (jstype, name) in [(JsType::JsString, "string"), (JsType::JsNumber, "number")] {
+            let options = FieldOptions {
+                jstype: Some(jstype as i32),
+                ..Default::default()
+            };
+            run_test(options.clone(), "jstype_name", name)?;
+            test_file_script(
+                file_context(options)?,
+                "output.append(context.messages[0].fields[0].jstype_name);",
+                name,
+            )?;
+        }
+        Ok(())
+    }
     opt_test!(FieldOptions, packed, true);
     opt_test!(FieldOptions, lazy, true);
     opt_test!(FieldOptions, deprecated, true);
     opt_test!(FieldOptions, weak, true);
+
+    #[test]
+    fn registered_options_are_not_unknown() -> Result<()> {
+        let mut options = FieldOptions::default();
+        options.set_extension_data(&proto_options::NATIVE_TYPE, "i32".to_owned())?;
+        run_test(options, "unknown_options.len()", "0")
+    }
+
+    #[test]
+    fn no_unknown_options() -> Result<()> {
+        run_test(FieldOptions::default(), "unknown_options.len()", "0")
+    }
+
     fn run_test(options: FieldOptions, method: &str, expected_output: &str) -> Result<()> {
         let context = file_context(options)?;
         test_file_script(
@@ -1,26 +1,30 @@
 use anyhow::Result;
+use prost_types::FileDescriptorSet;
 
 use crate::in_out_generator::InOutGenerator;
-use crate::renderer::scripted::renderer::ScriptedRenderer;
 use crate::{Config, InOutConfig};
 
 mod api;
 mod renderer;
 
+pub use renderer::ScriptedRenderer;
+
 #[cfg(test)]
 mod integration_tests;
 
 pub const SCRIPT_EXT: &'static str = "rhai";
 pub const MAIN_SCRIPT_NAME: &'static str = "main";
+pub const PRELUDE_SCRIPT_NAME: &'static str = "prelude";
 pub const RENDER_FILE_FN_NAME: &'static str = "render_file";
 pub const RENDER_METADATA_FN_NAME: &'static str = "render_metadata";
+pub const RENDER_DIRECTORY_FN_NAME: &'static str = "render_directory";
 
-pub fn generate(config: &Config) -> Result<()> {
+pub fn generate(config: &Config, descriptor_set: &FileDescriptorSet) -> Result<bool> {
     Generator {
         config,
         renderer: ScriptedRenderer::new(),
     }
-    .generate()
+    .generate_from_descriptor_set(descriptor_set)
 }
 
 struct Generator<'a> {
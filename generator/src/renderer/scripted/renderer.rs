@@ -9,10 +9,11 @@ use rhai::{Dynamic, Engine, Scope, ScriptFnMetadata, AST};
 use crate::renderer::context::{FileContext, MetadataContext};
 use crate::renderer::scripted::api::output::Output;
 use crate::renderer::scripted::{
-    api, MAIN_SCRIPT_NAME, RENDER_FILE_FN_NAME, RENDER_METADATA_FN_NAME, SCRIPT_EXT,
+    api, MAIN_SCRIPT_NAME, PRELUDE_SCRIPT_NAME, RENDER_DIRECTORY_FN_NAME, RENDER_FILE_FN_NAME,
+    RENDER_METADATA_FN_NAME, SCRIPT_EXT,
 };
 use crate::renderer::{find_existing_config_path, Renderer, RendererConfig};
-use crate::DisplayNormalized;
+use crate::{ConfigFormat, DisplayNormalized};
 
 pub struct ScriptedRenderer {
     engine: Engine,
@@ -39,6 +40,16 @@ impl ScriptedRenderer {
         engine
     }
 
+    fn has_fn(&self, name: &str) -> bool {
+        if let Some(ast) = &self.main_ast {
+            return ast
+                .iter_functions()
+                .find(|f: &ScriptFnMetadata| f.name == name)
+                .is_some();
+        }
+        false
+    }
+
     fn main_ast_or_error(&self) -> Result<&AST> {
         match &self.main_ast {
             None => Err(anyhow!("`{}` script file is not loaded.", MAIN_SCRIPT_NAME)),
@@ -75,14 +86,22 @@ impl ScriptedRenderer {
 }
 
 impl Renderer for ScriptedRenderer {
-    fn load(&mut self, input_root: &Path, overlays: &[PathBuf]) -> Result<()> {
-        self.config = Self::load_config(&find_existing_config_path(input_root)?, overlays)?;
+    fn load(
+        &mut self,
+        input_root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        let config_path = find_existing_config_path(input_root)?;
+        self.config = Self::load_config(&config_path, overlays, config_format)?;
         let resolver = FileModuleResolver::new_with_path_and_extension(input_root, SCRIPT_EXT);
         self.engine.set_module_resolver(resolver);
-        self.main_ast = Some(compile_file(
-            &mut self.engine,
-            &main_script_path(input_root),
-        )?);
+        let main_ast = compile_file(&mut self.engine, &script_path(input_root, MAIN_SCRIPT_NAME))?;
+        let prelude_path = script_path(input_root, PRELUDE_SCRIPT_NAME);
+        self.main_ast = Some(match prelude_path.exists() {
+            false => main_ast,
+            true => compile_file(&mut self.engine, &prelude_path)?.merge(&main_ast),
+        });
         Ok(())
     }
 
@@ -94,14 +113,12 @@ impl Renderer for ScriptedRenderer {
         &self.config
     }
 
+    fn set_config(&mut self, config: RendererConfig) {
+        self.config = config;
+    }
+
     fn has_metadata(&self) -> bool {
-        if let Some(ast) = &self.main_ast {
-            return ast
-                .iter_functions()
-                .find(|f: &ScriptFnMetadata| f.name == RENDER_METADATA_FN_NAME)
-                .is_some();
-        }
-        false
+        self.has_fn(RENDER_METADATA_FN_NAME)
     }
 
     fn render_metadata<W: Write>(&self, context: MetadataContext, writer: &mut W) -> Result<()> {
@@ -111,10 +128,18 @@ impl Renderer for ScriptedRenderer {
     fn render_file<W: Write>(&self, context: FileContext, writer: &mut W) -> Result<()> {
         self.render(Dynamic::from(context), RENDER_FILE_FN_NAME, writer)
     }
+
+    fn has_directory_renderer(&self) -> bool {
+        self.has_fn(RENDER_DIRECTORY_FN_NAME)
+    }
+
+    fn render_directory<W: Write>(&self, context: MetadataContext, writer: &mut W) -> Result<()> {
+        self.render(Dynamic::from(context), RENDER_DIRECTORY_FN_NAME, writer)
+    }
 }
 
-fn main_script_path(root: &Path) -> PathBuf {
-    root.join(MAIN_SCRIPT_NAME).with_extension(SCRIPT_EXT)
+fn script_path(root: &Path, name: &str) -> PathBuf {
+    root.join(name).with_extension(SCRIPT_EXT)
 }
 
 fn compile_file(engine: &mut rhai::Engine, path: &Path) -> Result<AST> {
@@ -128,7 +153,9 @@ mod tests {
     use crate::renderer::context::{FileContext, MetadataContext};
     use anyhow::Result;
     use prost_types::FileDescriptorProto;
+    use std::fs;
     use std::path::PathBuf;
+    use tempfile::tempdir;
 
     use crate::renderer::scripted::renderer::ScriptedRenderer;
     use crate::renderer::{Renderer, RendererConfig};
@@ -173,6 +200,72 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn imports_modules_from_input_root() -> Result<()> {
+        let root = tempdir()?;
+        fs::write(
+            root.path().join("config.yml"),
+            "file_extension: txt\ntype_config: {}\n",
+        )?;
+        fs::write(
+            root.path().join("util.rhai"),
+            r#"fn greet(name) { `hello ${name}!` }"#,
+        )?;
+        fs::write(
+            root.path().join("main.rhai"),
+            r#"fn render_file(f, o) {
+                import "util" as util;
+                o.append(util::greet(f.source_file));
+                o
+            }"#,
+        )?;
+        let mut renderer = ScriptedRenderer::new();
+        renderer.load(root.path(), &[], None)?;
+
+        let file = &FileDescriptorProto {
+            name: Some("FileName".to_owned()),
+            ..Default::default()
+        };
+        let context = FileContext::new(file, renderer.config())?;
+        let mut output = Vec::new();
+        renderer.render_file(context, &mut output)?;
+        assert_eq!(String::from_utf8(output)?, "hello FileName!");
+        Ok(())
+    }
+
+    #[test]
+    fn prelude_definitions_are_available() -> Result<()> {
+        let root = tempdir()?;
+        fs::write(
+            root.path().join("config.yml"),
+            "file_extension: txt\ntype_config: {}\n",
+        )?;
+        fs::write(
+            root.path().join("prelude.rhai"),
+            r#"const GREETING = "hello";
+            fn punctuate(s) { `${s}!` }"#,
+        )?;
+        fs::write(
+            root.path().join("main.rhai"),
+            r#"fn render_file(f, o) {
+                o.append(punctuate(`${global::GREETING} ${f.source_file}`));
+                o
+            }"#,
+        )?;
+        let mut renderer = ScriptedRenderer::new();
+        renderer.load(root.path(), &[], None)?;
+
+        let file = &FileDescriptorProto {
+            name: Some("FileName".to_owned()),
+            ..Default::default()
+        };
+        let context = FileContext::new(file, renderer.config())?;
+        let mut output = Vec::new();
+        renderer.render_file(context, &mut output)?;
+        assert_eq!(String::from_utf8(output)?, "hello FileName!");
+        Ok(())
+    }
+
     #[test]
     fn has_metadata() -> Result<()> {
         let mut renderer = ScriptedRenderer::new();
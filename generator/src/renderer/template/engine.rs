@@ -0,0 +1,171 @@
+use crate::renderer::context::{FileContext, MetadataContext};
+use crate::renderer::template::{TemplateRenderer, TeraRenderer};
+use crate::renderer::{find_existing_config_path, Renderer, RendererConfig, TemplateEngine};
+use crate::ConfigFormat;
+use anyhow::Result;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Delegates to the renderer for the `template_engine` chosen in each template folder's config.
+pub enum EngineRenderer<'a> {
+    Handlebars(TemplateRenderer<'a>),
+    Tera(TeraRenderer),
+}
+
+impl EngineRenderer<'_> {
+    pub fn new() -> Self {
+        Self::Handlebars(TemplateRenderer::new())
+    }
+
+    pub fn engine(&self) -> TemplateEngine {
+        match self {
+            Self::Handlebars(_) => TemplateEngine::Handlebars,
+            Self::Tera(_) => TemplateEngine::Tera,
+        }
+    }
+
+    fn select(&mut self, engine: TemplateEngine) {
+        if self.engine() == engine {
+            return;
+        }
+        *self = match engine {
+            TemplateEngine::Handlebars => Self::Handlebars(TemplateRenderer::new()),
+            TemplateEngine::Tera => Self::Tera(TeraRenderer::new()),
+        };
+    }
+}
+
+impl Renderer for EngineRenderer<'_> {
+    fn load(
+        &mut self,
+        root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        let config = Self::load_config(&find_existing_config_path(root)?, overlays, config_format)?;
+        self.select(config.template_engine);
+        self.set_config(config);
+        match self {
+            Self::Handlebars(renderer) => renderer.load_templates(root),
+            Self::Tera(renderer) => renderer.load_templates(root),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Handlebars(renderer) => renderer.reset(),
+            Self::Tera(renderer) => renderer.reset(),
+        }
+    }
+
+    fn config(&self) -> &RendererConfig {
+        match self {
+            Self::Handlebars(renderer) => renderer.config(),
+            Self::Tera(renderer) => renderer.config(),
+        }
+    }
+
+    fn set_config(&mut self, config: RendererConfig) {
+        match self {
+            Self::Handlebars(renderer) => renderer.set_config(config),
+            Self::Tera(renderer) => renderer.set_config(config),
+        }
+    }
+
+    fn has_metadata(&self) -> bool {
+        match self {
+            Self::Handlebars(renderer) => renderer.has_metadata(),
+            Self::Tera(renderer) => renderer.has_metadata(),
+        }
+    }
+
+    fn render_metadata<W: io::Write>(
+        &self,
+        context: MetadataContext,
+        writer: &mut W,
+    ) -> Result<()> {
+        match self {
+            Self::Handlebars(renderer) => renderer.render_metadata(context, writer),
+            Self::Tera(renderer) => renderer.render_metadata(context, writer),
+        }
+    }
+
+    fn render_file<W: io::Write>(&self, context: FileContext, writer: &mut W) -> Result<()> {
+        match self {
+            Self::Handlebars(renderer) => renderer.render_file(context, writer),
+            Self::Tera(renderer) => renderer.render_file(context, writer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use crate::renderer::context::FileContext;
+    use crate::renderer::template::engine::EngineRenderer;
+    use crate::renderer::tests::fake_file_empty;
+    use crate::renderer::{Renderer, TemplateEngine};
+
+    #[test]
+    fn handlebars_by_default() -> Result<()> {
+        let root = tempdir()?;
+        write_template_dir(root.path(), "")?;
+        let mut renderer = EngineRenderer::new();
+        renderer.load(root.path(), &[], None)?;
+        assert_eq!(renderer.engine(), TemplateEngine::Handlebars);
+        assert_eq!(render_file(&renderer)?, "hbs file_name");
+        Ok(())
+    }
+
+    #[test]
+    fn tera_from_config() -> Result<()> {
+        let root = tempdir()?;
+        write_template_dir(root.path(), "template_engine: tera\n")?;
+        let mut renderer = EngineRenderer::new();
+        renderer.load(root.path(), &[], None)?;
+        assert_eq!(renderer.engine(), TemplateEngine::Tera);
+        assert_eq!(render_file(&renderer)?, "tera file_name");
+        Ok(())
+    }
+
+    #[test]
+    fn tera_applies_overlays() -> Result<()> {
+        let root = tempdir()?;
+        write_template_dir(root.path(), "template_engine: tera\n")?;
+        let overlay_dir = tempdir()?;
+        let overlay = overlay_dir.path().join("overlay.yml");
+        fs::write(
+            &overlay,
+            "by_target:\n  file_name:\n    some_key: some_value\n",
+        )?;
+
+        let mut renderer = EngineRenderer::new();
+        renderer.load(root.path(), &[overlay], None)?;
+        assert_eq!(renderer.engine(), TemplateEngine::Tera);
+        let overlays = renderer.config().overlays.by_target("file_name");
+        assert!(overlays.map_or(false, |overlays| overlays.contains_key("some_key")));
+        Ok(())
+    }
+
+    fn write_template_dir(root: &Path, extra_config: &str) -> Result<()> {
+        fs::write(
+            root.join("config.yml"),
+            ["file_extension: txt\ntype_config: {}\n", extra_config].concat(),
+        )?;
+        fs::write(root.join("file.hbs"), "hbs {{source_file}}")?;
+        fs::write(root.join("file.tera"), "tera {{source_file}}")?;
+        Ok(())
+    }
+
+    fn render_file(renderer: &EngineRenderer) -> Result<String> {
+        let file = fake_file_empty("file_name");
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_file(FileContext::new(&file, renderer.config())?, &mut bytes)?;
+        Ok(String::from_utf8(bytes)?)
+    }
+}
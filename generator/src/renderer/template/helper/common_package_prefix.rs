@@ -0,0 +1,102 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+};
+use serde_json::Value;
+
+use crate::renderer::context;
+
+/// Writes the longest package prefix shared by its params. Each param is a package string, an
+/// array of them, or an array of objects with a `package`, like `package_files_full`.
+///
+/// ```hbs
+/// {{common_package_prefix package_files_full}}
+/// {{common_package_prefix "a.b.c" "a.b.d"}}
+/// ```
+#[derive(Clone, Copy)]
+pub struct CommonPackagePrefix;
+
+impl HelperDef for CommonPackagePrefix {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let mut packages = Vec::new();
+        for param in h.params() {
+            collect_packages(param.value(), &mut packages)?;
+        }
+        out.write(&context::common_package_prefix(packages))?;
+        Ok(())
+    }
+}
+
+fn collect_packages<'a>(value: &'a Value, packages: &mut Vec<&'a str>) -> Result<(), RenderError> {
+    match value {
+        Value::String(package) => packages.push(package),
+        Value::Array(values) => {
+            for value in values {
+                collect_packages(value, packages)?;
+            }
+        }
+        Value::Object(object) => match object.get("package") {
+            Some(Value::String(package)) => packages.push(package),
+            _ => return Err(error_invalid_param(value)),
+        },
+        _ => return Err(error_invalid_param(value)),
+    }
+    Ok(())
+}
+
+fn error_invalid_param(value: &Value) -> RenderError {
+    RenderError::new(format!(
+        "Helper 'common_package_prefix': expected packages, got {}",
+        value
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use handlebars::Handlebars;
+    use serde_json::json;
+
+    use crate::renderer::template::helper::CommonPackagePrefix;
+
+    #[test]
+    fn from_strings() -> Result<()> {
+        assert_eq!(
+            render(r#"{{common_package_prefix "a.b.c" "a.b.d"}}"#, json!({}))?,
+            "a.b"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_package_files() -> Result<()> {
+        let data = json!({
+            "package_files_full": [
+                { "package": "a.b.c", "file_name": "a-b-c.rs" },
+                { "package": "a.b.d", "file_name": "a-b-d.rs" },
+            ]
+        });
+        assert_eq!(
+            render("{{common_package_prefix package_files_full}}", data)?,
+            "a.b"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_param_errors() {
+        assert!(render("{{common_package_prefix 5}}", json!({})).is_err());
+    }
+
+    fn render(template: &str, data: serde_json::Value) -> Result<String> {
+        let mut hbs = Handlebars::new();
+        hbs.register_helper("common_package_prefix", Box::new(CommonPackagePrefix));
+        Ok(hbs.render_template(template, &data)?)
+    }
+}
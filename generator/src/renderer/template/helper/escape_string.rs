@@ -0,0 +1,73 @@
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderError,
+};
+
+use crate::renderer::escape;
+
+/// Writes its first param escaped for a string literal in the style of the second param, one of
+/// `rust`, `c` or `json`.
+///
+/// ```hbs
+/// "{{escape_string default_value "rust"}}"
+/// ```
+#[derive(Clone, Copy)]
+pub struct EscapeString;
+
+impl HelperDef for EscapeString {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = param_str(h, 0, "value")?;
+        let style = param_str(h, 1, "style")?;
+        let escaped = escape::escape_string(value, style)
+            .map_err(|e| RenderError::new(format!("Helper 'escape_string': {}", e)))?;
+        out.write(&escaped)?;
+        Ok(())
+    }
+}
+
+fn param_str<'a>(h: &'a Helper, index: usize, name: &str) -> Result<&'a str, RenderError> {
+    h.param(index)
+        .and_then(|param| param.value().as_str())
+        .ok_or_else(|| {
+            RenderError::new(format!(
+                "Helper 'escape_string': param '{}' must be a string",
+                name
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use handlebars::Handlebars;
+    use serde_json::json;
+
+    use crate::renderer::template::helper::EscapeString;
+
+    #[test]
+    fn rust_style() -> Result<()> {
+        let data = json!({ "value": "say \"hi\"\n" });
+        assert_eq!(
+            render(r#"{{escape_string value "rust"}}"#, data)?,
+            r#"say \"hi\"\n"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_style_errors() {
+        assert!(render("{{escape_string value}}", json!({ "value": "" })).is_err());
+    }
+
+    fn render(template: &str, data: serde_json::Value) -> Result<String> {
+        let mut hbs = Handlebars::new();
+        hbs.register_helper("escape_string", Box::new(EscapeString));
+        Ok(hbs.render_template(template, &data)?)
+    }
+}
@@ -1,5 +1,9 @@
+mod common_package_prefix;
+mod escape_string;
 mod if_equals;
 mod indent;
 
+pub use common_package_prefix::CommonPackagePrefix;
+pub use escape_string::EscapeString;
 pub use if_equals::IfEquals;
 pub use indent::Indent;
@@ -1,34 +1,80 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use crate::in_out_generator::InOutGenerator;
-use crate::renderer::template::renderer::TemplateRenderer;
+use crate::inline_template_config::InlineTemplateConfig;
+use crate::renderer::{Renderer, RendererConfig};
 use crate::{Config, InOutConfig};
+use prost_types::FileDescriptorSet;
+use std::path::Path;
 
+mod engine;
 mod helper;
 mod renderer;
+mod tera_renderer;
+
+pub use engine::EngineRenderer;
+pub use renderer::TemplateRenderer;
+pub use tera_renderer::TeraRenderer;
 
 pub const TEMPLATE_EXT: &'static str = "hbs";
+pub const TERA_TEMPLATE_EXT: &'static str = "tera";
 pub const METADATA_TEMPLATE_NAME: &'static str = "metadata";
 pub const FILE_TEMPLATE_NAME: &'static str = "file";
 
-pub fn generate(config: &Config) -> Result<()> {
-    Generator {
+pub const INLINE_TEMPLATE_FILE_EXTENSION: &'static str = "txt";
+
+/// Name of the template at `file` under `root`: its path relative to the root without the
+/// extension, separated by '/', e.g. `sub/foo` for `root/sub/foo.hbs`.
+fn template_name(root: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(root).ok()?.with_extension("");
+    let components = relative
+        .components()
+        .map(|c| c.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(components.join("/"))
+}
+
+/// Renders every --template, then every --template-inline. With `--diff`, returns true if any
+/// output differs from the existing files.
+pub fn generate(config: &Config, descriptor_set: &FileDescriptorSet) -> Result<bool> {
+    let differs = Generator {
+        config,
+        renderer: EngineRenderer::new(),
+    }
+    .generate_from_descriptor_set(descriptor_set)?;
+    Ok(generate_inline(config, descriptor_set)? || differs)
+}
+
+/// Renders each --template-inline TEMPLATE as the file template of a default config.
+fn generate_inline(config: &Config, descriptor_set: &FileDescriptorSet) -> Result<bool> {
+    InlineGenerator {
         config,
         renderer: TemplateRenderer::new(),
     }
-    .generate()
+    .generate_from_descriptor_set(descriptor_set)
+}
+
+fn inline_renderer(inline: &InlineTemplateConfig) -> Result<TemplateRenderer<'static>> {
+    let mut renderer_config = RendererConfig::default();
+    renderer_config.file_extension = INLINE_TEMPLATE_FILE_EXTENSION.to_owned();
+    let mut renderer = TemplateRenderer::new();
+    renderer.set_config(renderer_config);
+    renderer
+        .load_file_template_string(&inline.template)
+        .context("--template-inline")?;
+    Ok(renderer)
 }
 
 struct Generator<'a> {
     config: &'a Config,
-    renderer: TemplateRenderer<'a>,
+    renderer: EngineRenderer<'a>,
 }
-impl<'a> InOutGenerator<TemplateRenderer<'a>> for Generator<'a> {
+impl<'a> InOutGenerator<EngineRenderer<'a>> for Generator<'a> {
     fn name(&self) -> &str {
         "Templates"
     }
 
-    fn renderer(&mut self) -> &mut TemplateRenderer<'a> {
+    fn renderer(&mut self) -> &mut EngineRenderer<'a> {
         &mut self.renderer
     }
 
@@ -40,3 +86,94 @@ impl<'a> InOutGenerator<TemplateRenderer<'a>> for Generator<'a> {
         self.app_config().templates.clone()
     }
 }
+
+struct InlineGenerator<'a> {
+    config: &'a Config,
+    renderer: TemplateRenderer<'static>,
+}
+impl<'a> InOutGenerator<TemplateRenderer<'static>> for InlineGenerator<'a> {
+    fn name(&self) -> &str {
+        "Inline templates"
+    }
+
+    fn renderer(&mut self) -> &mut TemplateRenderer<'static> {
+        &mut self.renderer
+    }
+
+    fn app_config(&self) -> &Config {
+        &self.config
+    }
+
+    fn in_out_configs(&self) -> Vec<InOutConfig> {
+        self.app_config()
+            .inline_templates
+            .iter()
+            .map(|x| x.into())
+            .collect::<Vec<InOutConfig>>()
+    }
+
+    /// There is no input folder, the renderer is created from the inline template.
+    fn load_renderer(&mut self, index: usize, _: &InOutConfig) -> Result<()> {
+        self.renderer = inline_renderer(&self.config.inline_templates[index])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use prost_types::{FileDescriptorProto, FileDescriptorSet};
+    use tempfile::tempdir;
+
+    use crate::inline_template_config::InlineTemplateConfig;
+    use crate::renderer::template::generate_inline;
+    use crate::Config;
+
+    #[test]
+    fn renders_inline_template() -> Result<()> {
+        let test_dir = tempdir()?;
+        let descriptor_set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("sub/file.proto".to_owned()),
+                ..Default::default()
+            }],
+        };
+        let mut config = Config::default();
+        let output = test_dir.path().join("output");
+        config.inline_templates = vec![InlineTemplateConfig {
+            template: "source: {{source_file}}".to_owned(),
+            output: output.clone(),
+        }];
+
+        generate_inline(&config, &descriptor_set)?;
+        let contents = fs::read_to_string(output.join("sub/file.txt"))?;
+        assert!(contents.ends_with("source: sub/file.proto"), "{}", contents);
+        Ok(())
+    }
+
+    #[test]
+    fn inline_template_diff_leaves_output_untouched() -> Result<()> {
+        let test_dir = tempdir()?;
+        let descriptor_set = FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("file.proto".to_owned()),
+                ..Default::default()
+            }],
+        };
+        let mut config = Config::default();
+        config.diff = true;
+        let output = test_dir.path().join("output");
+        fs::create_dir_all(&output)?;
+        fs::write(output.join("file.txt"), "old")?;
+        config.inline_templates = vec![InlineTemplateConfig {
+            template: "new".to_owned(),
+            output: output.clone(),
+        }];
+
+        assert!(generate_inline(&config, &descriptor_set)?);
+        assert_eq!(fs::read_to_string(output.join("file.txt"))?, "old");
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use crate::renderer::context::{FileContext, MetadataContext};
-use crate::renderer::template::{helper, FILE_TEMPLATE_NAME, METADATA_TEMPLATE_NAME, TEMPLATE_EXT};
+use crate::renderer::template::{helper, template_name, TEMPLATE_EXT};
 use crate::renderer::{find_existing_config_path, Renderer, RendererConfig};
-use crate::DisplayNormalized;
+use crate::{ConfigFormat, DisplayNormalized};
 use anyhow::{Context, Result};
 use handlebars::Handlebars;
 use serde::Serialize;
@@ -23,6 +23,11 @@ impl TemplateRenderer<'_> {
         let mut hbs = Handlebars::new();
         hbs.register_helper("indent", Box::new(helper::Indent));
         hbs.register_helper("if_equals", Box::new(helper::IfEquals));
+        hbs.register_helper(
+            "common_package_prefix",
+            Box::new(helper::CommonPackagePrefix),
+        );
+        hbs.register_helper("escape_string", Box::new(helper::EscapeString));
         Self {
             hbs,
             config: Default::default(),
@@ -39,12 +44,13 @@ impl TemplateRenderer<'_> {
 
     #[allow(dead_code)]
     fn load_metadata_template_string(&mut self, template: impl AsRef<str>) -> Result<()> {
-        self.load_template_string(METADATA_TEMPLATE_NAME, template)
+        let name = self.config.metadata_template_name.clone();
+        self.load_template_string(&name, template)
     }
 
-    #[allow(dead_code)]
-    fn load_file_template_string(&mut self, template: impl AsRef<str>) -> Result<()> {
-        self.load_template_string(FILE_TEMPLATE_NAME, template)
+    pub(crate) fn load_file_template_string(&mut self, template: impl AsRef<str>) -> Result<()> {
+        let name = self.config.file_template_name.clone();
+        self.load_template_string(&name, template)
     }
 
     #[allow(dead_code)]
@@ -58,7 +64,7 @@ impl TemplateRenderer<'_> {
     pub fn load_templates(&mut self, root: &Path) -> Result<()> {
         for entry in WalkDir::new(root)
             .follow_links(false)
-            .max_depth(1)
+            .max_depth(self.config.template_scan_depth)
             .into_iter()
             .filter_map(|r| r.ok())
             .filter(|e| e.file_type().is_file())
@@ -69,12 +75,9 @@ impl TemplateRenderer<'_> {
                 _ => continue,
             };
 
-            let template_name = match file.file_stem() {
+            let template_name = match template_name(root, file) {
                 None => continue,
-                Some(file_name) => match file_name.to_str() {
-                    None => continue,
-                    Some(name) => name.to_owned(),
-                },
+                Some(name) => name,
             };
 
             self.load_template_file(&template_name, file)?;
@@ -108,11 +111,19 @@ impl TemplateRenderer<'_> {
         &self,
         template: &str,
         data: &S,
-        writer: W,
+        mut writer: W,
     ) -> Result<()> {
-        self.hbs
-            .render_to_write(template, data, writer)
-            .with_context(|| render_error_context(template, data))?;
+        if self.config.trim_whitespace {
+            let rendered = self
+                .hbs
+                .render(template, data)
+                .with_context(|| render_error_context(template, data))?;
+            writer.write_all(trim_whitespace(&rendered).as_bytes())?;
+        } else {
+            self.hbs
+                .render_to_write(template, data, writer)
+                .with_context(|| render_error_context(template, data))?;
+        }
         Ok(())
     }
 }
@@ -125,11 +136,21 @@ impl Renderer for TemplateRenderer<'_> {
     ///     root/metadata.hbs (optional)
     /// ```
     ///
+    /// The file and metadata template names can be changed with `file_template_name` and
+    /// `metadata_template_name` in the config.
+    ///
     /// Any other `*.hbs` files will also be loaded as templates based on the file name, and can
-    /// be used in other templates as partials with the syntax {{> file_name}}.
+    /// be used in other templates as partials with the syntax {{> file_name}}. Subdirectories up
+    /// to `template_scan_depth` are also searched, e.g. `root/sub/foo.hbs` is {{> sub/foo}}.
     /// (See also: https://handlebarsjs.com/guide/partials.html)
-    fn load(&mut self, root: &Path, _: &[PathBuf]) -> Result<()> {
-        self.config = Self::load_config(&find_existing_config_path(root)?, &[])?;
+    fn load(
+        &mut self,
+        root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        self.config =
+            Self::load_config(&find_existing_config_path(root)?, overlays, config_format)?;
         self.load_templates(root)?;
         Ok(())
     }
@@ -142,8 +163,12 @@ impl Renderer for TemplateRenderer<'_> {
         &self.config
     }
 
+    fn set_config(&mut self, config: RendererConfig) {
+        self.config = config;
+    }
+
     fn has_metadata(&self) -> bool {
-        self.hbs.has_template(METADATA_TEMPLATE_NAME)
+        self.hbs.has_template(&self.config.metadata_template_name)
     }
 
     fn render_metadata<W: io::Write>(
@@ -151,15 +176,38 @@ impl Renderer for TemplateRenderer<'_> {
         context: MetadataContext,
         writer: &mut W,
     ) -> Result<()> {
-        self.render_to_write(METADATA_TEMPLATE_NAME, &context, writer)
+        self.render_to_write(&self.config.metadata_template_name, &context, writer)
     }
 
     fn render_file<W: io::Write>(&self, context: FileContext, writer: &mut W) -> Result<()> {
-        self.render_to_write(FILE_TEMPLATE_NAME, &context, writer)
+        self.render_to_write(&self.config.file_template_name, &context, writer)
     }
 }
 
-fn render_error_context<S: Serialize>(name: &str, data: &S) -> String {
+/// Strips trailing whitespace from each line and collapses runs of blank lines into one.
+pub(super) fn trim_whitespace(rendered: &str) -> String {
+    let mut result = String::with_capacity(rendered.len());
+    let mut previous_blank = false;
+    for line in rendered.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            if previous_blank {
+                continue;
+            }
+            previous_blank = true;
+        } else {
+            previous_blank = false;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    if !rendered.ends_with('\n') {
+        result.pop();
+    }
+    result
+}
+
+pub(super) fn render_error_context<S: Serialize>(name: &str, data: &S) -> String {
     format!(
         "Failed to render template '{}' for data: {}",
         name,
@@ -169,16 +217,21 @@ fn render_error_context<S: Serialize>(name: &str, data: &S) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::renderer::context::{EnumContext, FieldContext, FileContext, MessageContext};
+    use crate::renderer::context::{
+        EnumContext, FieldContext, FileContext, MessageContext, MetadataContext,
+    };
     use crate::renderer::template::renderer::TemplateRenderer;
-    use crate::renderer::template::FILE_TEMPLATE_NAME;
+    use crate::renderer::template::{FILE_TEMPLATE_NAME, METADATA_TEMPLATE_NAME};
     use crate::renderer::tests::{fake_field, fake_file, fake_file_empty, fake_message};
     use crate::renderer::{primitive, Renderer, RendererConfig};
     use anyhow::Result;
     use prost_types::{
         DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FieldOptions, FileOptions,
     };
     use std::collections::HashMap;
+    use std::fs;
+    use tempfile::tempdir;
 
     #[test]
     fn file_template() -> Result<()> {
@@ -211,6 +264,63 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn configured_file_template_name() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.file_template_name = "file.impl".to_owned();
+        let mut renderer = TemplateRenderer::with_config(config.clone());
+        renderer.load_template_string(FILE_TEMPLATE_NAME, "default")?;
+        renderer.load_template_string("file.header", "header")?;
+        renderer.load_template_string("file.impl", "impl {{source_file}}")?;
+
+        let mut bytes = Vec::<u8>::new();
+        let file = fake_file_empty("file_name");
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "impl file_name");
+        Ok(())
+    }
+
+    #[test]
+    fn configured_metadata_template_name() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.metadata_template_name = "metadata.impl".to_owned();
+        let mut renderer = TemplateRenderer::with_config(config);
+        renderer.load_template_string(METADATA_TEMPLATE_NAME, "default")?;
+        assert!(!renderer.has_metadata());
+
+        renderer.load_template_string("metadata.impl", "impl")?;
+        assert!(renderer.has_metadata());
+        Ok(())
+    }
+
+    #[test]
+    fn loads_profile_from_configured_template_names() -> Result<()> {
+        let root = tempdir()?;
+        fs::write(
+            root.path().join("config.yml"),
+            "type_config: {}\nfile_template_name: file.b\nmetadata_template_name: metadata.b\n",
+        )?;
+        fs::write(root.path().join("file.a.hbs"), "a {{source_file}}")?;
+        fs::write(root.path().join("file.b.hbs"), "b {{source_file}}")?;
+        fs::write(root.path().join("metadata.a.hbs"), "a metadata")?;
+        fs::write(root.path().join("metadata.b.hbs"), "b metadata")?;
+        let mut renderer = TemplateRenderer::new();
+        renderer.load(root.path(), &[], None)?;
+        assert!(renderer.has_metadata());
+
+        let mut bytes = Vec::<u8>::new();
+        let file = fake_file_empty("file_name");
+        let context = FileContext::new(&file, renderer.config())?;
+        renderer.render_file(context, &mut bytes)?;
+        assert_eq!(String::from_utf8(bytes)?, "b file_name");
+
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_metadata(MetadataContext::new(), &mut bytes)?;
+        assert_eq!(String::from_utf8(bytes)?, "b metadata");
+        Ok(())
+    }
+
     #[test]
     fn import_template() -> Result<()> {
         let config = RendererConfig::default();
@@ -248,6 +358,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn import_output_file_path() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.file_extension = "rs".to_owned();
+        let mut renderer = TemplateRenderer::with_config(config.clone());
+        renderer.load_file_template_string("{{#each imports}}{{> import}}{{/each}}")?;
+        load_import_template_string(&mut renderer, "{{output_file_path}}|{{file_extension}}")?;
+
+        let mut file = fake_file_empty("file_name");
+        file.dependency.push("root/test/value.proto".to_owned());
+
+        let mut bytes = Vec::<u8>::new();
+        let context = FileContext::new(&file, &config)?;
+        renderer.render_file(context, &mut bytes)?;
+        assert_eq!(String::from_utf8(bytes)?, "root/test/value.rs|rs");
+        Ok(())
+    }
+
+    #[test]
+    fn builtin_file_options() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut renderer = TemplateRenderer::with_config(config.clone());
+        renderer.load_file_template_string(
+            "{{options.csharp_namespace}}|{{options.go_package}}|{{#if options.java_package}}java{{/if}}",
+        )?;
+
+        let mut file = fake_file_empty("file_name");
+        file.options = Some(FileOptions {
+            csharp_namespace: Some("Some.Namespace".to_owned()),
+            go_package: Some("example.com/some/package".to_owned()),
+            ..Default::default()
+        });
+
+        let mut bytes = Vec::<u8>::new();
+        let context = FileContext::new(&file, &config)?;
+        renderer.render_file(context, &mut bytes)?;
+        assert_eq!(
+            String::from_utf8(bytes)?,
+            "Some.Namespace|example.com/some/package|"
+        );
+        Ok(())
+    }
+
     #[test]
     fn message_template() -> Result<()> {
         let config = RendererConfig::default();
@@ -273,6 +426,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn enum_value_numbers() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut renderer = TemplateRenderer::with_config(config);
+        load_enum_template(
+            &mut renderer,
+            "{{#each values}}{{name}} = {{number}};{{/each}}",
+        )?;
+
+        let proto = fake_enum("SomeEnum", &[(&"ZERO", 0), (&"FIVE", 5), (&"NEGATIVE", -1)]);
+        let result = render_enum(&mut renderer, &proto)?;
+        assert_eq!(result, "ZERO = 0;FIVE = 5;NEGATIVE = -1;");
+        Ok(())
+    }
+
+    #[test]
+    fn message_template_nested_enums() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut renderer = TemplateRenderer::with_config(config);
+        load_message_template(
+            &mut renderer,
+            "{{name}}{{#each nested_enums}}:{{name}}{{/each}}".to_owned(),
+        )?;
+
+        let mut message = fake_message("MsgName", vec![]);
+        message
+            .enum_type
+            .push(fake_enum::<&str, &str>("InnerEnum", &[]));
+
+        let result = render_message(&mut renderer, &message)?;
+        assert_eq!(result, "MsgName:InnerEnum");
+        Ok(())
+    }
+
     #[test]
     fn field_template() -> Result<()> {
         let field_name = "field_name";
@@ -299,6 +486,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn field_builtin_options() -> Result<()> {
+        let mut renderer = TemplateRenderer::with_config(RendererConfig::default());
+        load_field_template(
+            &mut renderer,
+            "{{field_name}}{{#if options.deprecated}} deprecated{{/if}}",
+        )?;
+
+        let mut field = fake_field("old", primitive::FLOAT);
+        field.options = Some(FieldOptions {
+            deprecated: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(
+            render_field(&mut renderer, &field, None, None)?,
+            "old deprecated"
+        );
+        let field = fake_field("new", primitive::FLOAT);
+        assert_eq!(render_field(&mut renderer, &field, None, None)?, "new");
+        Ok(())
+    }
+
     #[test]
     fn field_gets_package_from_file() -> Result<()> {
         let config = RendererConfig::default();
@@ -321,6 +530,71 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn trim_whitespace() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.trim_whitespace = true;
+        let mut renderer = TemplateRenderer::with_config(config.clone());
+        renderer.load_file_template_string(
+            "start  \n\n\n{{#each messages}}\n{{name}}\t\n\n{{/each}}\n\nend\n",
+        )?;
+
+        let file = fake_file(
+            "file_name",
+            vec![],
+            vec![fake_message("msg0", vec![]), fake_message("msg1", vec![])],
+        );
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "start\n\nmsg0\n\nmsg1\n\nend\n");
+        Ok(())
+    }
+
+    #[test]
+    fn no_trim_whitespace_by_default() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut renderer = TemplateRenderer::with_config(config.clone());
+        renderer.load_file_template_string("start  \n\n\nend")?;
+
+        let mut bytes = Vec::<u8>::new();
+        let file = fake_file_empty("file_name");
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "start  \n\n\nend");
+        Ok(())
+    }
+
+    #[test]
+    fn nested_partials_with_scan_depth() -> Result<()> {
+        let root = tempdir()?;
+        fs::create_dir(root.path().join("sub"))?;
+        fs::write(root.path().join("file.hbs"), "[{{> sub/foo}}]")?;
+        fs::write(root.path().join("sub/foo.hbs"), "{{source_file}}")?;
+        let mut config = RendererConfig::default();
+        config.template_scan_depth = 2;
+        let mut renderer = TemplateRenderer::with_config(config.clone());
+        renderer.load_templates(root.path())?;
+
+        let mut bytes = Vec::<u8>::new();
+        let file = fake_file_empty("file_name");
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "[file_name]");
+        Ok(())
+    }
+
+    #[test]
+    fn nested_partials_skipped_by_default() -> Result<()> {
+        let root = tempdir()?;
+        fs::create_dir(root.path().join("sub"))?;
+        fs::write(root.path().join("sub/foo.hbs"), "foo")?;
+        let mut renderer = TemplateRenderer::with_config(RendererConfig::default());
+        renderer.load_templates(root.path())?;
+        assert!(!renderer.hbs.has_template("sub/foo"));
+        Ok(())
+    }
+
     mod metadata {
         use std::collections::HashSet;
         use std::io;
@@ -369,11 +643,7 @@ mod tests {
                 Vec::new(),
                 MetadataContext::with_relative_dir(&PathBuf::from(root))?,
             )?;
-            // Can't rely on order.
-            let dirs = result.split(":::").collect::<Vec<&str>>();
-            assert_eq!(dirs.len(), 2);
-            assert!(dirs.contains(&"sub0"));
-            assert!(dirs.contains(&"sub1"));
+            assert_eq!(result, "sub0:::sub1");
             Ok(())
         }
 
@@ -501,6 +771,7 @@ mod tests {
             package,
             message_name,
             &HashMap::new(),
+            false,
             &renderer.config,
         )?;
         renderer.render_to_string(FIELD_TEMPLATE_NAME, &context)
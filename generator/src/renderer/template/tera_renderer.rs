@@ -0,0 +1,251 @@
+use crate::renderer::context::{FileContext, MetadataContext};
+use crate::renderer::template::renderer::{render_error_context, trim_whitespace};
+use crate::renderer::template::{template_name, TERA_TEMPLATE_EXT};
+use crate::renderer::{find_existing_config_path, Renderer, RendererConfig};
+use crate::{ConfigFormat, DisplayNormalized};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tera::Tera;
+use walkdir::WalkDir;
+
+/// Renders final output files the same way as the `TemplateRenderer`, but with templates written
+/// in the Tera template language (https://tera.netlify.app/) instead of handlebars.
+pub struct TeraRenderer {
+    tera: Tera,
+    config: RendererConfig,
+}
+
+impl TeraRenderer {
+    pub fn new() -> Self {
+        Self {
+            tera: Tera::default(),
+            config: Default::default(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_config(config: RendererConfig) -> Self {
+        Self {
+            tera: Tera::default(),
+            config,
+        }
+    }
+
+    /// Loads every `*.tera` file inside `root` up to `template_scan_depth`, named by its path
+    /// relative to `root` without the extension. Templates can be used in each other with e.g.
+    /// {% include "message" %} or {% include "sub/message" %}.
+    pub fn load_templates(&mut self, root: &Path) -> Result<()> {
+        let mut templates = Vec::new();
+        for entry in WalkDir::new(root)
+            .follow_links(false)
+            .max_depth(self.config.template_scan_depth)
+            .into_iter()
+            .filter_map(|r| r.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let file = entry.path();
+            match file.extension() {
+                Some(ext) if ext == TERA_TEMPLATE_EXT => {}
+                _ => continue,
+            };
+
+            let template_name = match template_name(root, file) {
+                None => continue,
+                Some(name) => name,
+            };
+
+            let template = fs::read_to_string(file).with_context(|| {
+                format!(
+                    "Failed to read '{}' template at path: {}",
+                    template_name,
+                    file.display_normalized()
+                )
+            })?;
+            templates.push((template_name, template));
+        }
+        // Added together so that templates can include each other regardless of load order.
+        self.tera
+            .add_raw_templates(templates)
+            .with_context(|| format!("Failed to load templates in: {}", root.display_normalized()))
+    }
+
+    #[allow(dead_code)]
+    fn load_template_string(&mut self, name: &str, template: impl AsRef<str>) -> Result<()> {
+        self.tera
+            .add_raw_template(name, template.as_ref())
+            .with_context(|| format!("Failed to load '{}' template from string", name))
+    }
+
+    fn has_template(&self, name: &str) -> bool {
+        self.tera
+            .get_template_names()
+            .any(|template| template == name)
+    }
+
+    fn render_to_write<S: Serialize, W: io::Write>(
+        &self,
+        template: &str,
+        data: &S,
+        mut writer: W,
+    ) -> Result<()> {
+        let context = tera::Context::from_serialize(data)
+            .with_context(|| render_error_context(template, data))?;
+        let rendered = self
+            .tera
+            .render(template, &context)
+            .with_context(|| render_error_context(template, data))?;
+        if self.config.trim_whitespace {
+            writer.write_all(trim_whitespace(&rendered).as_bytes())?;
+        } else {
+            writer.write_all(rendered.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for TeraRenderer {
+    /// Loads config and templates from the same root path with the following names:
+    /// ```txt
+    ///     root/config.json
+    ///     root/file.tera
+    ///     root/metadata.tera (optional)
+    /// ```
+    ///
+    /// As with the TemplateRenderer, the file and metadata template names can be changed with
+    /// `file_template_name` and `metadata_template_name` in the config.
+    fn load(
+        &mut self,
+        root: &Path,
+        overlays: &[PathBuf],
+        config_format: Option<ConfigFormat>,
+    ) -> Result<()> {
+        self.config =
+            Self::load_config(&find_existing_config_path(root)?, overlays, config_format)?;
+        self.load_templates(root)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.tera = Tera::default();
+    }
+
+    fn config(&self) -> &RendererConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: RendererConfig) {
+        self.config = config;
+    }
+
+    fn has_metadata(&self) -> bool {
+        self.has_template(&self.config.metadata_template_name)
+    }
+
+    fn render_metadata<W: io::Write>(
+        &self,
+        context: MetadataContext,
+        writer: &mut W,
+    ) -> Result<()> {
+        self.render_to_write(&self.config.metadata_template_name, &context, writer)
+    }
+
+    fn render_file<W: io::Write>(&self, context: FileContext, writer: &mut W) -> Result<()> {
+        self.render_to_write(&self.config.file_template_name, &context, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+
+    use crate::renderer::context::{FileContext, MetadataContext};
+    use crate::renderer::template::tera_renderer::TeraRenderer;
+    use crate::renderer::template::{FILE_TEMPLATE_NAME, METADATA_TEMPLATE_NAME};
+    use crate::renderer::tests::{fake_field, fake_file, fake_message};
+    use crate::renderer::{primitive, Renderer, RendererConfig};
+
+    #[test]
+    fn file_template() -> Result<()> {
+        let config = RendererConfig::default();
+        let mut renderer = TeraRenderer::with_config(config.clone());
+        renderer.load_template_string(
+            FILE_TEMPLATE_NAME,
+            "{{source_file}}{% for message in messages %}:{{message.name}}{% for field in message.fields %}.{{field.field_name}}{% endfor %}{% endfor %}",
+        )?;
+
+        let message = fake_message("Msg", vec![fake_field("field0", primitive::FLOAT)]);
+        let file = fake_file("file_name", vec![], vec![message]);
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "file_name:Msg.field0");
+        Ok(())
+    }
+
+    #[test]
+    fn loads_templates_with_includes() -> Result<()> {
+        let root = tempdir()?;
+        fs::write(
+            root.path().join("file.tera"),
+            r#"{% for message in messages %}{% include "message" %}{% endfor %}"#,
+        )?;
+        fs::write(root.path().join("message.tera"), "[{{message.name}}]")?;
+        fs::write(root.path().join("ignored.hbs"), "{{name}}")?;
+        let config = RendererConfig::default();
+        let mut renderer = TeraRenderer::with_config(config.clone());
+        renderer.load_templates(root.path())?;
+
+        let file = fake_file(
+            "file_name",
+            vec![],
+            vec![fake_message("Msg0", vec![]), fake_message("Msg1", vec![])],
+        );
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "[Msg0][Msg1]");
+        assert!(!renderer.has_template("ignored"));
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_template() -> Result<()> {
+        let mut renderer = TeraRenderer::with_config(RendererConfig::default());
+        assert!(!renderer.has_metadata());
+        renderer.load_template_string(
+            METADATA_TEMPLATE_NAME,
+            "{% for name in file_names %}{{name}};{% endfor %}",
+        )?;
+        assert!(renderer.has_metadata());
+
+        let mut context = MetadataContext::new();
+        context.push_file("file0.proto".as_ref())?;
+        context.push_file("file1.proto".as_ref())?;
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_metadata(context, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "file0;file1;");
+        Ok(())
+    }
+
+    #[test]
+    fn trim_whitespace() -> Result<()> {
+        let mut config = RendererConfig::default();
+        config.trim_whitespace = true;
+        let mut renderer = TeraRenderer::with_config(config.clone());
+        renderer.load_template_string(FILE_TEMPLATE_NAME, "start  \n\n\nend\n")?;
+
+        let file = fake_file("file_name", vec![], vec![]);
+        let mut bytes = Vec::<u8>::new();
+        renderer.render_file(FileContext::new(&file, &config)?, &mut bytes)?;
+
+        assert_eq!(String::from_utf8(bytes)?, "start\n\nend\n");
+        Ok(())
+    }
+}
@@ -1,11 +1,12 @@
 use crate::lang_config::LangConfig;
 use crate::Config;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use prost::Message;
 use prost_types::FileDescriptorSet;
 use std::borrow::Borrow;
-use std::fs;
 use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+use walkdir::WalkDir;
 
 pub fn unquote_arg(arg: &str) -> String {
     arg[1..arg.len() - 1].to_owned()
@@ -36,6 +37,19 @@ pub fn create_proto_out_dirs<C: Borrow<LangConfig>>(configs: &[C]) -> Result<()>
     Ok(())
 }
 
+/// Opens an existing file to write at its end.
+pub fn append_file_or_error(path: &Path) -> Result<fs::File> {
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .with_context(|| {
+            format!(
+                "Failed to open file for appending at path '{}'",
+                path.display_normalized()
+            )
+        })
+}
+
 pub fn create_dir_or_error(path: &Path) -> Result<()> {
     fs::create_dir_all(path).with_context(|| {
         format!(
@@ -59,6 +73,40 @@ pub fn create_file_or_error(path: &Path) -> Result<fs::File> {
     })
 }
 
+/// Thread pool with exactly `jobs` threads for running work in parallel.
+pub fn thread_pool(jobs: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .with_context(|| format!("Failed to create thread pool with {} threads", jobs))
+}
+
+/// Copies the only file under `dir` to `writer`. Errors if `dir` doesn't contain exactly one file.
+pub fn write_single_file<W: io::Write>(dir: &Path, writer: &mut W) -> Result<()> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    if files.len() != 1 {
+        return Err(anyhow!(
+            "Expected exactly one rendered file, but found {}.",
+            files.len()
+        ));
+    }
+    let bytes = fs::read(&files[0]).with_context(|| {
+        format!(
+            "Failed to read rendered file at path '{}'",
+            files[0].display_normalized()
+        )
+    })?;
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
 pub fn path_parent_or_error(path: &Path) -> Result<&Path> {
     path.parent().ok_or(anyhow!(
         "File path has no parent: '{}'.",
@@ -128,8 +176,50 @@ pub fn path_as_absolute<P: AsRef<Path>>(
     }
 }
 
+/// Replaces each `${NAME}` in `value` with the environment variable `NAME`, or each
+/// `${NAME:-default}` with `default` when the variable is not set. Errors on a variable that is
+/// not set and has no default.
+pub fn expand_env_vars(value: &str) -> Result<String> {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or_else(|| anyhow!("Unclosed '${{' in '{}'.", value))?;
+        let expression = &rest[start + 2..end];
+        let (name, default) = match expression.split_once(":-") {
+            None => (expression, None),
+            Some((name, default)) => (name, Some(default)),
+        };
+        match (env::var(name), default) {
+            (Ok(var), _) => result.push_str(&var),
+            (Err(_), Some(default)) => result.push_str(default),
+            (Err(_), None) => bail!(
+                "Environment variable '{}' used in '{}' is not set. Use '${{{}:-default}}' to fall back to a default.",
+                name,
+                value,
+                name
+            ),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Loads the descriptor set written by protoc, or the `--buf-image` in its place. Buf images share
+/// the `FileDescriptorSet` wire format, and buf's extra fields are skipped as unknown fields.
 pub(crate) fn load_descriptor_set(config: &Config) -> Result<FileDescriptorSet> {
-    let path = &config.descriptor_set_path;
+    let path = config
+        .buf_image
+        .as_ref()
+        .unwrap_or(&config.descriptor_set_path);
+    read_descriptor_set(path)
+}
+
+pub(crate) fn read_descriptor_set(path: &Path) -> Result<FileDescriptorSet> {
     let bytes = fs::read(&path).with_context(|| {
         format!(
             "Failed to read file descriptor set at path: {}",
@@ -193,6 +283,40 @@ mod tests {
         }
     }
 
+    mod write_single_file {
+        use crate::util::write_single_file;
+        use anyhow::Result;
+        use std::fs;
+        use tempfile::tempdir;
+
+        #[test]
+        fn writes_only_file() -> Result<()> {
+            let dir = tempdir()?;
+            fs::create_dir(dir.path().join("sub"))?;
+            fs::write(dir.path().join("sub").join("file.txt"), "contents")?;
+            let mut output = Vec::new();
+            write_single_file(dir.path(), &mut output)?;
+            assert_eq!(output, b"contents");
+            Ok(())
+        }
+
+        #[test]
+        fn errors_with_multiple_files() -> Result<()> {
+            let dir = tempdir()?;
+            fs::write(dir.path().join("first.txt"), "first")?;
+            fs::write(dir.path().join("second.txt"), "second")?;
+            assert!(write_single_file(dir.path(), &mut Vec::new()).is_err());
+            Ok(())
+        }
+
+        #[test]
+        fn errors_without_files() -> Result<()> {
+            let dir = tempdir()?;
+            assert!(write_single_file(dir.path(), &mut Vec::new()).is_err());
+            Ok(())
+        }
+    }
+
     mod path_as_absolute {
         use crate::util::path_as_absolute;
         use crate::DisplayNormalized;
@@ -233,4 +357,40 @@ mod tests {
             Ok(())
         }
     }
+
+    mod expand_env_vars {
+        use crate::util::expand_env_vars;
+        use anyhow::Result;
+        use std::env;
+
+        #[test]
+        fn defined_variable() -> Result<()> {
+            env::set_var("PROTOX_TEST_EXPAND_DEFINED", "/home/user");
+            let result = expand_env_vars("${PROTOX_TEST_EXPAND_DEFINED}/header.txt")?;
+            assert_eq!(result, "/home/user/header.txt");
+            Ok(())
+        }
+
+        #[test]
+        fn default_fallback() -> Result<()> {
+            env::remove_var("PROTOX_TEST_EXPAND_UNSET");
+            let result =
+                expand_env_vars("${PROTOX_TEST_EXPAND_UNSET:-out}/${PROTOX_TEST_EXPAND_UNSET:-}x")?;
+            assert_eq!(result, "out/x");
+            Ok(())
+        }
+
+        #[test]
+        fn undefined_variable_errors() {
+            env::remove_var("PROTOX_TEST_EXPAND_UNDEFINED");
+            let error = expand_env_vars("${PROTOX_TEST_EXPAND_UNDEFINED}/header.txt").unwrap_err();
+            assert!(error.to_string().contains("PROTOX_TEST_EXPAND_UNDEFINED"));
+        }
+
+        #[test]
+        fn no_variables() -> Result<()> {
+            assert_eq!(expand_env_vars("plain/$path")?, "plain/$path");
+            Ok(())
+        }
+    }
 }
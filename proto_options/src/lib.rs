@@ -1,4 +1,4 @@
-use prost::ExtensionRegistry;
+use prost::{Extension, ExtensionRegistry};
 use rhai;
 // use rhai::exported_module;
 
@@ -29,13 +29,33 @@ pub fn register_script_apis(engine: &mut rhai::Engine) {
     register_user_script_apis(engine);
 }
 
+/// Field numbers of every extension in `create_extension_registry`, so generators can tell them
+/// apart from options they don't know about.
+pub fn registered_field_numbers() -> Vec<u32> {
+    let mut numbers = vec![
+        extensions::NATIVE_TYPE.field_tag(),
+        extensions::ENUM_VALUE_KEY_VALUE.field_tag(),
+        extensions::OUTPUT_FILE_NAME.field_tag(),
+    ];
+    numbers.extend(user_field_numbers());
+    numbers
+}
+
 fn register_builtin_extensions(registry: &mut ExtensionRegistry) {
     registry.register(extensions::NATIVE_TYPE);
+    registry.register(extensions::ENUM_VALUE_KEY_VALUE);
+    registry.register(extensions::OUTPUT_FILE_NAME);
 }
 
 #[allow(unused)]
 fn register_user_extensions(registry: &mut ExtensionRegistry) {}
 
+/// Field numbers of the extensions in `register_user_extensions`.
+#[allow(unused)]
+fn user_field_numbers() -> Vec<u32> {
+    vec![]
+}
+
 #[allow(unused)]
 fn register_user_script_apis(engine: &mut rhai::Engine) {
     // engine.register_global_module(exported_module!(<<namespace::api>>).into());